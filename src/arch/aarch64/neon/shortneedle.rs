@@ -0,0 +1,215 @@
+/*!
+A 128-bit vector implementation of substring search for very short needles.
+
+This compares shifted vector loads of the haystack with each byte of the
+needle directly, so that every position reported is a match. There is no
+verification step.
+*/
+
+use core::arch::aarch64::uint8x16_t;
+
+use crate::arch::generic::shortneedle;
+
+/// A finder for needles of exactly `N` bytes that uses 128-bit vector
+/// operations.
+///
+/// Only needles of `2`, `3` or `4` bytes are supported. Unlike the "packed
+/// pair" finder, haystacks of any length can be searched. Haystacks that are
+/// too short for a vector search are searched one position at a time.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(target_arch = "aarch64")] {
+/// use memchr::arch::aarch64::neon::shortneedle::Finder;
+///
+/// let finder = Finder::new(*b"::").unwrap();
+/// assert_eq!(Some(4), finder.find(b"core::arch::aarch64"));
+/// assert_eq!(Some(10), finder.rfind(b"core::arch::aarch64"));
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Finder<const N: usize> {
+    needle: [u8; N],
+    neon: shortneedle::Finder<uint8x16_t, N>,
+}
+
+impl<const N: usize> Finder<N> {
+    /// Create a new finder for the given needle.
+    ///
+    /// If neon is unavailable in the current environment or if `N` isn't
+    /// one of `2`, `3` or `4`, then `None` is returned.
+    #[inline]
+    pub fn new(needle: [u8; N]) -> Option<Finder<N>> {
+        if Finder::<N>::is_available() && (2..=4).contains(&N) {
+            // SAFETY: we check that neon is available above, and that the
+            // needle is not empty.
+            unsafe { Some(Finder::new_impl(needle)) }
+        } else {
+            None
+        }
+    }
+
+    /// Create a new `Finder` specific to neon vectors and routines.
+    ///
+    /// # Safety
+    ///
+    /// Same as the safety for `shortneedle::Finder::new`, and callers must
+    /// also ensure that neon is available.
+    #[target_feature(enable = "neon")]
+    #[inline]
+    unsafe fn new_impl(needle: [u8; N]) -> Finder<N> {
+        let neon = shortneedle::Finder::<uint8x16_t, N>::new(needle);
+        Finder { needle, neon }
+    }
+
+    /// Returns true when this implementation is available in the current
+    /// environment.
+    ///
+    /// When this is true, it is guaranteed that [`Finder::new`] will return
+    /// a `Some` value for needles of `2`, `3` or `4` bytes. Similarly, when
+    /// it is false, it is guaranteed that `Finder::new` will return a `None`
+    /// value.
+    ///
+    /// Note also that for the lifetime of a single program, if this returns
+    /// true then it will always return true.
+    #[inline]
+    pub fn is_available() -> bool {
+        #[cfg(target_feature = "neon")]
+        {
+            true
+        }
+        #[cfg(not(target_feature = "neon"))]
+        {
+            false
+        }
+    }
+
+    /// Returns the offset of the first occurrence of the needle in the given
+    /// haystack. If no such occurrence exists, then `None` is returned.
+    #[inline]
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: Building a `Finder` means it's safe to call 'neon' routines.
+        unsafe { self.find_impl(haystack) }
+    }
+
+    /// Returns the offset of the last occurrence of the needle in the given
+    /// haystack. If no such occurrence exists, then `None` is returned.
+    #[inline]
+    pub fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: Building a `Finder` means it's safe to call 'neon' routines.
+        unsafe { self.rfind_impl(haystack) }
+    }
+
+    /// Execute a forward search using neon vectors and routines.
+    ///
+    /// # Safety
+    ///
+    /// (The target feature safety obligation is automatically fulfilled by
+    /// virtue of being a method on `Finder`, which can only be constructed
+    /// when it is safe to call `neon` routines.)
+    #[target_feature(enable = "neon")]
+    #[inline]
+    unsafe fn find_impl(&self, haystack: &[u8]) -> Option<usize> {
+        if haystack.len() < Finder::<N>::min_haystack_len() {
+            shortneedle::find_scalar(&self.needle, haystack)
+        } else {
+            self.neon.find(haystack)
+        }
+    }
+
+    /// Execute a reverse search using neon vectors and routines.
+    ///
+    /// # Safety
+    ///
+    /// (The target feature safety obligation is automatically fulfilled by
+    /// virtue of being a method on `Finder`, which can only be constructed
+    /// when it is safe to call `neon` routines.)
+    #[target_feature(enable = "neon")]
+    #[inline]
+    unsafe fn rfind_impl(&self, haystack: &[u8]) -> Option<usize> {
+        if haystack.len() < Finder::<N>::min_haystack_len() {
+            shortneedle::rfind_scalar(&self.needle, haystack)
+        } else {
+            self.neon.rfind(haystack)
+        }
+    }
+
+    /// Returns the needle that this finder searches for.
+    #[inline]
+    pub fn needle(&self) -> &[u8; N] {
+        &self.needle
+    }
+
+    /// Returns the minimum haystack length that is searched with vector
+    /// operations. Shorter haystacks are searched one position at a time.
+    #[inline]
+    pub fn min_haystack_len() -> usize {
+        shortneedle::Finder::<uint8x16_t, N>::min_haystack_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<Option<usize>> {
+        let got = match needle.len() {
+            2 => Finder::<2>::new(needle.try_into().ok()?)?.find(haystack),
+            3 => Finder::<3>::new(needle.try_into().ok()?)?.find(haystack),
+            4 => Finder::<4>::new(needle.try_into().ok()?)?.find(haystack),
+            _ => return None,
+        };
+        Some(got)
+    }
+
+    fn rfind(haystack: &[u8], needle: &[u8]) -> Option<Option<usize>> {
+        let got = match needle.len() {
+            2 => Finder::<2>::new(needle.try_into().ok()?)?.rfind(haystack),
+            3 => Finder::<3>::new(needle.try_into().ok()?)?.rfind(haystack),
+            4 => Finder::<4>::new(needle.try_into().ok()?)?.rfind(haystack),
+            _ => return None,
+        };
+        Some(got)
+    }
+
+    define_substring_forward_quickcheck!(find);
+    define_substring_reverse_quickcheck!(rfind);
+
+    #[test]
+    fn forward_substring() {
+        crate::tests::substring::Runner::new().fwd(find).run()
+    }
+
+    #[test]
+    fn reverse_substring() {
+        crate::tests::substring::Runner::new().rev(rfind).run()
+    }
+
+    #[test]
+    fn every_position() {
+        let finder = match Finder::new(*b"::=") {
+            None => return,
+            Some(finder) => finder,
+        };
+        for len in 3..100 {
+            for i in 0..=len - 3 {
+                // Partial matches are everywhere, but the only match is
+                // at `i`.
+                let mut haystack = alloc::vec![b':'; len];
+                haystack[i + 2] = b'=';
+                assert_eq!(Some(i), finder.find(&haystack));
+                assert_eq!(Some(i), finder.rfind(&haystack));
+            }
+            assert_eq!(None, finder.find(&alloc::vec![b':'; len]));
+            assert_eq!(None, finder.rfind(&alloc::vec![b':'; len]));
+        }
+    }
+
+    #[test]
+    fn unsupported_lengths() {
+        assert!(Finder::<0>::new([]).is_none());
+        assert!(Finder::<1>::new([b'a']).is_none());
+        assert!(Finder::<5>::new(*b"abcde").is_none());
+    }
+}
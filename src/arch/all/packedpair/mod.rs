@@ -14,7 +14,7 @@ however can be good enough in many circumstances.
 
 use crate::memchr;
 
-mod default_rank;
+pub(crate) mod default_rank;
 
 /// An architecture independent "packed pair" finder.
 ///
@@ -42,8 +42,11 @@ impl Finder {
     /// Create a new prefilter that reports possible locations where the given
     /// needle matches.
     #[inline]
-    pub fn new(needle: &[u8]) -> Option<Finder> {
-        Finder::with_pair(needle, Pair::new(needle)?)
+    pub const fn new(needle: &[u8]) -> Option<Finder> {
+        match Pair::new(needle) {
+            None => None,
+            Some(pair) => Finder::with_pair(needle, pair),
+        }
     }
 
     /// Create a new prefilter using the pair given.
@@ -53,9 +56,9 @@ impl Finder {
     /// This constructor permits callers to control precisely which pair of
     /// bytes is used as a predicate.
     #[inline]
-    pub fn with_pair(needle: &[u8], pair: Pair) -> Option<Finder> {
-        let byte1 = needle[usize::from(pair.index1())];
-        let byte2 = needle[usize::from(pair.index2())];
+    pub const fn with_pair(needle: &[u8], pair: Pair) -> Option<Finder> {
+        let byte1 = needle[pair.index1 as usize];
+        let byte2 = needle[pair.index2 as usize];
         // Currently this can never fail so we could just return a Finder,
         // but it's conceivable this could change.
         Some(Finder { pair, byte1, byte2 })
@@ -131,7 +134,7 @@ impl Finder {
 /// requirement that the indices be both valid for the needle used to build
 /// the pair and not equal, it follows that a pair can only be constructed for
 /// needles with length at least 2.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Pair {
     index1: u8,
     index2: u8,
@@ -145,9 +148,44 @@ impl Pair {
     ///
     /// This chooses the pair in the needle that is believed to be as
     /// predictive of an overall match of the needle as possible.
+    ///
+    /// This is equivalent to [`Pair::with_ranker`] with the default
+    /// frequency ranking, except that it can be called in a `const`
+    /// context.
     #[inline]
-    pub fn new(needle: &[u8]) -> Option<Pair> {
-        Pair::with_ranker(needle, DefaultFrequencyRank)
+    pub const fn new(needle: &[u8]) -> Option<Pair> {
+        // This mirrors `with_ranker`, but is written with `while` loops and
+        // direct lookups into the rank table so that it is a `const fn`.
+        let rank = &self::default_rank::RANK;
+        if needle.len() <= 1 {
+            return None;
+        }
+        let (mut rare1, mut index1) = (needle[0], 0);
+        let (mut rare2, mut index2) = (needle[1], 1);
+        if rank[rare2 as usize] < rank[rare1 as usize] {
+            let (b, i) = (rare1, index1);
+            rare1 = rare2;
+            index1 = index2;
+            rare2 = b;
+            index2 = i;
+        }
+        let max = if needle.len() < 255 { needle.len() } else { 255 };
+        let mut i = 2;
+        while i < max {
+            let b = needle[i];
+            if rank[b as usize] < rank[rare1 as usize] {
+                rare2 = rare1;
+                index2 = index1;
+                rare1 = b;
+                index1 = i as u8;
+            } else if b != rare1 && rank[b as usize] < rank[rare2 as usize] {
+                rare2 = b;
+                index2 = i as u8;
+            }
+            i += 1;
+        }
+        assert!(index1 != index2);
+        Some(Pair { index1, index2 })
     }
 
     /// Create a new pair of offsets from the given needle and ranker.
@@ -235,15 +273,151 @@ impl Pair {
 
     /// Returns the first offset of the pair.
     #[inline]
-    pub fn index1(&self) -> u8 {
+    pub const fn index1(&self) -> u8 {
         self.index1
     }
 
     /// Returns the second offset of the pair.
     #[inline]
-    pub fn index2(&self) -> u8 {
+    pub const fn index2(&self) -> u8 {
+        self.index2
+    }
+}
+
+/// A triple of byte offsets into a needle to use as a predicate.
+///
+/// This is like a [`Pair`], but with a third offset. It is meant for data in
+/// which even the rarest pair of bytes in a needle occurs very frequently.
+/// For example, base64, hex dumps or DNA sequences. Requiring a third byte to
+/// match makes candidates much rarer in such data, at the cost of one more
+/// comparison for every position in the haystack.
+///
+/// Like a `Pair`, the offsets are limited each to a maximum of 255 and no two
+/// of them are equivalent. By convention, `index1` corresponds to the byte in
+/// the needle that is believed to be the most predictive, followed by
+/// `index2` and then `index3`. It follows that a triple can only be
+/// constructed for needles with length at least 3.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Triple {
+    index1: u8,
+    index2: u8,
+    index3: u8,
+}
+
+impl Triple {
+    /// Create a new triple of offsets from the given needle.
+    ///
+    /// If a triple could not be created (for example, if the needle is too
+    /// short), then `None` is returned.
+    ///
+    /// This is equivalent to [`Triple::with_ranker`] with the default
+    /// frequency ranking.
+    #[inline]
+    pub fn new(needle: &[u8]) -> Option<Triple> {
+        Triple::with_ranker(needle, DefaultFrequencyRank)
+    }
+
+    /// Create a new triple of offsets from the given needle and ranker.
+    ///
+    /// The first two offsets are always the same as the offsets chosen by
+    /// [`Pair::with_ranker`] for the same needle and ranker. The third offset
+    /// is the next rarest byte in the needle, preferring a byte whose value
+    /// differs from the bytes at the first two offsets.
+    ///
+    /// If a triple could not be created (for example, if the needle is too
+    /// short), then `None` is returned.
+    #[inline]
+    pub fn with_ranker<R: HeuristicFrequencyRank>(
+        needle: &[u8],
+        ranker: R,
+    ) -> Option<Triple> {
+        if needle.len() <= 2 {
+            return None;
+        }
+        // This is the same as the search done by `Pair::with_ranker`, except
+        // that bytes displaced from the first two slots, or not good enough
+        // for them, are considered for a third.
+        let (mut rare1, mut index1) = (needle[0], 0);
+        let (mut rare2, mut index2) = (needle[1], 1);
+        if ranker.rank(rare2) < ranker.rank(rare1) {
+            core::mem::swap(&mut rare1, &mut rare2);
+            core::mem::swap(&mut index1, &mut index2);
+        }
+        let (mut rare3, mut index3) = (needle[2], 2);
+        let max = usize::from(u8::MAX);
+        for (i, &b) in needle.iter().enumerate().take(max).skip(2) {
+            let i = u8::try_from(i).unwrap();
+            if ranker.rank(b) < ranker.rank(rare1) {
+                (rare3, index3) = (rare2, index2);
+                (rare2, index2) = (rare1, index1);
+                (rare1, index1) = (b, i);
+            } else if b != rare1 && ranker.rank(b) < ranker.rank(rare2) {
+                (rare3, index3) = (rare2, index2);
+                (rare2, index2) = (b, i);
+            } else if i == 2
+                || (b != rare1
+                    && b != rare2
+                    && (rare3 == rare1
+                        || rare3 == rare2
+                        || ranker.rank(b) < ranker.rank(rare3)))
+            {
+                // A third byte equal to one of the first two adds little, so
+                // any other byte value is preferred over it.
+                (rare3, index3) = (b, i);
+            }
+        }
+        assert!(index1 != index2 && index1 != index3 && index2 != index3);
+        Some(Triple { index1, index2, index3 })
+    }
+
+    /// Create a new triple using the offsets given for the needle given.
+    ///
+    /// This bypasses any sort of heuristic process for choosing the offsets
+    /// and permits the caller to choose the offsets themselves.
+    ///
+    /// If a triple could not be created (for example, if any two offsets are
+    /// equivalent or if any offset is out of bounds for the needle), then
+    /// `None` is returned.
+    #[inline]
+    pub fn with_indices(
+        needle: &[u8],
+        index1: u8,
+        index2: u8,
+        index3: u8,
+    ) -> Option<Triple> {
+        if index1 == index2 || index1 == index3 || index2 == index3 {
+            return None;
+        }
+        let max = index1.max(index2).max(index3);
+        if usize::from(max) >= needle.len() {
+            return None;
+        }
+        Some(Triple { index1, index2, index3 })
+    }
+
+    /// Returns the first offset of the triple.
+    #[inline]
+    pub const fn index1(&self) -> u8 {
+        self.index1
+    }
+
+    /// Returns the second offset of the triple.
+    #[inline]
+    pub const fn index2(&self) -> u8 {
         self.index2
     }
+
+    /// Returns the third offset of the triple.
+    #[inline]
+    pub const fn index3(&self) -> u8 {
+        self.index3
+    }
+
+    /// Returns the pair made up of the first two offsets of this triple.
+    #[inline]
+    pub const fn pair(&self) -> Pair {
+        Pair { index1: self.index1, index2: self.index2 }
+    }
 }
 
 /// This trait allows the user to customize the heuristic used to determine the
@@ -356,4 +530,62 @@ mod tests {
         }
         crate::tests::packedpair::Runner::new().fwd(find).run()
     }
+
+    #[test]
+    fn const_pair_same_as_ranked() {
+        let long: alloc::vec::Vec<u8> = (0..=255).cycle().take(600).collect();
+        let needles: &[&[u8]] = &[
+            b"",
+            b"a",
+            b"ab",
+            b"ba",
+            b"aa",
+            b"zq",
+            b"foobar",
+            b"Sherlock Holmes",
+            b"\x00\x00\xFF\xFF",
+            &[b'z'; 300],
+            &long,
+        ];
+        for &needle in needles {
+            let got = Pair::new(needle).map(|p| (p.index1(), p.index2()));
+            let expected = Pair::with_ranker(needle, DefaultFrequencyRank)
+                .map(|p| (p.index1(), p.index2()));
+            assert_eq!(expected, got, "needle: {:?}", needle);
+        }
+    }
+
+    #[test]
+    fn triple_extends_pair() {
+        let long: alloc::vec::Vec<u8> = (0..=255).cycle().take(600).collect();
+        let needles: &[&[u8]] = &[
+            b"abc",
+            b"aaa",
+            b"zqz",
+            b"foobar",
+            b"Sherlock Holmes",
+            b"GATTACAGATTACA",
+            b"\x00\x00\xFF\xFF",
+            &[b'z'; 300],
+            &long,
+        ];
+        for &needle in needles {
+            let triple = Triple::new(needle).unwrap();
+            assert_eq!(Pair::new(needle), Some(triple.pair()));
+            let (i1, i2, i3) =
+                (triple.index1(), triple.index2(), triple.index3());
+            assert_eq!(Triple::with_indices(needle, i1, i2, i3), Some(triple));
+        }
+        // The third byte prefers a value that differs from the first two.
+        let triple = Triple::new(b"GGGGTTTTA").unwrap();
+        assert_eq!(
+            (0, 1, 4),
+            (triple.index1(), triple.index2(), triple.index3())
+        );
+
+        assert_eq!(None, Triple::new(b""));
+        assert_eq!(None, Triple::new(b"ab"));
+        assert_eq!(None, Triple::with_indices(b"abc", 0, 1, 1));
+        assert_eq!(None, Triple::with_indices(b"abc", 0, 1, 3));
+    }
 }
@@ -84,20 +84,27 @@ impl Finder {
     /// Note that callers must pass the same needle to all search calls using
     /// this `Finder`.
     #[inline]
-    pub fn new(needle: &[u8]) -> Finder {
+    pub const fn new(needle: &[u8]) -> Finder {
         let mut s = Finder { hash: Hash::new(), hash_2pow: 1 };
-        let first_byte = match needle.first() {
-            None => return s,
-            Some(&first_byte) => first_byte,
-        };
-        s.hash.add(first_byte);
-        for b in needle.iter().copied().skip(1) {
-            s.hash.add(b);
+        if needle.is_empty() {
+            return s;
+        }
+        s.hash = s.hash.with(needle[0]);
+        let mut i = 1;
+        while i < needle.len() {
+            s.hash = s.hash.with(needle[i]);
             s.hash_2pow = s.hash_2pow.wrapping_shl(1);
+            i += 1;
         }
         s
     }
 
+    /// Returns the hash of the needle along with the factor used to remove a
+    /// byte from a rolling hash. This is used to serialize searchers.
+    pub(crate) fn hash_parts(&self) -> (u32, u32) {
+        (self.hash.0, self.hash_2pow)
+    }
+
     /// Return the first occurrence of the `needle` in the `haystack`
     /// given. If no such occurrence exists, then `None` is returned.
     ///
@@ -196,6 +203,12 @@ impl FinderRev {
         s
     }
 
+    /// Returns the hash of the needle along with the factor used to remove a
+    /// byte from a rolling hash. This is used to serialize searchers.
+    pub(crate) fn hash_parts(&self) -> (u32, u32) {
+        self.0.hash_parts()
+    }
+
     /// Return the last occurrence of the `needle` in the `haystack`
     /// given. If no such occurrence exists, then `None` is returned.
     ///
@@ -287,7 +300,7 @@ struct Hash(u32);
 impl Hash {
     /// Create a new hash that represents the empty string.
     #[inline(always)]
-    fn new() -> Hash {
+    const fn new() -> Hash {
         Hash(0)
     }
 
@@ -335,7 +348,15 @@ impl Hash {
     /// Add a byte to this hash.
     #[inline(always)]
     fn add(&mut self, byte: u8) {
-        self.0 = self.0.wrapping_shl(1).wrapping_add(u32::from(byte));
+        *self = self.with(byte);
+    }
+
+    /// Return this hash with the given byte added to it.
+    ///
+    /// This is like `add`, but can be used in a `const fn`.
+    #[inline(always)]
+    const fn with(self, byte: u8) -> Hash {
+        Hash(self.0.wrapping_shl(1).wrapping_add(byte as u32))
     }
 
     /// Remove a byte from this hash. The given needle hash should correspond
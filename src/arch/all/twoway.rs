@@ -27,10 +27,7 @@ Two-Way can be found in the `memmem` implementations in at least [GNU libc] and
 
 use core::cmp;
 
-use crate::{
-    arch::all::{is_prefix, is_suffix},
-    memmem::Pre,
-};
+use crate::memmem::Pre;
 
 /// A forward substring searcher that uses the Two-Way algorithm.
 #[derive(Clone, Copy, Debug)]
@@ -111,7 +108,7 @@ impl Finder {
     /// An empty `needle` results in a match at every position in a haystack,
     /// including at `haystack.len()`.
     #[inline]
-    pub fn new(needle: &[u8]) -> Finder {
+    pub const fn new(needle: &[u8]) -> Finder {
         let byteset = ApproximateByteSet::new(needle);
         let min_suffix = Suffix::forward(needle, SuffixKind::Minimal);
         let max_suffix = Suffix::forward(needle, SuffixKind::Maximal);
@@ -138,6 +135,27 @@ impl Finder {
         self.find_with_prefilter(None, haystack, needle)
     }
 
+    /// This is like [`Finder::find`], but it can be called in a `const`
+    /// context.
+    ///
+    /// This uses a separate implementation of the search loop, which is
+    /// checked against the one used by `Finder::find` in tests.
+    #[inline]
+    pub(crate) const fn find_const(
+        &self,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        match self.0.shift {
+            Shift::Small { period } => {
+                self.find_small_const(haystack, needle, period)
+            }
+            Shift::Large { shift } => {
+                self.find_large_const(haystack, needle, shift)
+            }
+        }
+    }
+
     /// This is like [`Finder::find`], but it accepts a prefilter for
     /// accelerating searches.
     ///
@@ -164,6 +182,90 @@ impl Finder {
         }
     }
 
+    // The two search implementations below are the same as `find_small_imp`
+    // and `find_large_imp` without a prefilter, but are written such that
+    // they can be called in a `const` context. Namely, a prefilter requires
+    // mutable state, which isn't permitted in a `const fn` on our MSRV.
+
+    #[inline(always)]
+    const fn find_small_const(
+        &self,
+        haystack: &[u8],
+        needle: &[u8],
+        period: usize,
+    ) -> Option<usize> {
+        let mut pos = 0;
+        let mut shift = 0;
+        let last_byte_pos = match needle.len().checked_sub(1) {
+            None => return Some(pos),
+            Some(last_byte) => last_byte,
+        };
+        while pos + needle.len() <= haystack.len() {
+            let mut i = max(self.0.critical_pos, shift);
+            if !self.0.byteset.contains(haystack[pos + last_byte_pos]) {
+                pos += needle.len();
+                shift = 0;
+                continue;
+            }
+            while i < needle.len() && needle[i] == haystack[pos + i] {
+                i += 1;
+            }
+            if i < needle.len() {
+                pos += i - self.0.critical_pos + 1;
+                shift = 0;
+            } else {
+                let mut j = self.0.critical_pos;
+                while j > shift && needle[j] == haystack[pos + j] {
+                    j -= 1;
+                }
+                if j <= shift && needle[shift] == haystack[pos + shift] {
+                    return Some(pos);
+                }
+                pos += period;
+                shift = needle.len() - period;
+            }
+        }
+        None
+    }
+
+    #[inline(always)]
+    const fn find_large_const(
+        &self,
+        haystack: &[u8],
+        needle: &[u8],
+        shift: usize,
+    ) -> Option<usize> {
+        let mut pos = 0;
+        let last_byte_pos = match needle.len().checked_sub(1) {
+            None => return Some(pos),
+            Some(last_byte) => last_byte,
+        };
+        'outer: while pos + needle.len() <= haystack.len() {
+            if !self.0.byteset.contains(haystack[pos + last_byte_pos]) {
+                pos += needle.len();
+                continue;
+            }
+            let mut i = self.0.critical_pos;
+            while i < needle.len() && needle[i] == haystack[pos + i] {
+                i += 1;
+            }
+            if i < needle.len() {
+                pos += i - self.0.critical_pos + 1;
+            } else {
+                let mut j = self.0.critical_pos;
+                while j > 0 {
+                    j -= 1;
+                    if needle[j] != haystack[pos + j] {
+                        pos += shift;
+                        continue 'outer;
+                    }
+                }
+                return Some(pos);
+            }
+        }
+        None
+    }
+
     // Each of the two search implementations below can be accelerated by a
     // prefilter, but it is not always enabled. To avoid its overhead when
     // its disabled, we explicitly inline each search implementation based on
@@ -201,9 +303,13 @@ impl Finder {
                 shift = 0;
                 continue;
             }
+            let start = i;
             while i < needle.len() && needle[i] == haystack[pos + i] {
                 i += 1;
             }
+            if let Some(pre) = pre.as_mut() {
+                pre.verified(i - start + usize::from(i < needle.len()));
+            }
             if i < needle.len() {
                 pos += i - self.0.critical_pos + 1;
                 shift = 0;
@@ -212,6 +318,9 @@ impl Finder {
                 while j > shift && needle[j] == haystack[pos + j] {
                     j -= 1;
                 }
+                if let Some(pre) = pre.as_mut() {
+                    pre.verified(self.0.critical_pos - j + 1);
+                }
                 if j <= shift && needle[shift] == haystack[pos + shift] {
                     return Some(pos);
                 }
@@ -253,20 +362,53 @@ impl Finder {
             while i < needle.len() && needle[i] == haystack[pos + i] {
                 i += 1;
             }
+            if let Some(pre) = pre.as_mut() {
+                let len = i - self.0.critical_pos;
+                pre.verified(len + usize::from(i < needle.len()));
+            }
             if i < needle.len() {
                 pos += i - self.0.critical_pos + 1;
             } else {
                 for j in (0..self.0.critical_pos).rev() {
                     if needle[j] != haystack[pos + j] {
+                        if let Some(pre) = pre.as_mut() {
+                            pre.verified(self.0.critical_pos - j);
+                        }
                         pos += shift;
                         continue 'outer;
                     }
                 }
+                if let Some(pre) = pre.as_mut() {
+                    pre.verified(self.0.critical_pos);
+                }
                 return Some(pos);
             }
         }
         None
     }
+
+    /// Returns the critical factorization computed for the needle.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn factorization(&self) -> Factorization {
+        self.0.factorization()
+    }
+
+    /// Create a searcher from a critical factorization previously returned
+    /// by [`Finder::factorization`] for the same needle.
+    ///
+    /// This returns `None` if the factorization given could cause a search
+    /// to panic or fail to terminate. However, it is not checked that the
+    /// factorization is actually the critical factorization of the needle.
+    /// If it isn't, then searches may report incorrect results.
+    pub(crate) fn from_factorization(
+        needle: &[u8],
+        factorization: Factorization,
+    ) -> Option<Finder> {
+        if factorization.critical_pos >= needle.len() {
+            return None;
+        }
+        TwoWay::from_factorization(needle, factorization).map(Finder)
+    }
 }
 
 impl FinderRev {
@@ -275,7 +417,7 @@ impl FinderRev {
     /// An empty `needle` results in a match at every position in a haystack,
     /// including at `haystack.len()`.
     #[inline]
-    pub fn new(needle: &[u8]) -> FinderRev {
+    pub const fn new(needle: &[u8]) -> FinderRev {
         let byteset = ApproximateByteSet::new(needle);
         let min_suffix = Suffix::reverse(needle, SuffixKind::Minimal);
         let max_suffix = Suffix::reverse(needle, SuffixKind::Maximal);
@@ -297,8 +439,14 @@ impl FinderRev {
     ///
     /// An empty `needle` results in a match at every position in a haystack,
     /// including at `haystack.len()`.
+    ///
+    /// This routine can be called in a `const` context.
     #[inline]
-    pub fn rfind(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    pub const fn rfind(
+        &self,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
         // For the reverse case, we don't use a prefilter. It's plausible that
         // perhaps we should, but it's a lot of additional code to do it, and
         // it's not clear that it's actually worth it. If you have a really
@@ -314,7 +462,7 @@ impl FinderRev {
     }
 
     #[inline(always)]
-    fn rfind_small_imp(
+    const fn rfind_small_imp(
         &self,
         haystack: &[u8],
         needle: &[u8],
@@ -333,7 +481,7 @@ impl FinderRev {
                 shift = nlen;
                 continue;
             }
-            let mut i = cmp::min(self.0.critical_pos, shift);
+            let mut i = min(self.0.critical_pos, shift);
             while i > 0 && needle[i - 1] == haystack[pos - nlen + i - 1] {
                 i -= 1;
             }
@@ -356,7 +504,7 @@ impl FinderRev {
     }
 
     #[inline(always)]
-    fn rfind_large_imp(
+    const fn rfind_large_imp(
         &self,
         haystack: &[u8],
         needle: &[u8],
@@ -392,6 +540,76 @@ impl FinderRev {
         }
         None
     }
+
+    /// Returns the critical factorization computed for the needle.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn factorization(&self) -> Factorization {
+        self.0.factorization()
+    }
+
+    /// Create a searcher from a critical factorization previously returned
+    /// by [`FinderRev::factorization`] for the same needle.
+    ///
+    /// This has the same caveats as [`Finder::from_factorization`].
+    pub(crate) fn from_factorization(
+        needle: &[u8],
+        factorization: Factorization,
+    ) -> Option<FinderRev> {
+        if factorization.critical_pos > needle.len() {
+            return None;
+        }
+        TwoWay::from_factorization(needle, factorization).map(FinderRev)
+    }
+}
+
+impl TwoWay {
+    #[cfg(feature = "alloc")]
+    fn factorization(&self) -> Factorization {
+        let (small_period, shift) = match self.shift {
+            Shift::Small { period } => (true, period),
+            Shift::Large { shift } => (false, shift),
+        };
+        Factorization { critical_pos: self.critical_pos, small_period, shift }
+    }
+
+    /// Build a Two-Way searcher from its critical factorization. Callers
+    /// must check that the critical position is valid for the direction of
+    /// the search.
+    fn from_factorization(
+        needle: &[u8],
+        factorization: Factorization,
+    ) -> Option<TwoWay> {
+        let Factorization { critical_pos, small_period, shift } =
+            factorization;
+        // Every shift must make progress and must never move a candidate
+        // further than the length of the needle.
+        if needle.len() < 2 || shift == 0 || shift > needle.len() {
+            return None;
+        }
+        let shift = if small_period {
+            Shift::Small { period: shift }
+        } else {
+            Shift::Large { shift }
+        };
+        let byteset = ApproximateByteSet::new(needle);
+        Some(TwoWay { byteset, critical_pos, shift })
+    }
+}
+
+/// The critical factorization of a needle computed by the Two-Way
+/// algorithm, along with the amount to shift by during a search.
+///
+/// This is everything about a Two-Way searcher that is expensive to compute.
+/// It is exposed inside the crate so that searchers can be serialized.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Factorization {
+    /// The critical position in the needle.
+    pub(crate) critical_pos: usize,
+    /// Whether the period of the needle is known exactly. When true, `shift`
+    /// is the period of the needle.
+    pub(crate) small_period: bool,
+    /// The amount to shift by when a candidate fails to match.
+    pub(crate) shift: usize,
 }
 
 /// A representation of the amount we're allowed to shift by during Two-Way
@@ -437,18 +655,22 @@ impl Shift {
     /// These can be computed by extracting both the minimal and maximal
     /// lexicographic suffixes, and choosing the right-most starting position.
     /// The lower bound on the period is then the period of the chosen suffix.
-    fn forward(
+    const fn forward(
         needle: &[u8],
         period_lower_bound: usize,
         critical_pos: usize,
     ) -> Shift {
-        let large = cmp::max(critical_pos, needle.len() - critical_pos);
+        let large = max(critical_pos, needle.len() - critical_pos);
         if critical_pos * 2 >= needle.len() {
             return Shift::Large { shift: large };
         }
 
-        let (u, v) = needle.split_at(critical_pos);
-        if !is_suffix(&v[..period_lower_bound], u) {
+        // This is `is_suffix(&v[..period_lower_bound], u)`, where
+        // `(u, v) = needle.split_at(critical_pos)`, written without slicing
+        // so that it can be used in a `const fn`.
+        let suffix = critical_pos <= period_lower_bound
+            && is_equal_at(needle, period_lower_bound, 0, critical_pos);
+        if !suffix {
             return Shift::Large { shift: large };
         }
         Shift::Small { period: period_lower_bound }
@@ -460,18 +682,24 @@ impl Shift {
     /// These can be computed by extracting both the minimal and maximal
     /// lexicographic suffixes, and choosing the left-most starting position.
     /// The lower bound on the period is then the period of the chosen suffix.
-    fn reverse(
+    const fn reverse(
         needle: &[u8],
         period_lower_bound: usize,
         critical_pos: usize,
     ) -> Shift {
-        let large = cmp::max(critical_pos, needle.len() - critical_pos);
+        let large = max(critical_pos, needle.len() - critical_pos);
         if (needle.len() - critical_pos) * 2 >= needle.len() {
             return Shift::Large { shift: large };
         }
 
-        let (v, u) = needle.split_at(critical_pos);
-        if !is_prefix(&v[v.len() - period_lower_bound..], u) {
+        // This is `is_prefix(&v[v.len() - period_lower_bound..], u)`, where
+        // `(v, u) = needle.split_at(critical_pos)`, written without slicing
+        // so that it can be used in a `const fn`.
+        let (start, len) =
+            (critical_pos - period_lower_bound, needle.len() - critical_pos);
+        let prefix = len <= period_lower_bound
+            && is_equal_at(needle, start, critical_pos, len);
+        if !prefix {
             return Shift::Large { shift: large };
         }
         Shift::Small { period: period_lower_bound }
@@ -497,7 +725,7 @@ struct Suffix {
 }
 
 impl Suffix {
-    fn forward(needle: &[u8], kind: SuffixKind) -> Suffix {
+    const fn forward(needle: &[u8], kind: SuffixKind) -> Suffix {
         // suffix represents our maximal (or minimal) suffix, along with
         // its period.
         let mut suffix = Suffix { pos: 0, period: 1 };
@@ -545,7 +773,7 @@ impl Suffix {
         suffix
     }
 
-    fn reverse(needle: &[u8], kind: SuffixKind) -> Suffix {
+    const fn reverse(needle: &[u8], kind: SuffixKind) -> Suffix {
         // See the comments in `forward` for how this works.
         let mut suffix = Suffix { pos: needle.len(), period: 1 };
         if needle.len() == 1 {
@@ -627,7 +855,7 @@ impl SuffixKind {
     /// Returns true if and only if the given candidate byte indicates that
     /// it should replace the current suffix as the maximal (or minimal)
     /// suffix.
-    fn cmp(self, current: u8, candidate: u8) -> SuffixOrdering {
+    const fn cmp(self, current: u8, candidate: u8) -> SuffixOrdering {
         use self::SuffixOrdering::*;
 
         match self {
@@ -641,6 +869,38 @@ impl SuffixKind {
     }
 }
 
+/// Returns true if and only if `needle[i..i + len] == needle[j..j + len]`.
+const fn is_equal_at(needle: &[u8], i: usize, j: usize, len: usize) -> bool {
+    let mut k = 0;
+    while k < len {
+        if needle[i + k] != needle[j + k] {
+            return false;
+        }
+        k += 1;
+    }
+    true
+}
+
+/// Returns the smaller of the two values given. (`core::cmp::min` can't be
+/// used in a `const fn`.)
+const fn min(a: usize, b: usize) -> usize {
+    if a <= b {
+        a
+    } else {
+        b
+    }
+}
+
+/// Returns the larger of the two values given. (`core::cmp::max` can't be
+/// used in a `const fn`.)
+const fn max(a: usize, b: usize) -> usize {
+    if a >= b {
+        a
+    } else {
+        b
+    }
+}
+
 /// A bitset used to track whether a particular byte exists in a needle or not.
 ///
 /// Namely, bit 'i' is set if and only if byte%64==i for any byte in the
@@ -652,10 +912,12 @@ struct ApproximateByteSet(u64);
 
 impl ApproximateByteSet {
     /// Create a new set from the given needle.
-    fn new(needle: &[u8]) -> ApproximateByteSet {
+    const fn new(needle: &[u8]) -> ApproximateByteSet {
         let mut bits = 0;
-        for &b in needle {
-            bits |= 1 << (b % 64);
+        let mut i = 0;
+        while i < needle.len() {
+            bits |= 1 << (needle[i] % 64);
+            i += 1;
         }
         ApproximateByteSet(bits)
     }
@@ -663,7 +925,7 @@ impl ApproximateByteSet {
     /// Return true if and only if the given byte might be in this set. This
     /// may return a false positive, but will never return a false negative.
     #[inline(always)]
-    fn contains(&self, byte: u8) -> bool {
+    const fn contains(&self, byte: u8) -> bool {
         self.0 & (1 << (byte % 64)) != 0
     }
 }
@@ -715,6 +977,23 @@ mod tests {
         FinderRev::new(n).rfind(h, n)
     ));
 
+    #[cfg(not(miri))]
+    quickcheck::quickcheck! {
+        fn qc_fwd_const_matches_find(
+            haystack: Vec<u8>,
+            start: usize,
+            len: usize
+        ) -> bool {
+            // Take the needle from the haystack so that matches are likely.
+            let start = start.checked_rem(haystack.len()).unwrap_or(0);
+            let end = start + len.checked_rem(8).unwrap_or(0);
+            let needle = &haystack[start..end.min(haystack.len())];
+            let finder = Finder::new(needle);
+            finder.find(&haystack, needle)
+                == finder.find_const(&haystack, needle)
+        }
+    }
+
     #[test]
     fn forward() {
         crate::tests::substring::Runner::new()
@@ -722,6 +1001,18 @@ mod tests {
             .run();
     }
 
+    #[test]
+    fn forward_const() {
+        crate::tests::substring::Runner::new()
+            .fwd(|h, n| {
+                let finder = Finder::new(n);
+                let got = finder.find_const(h, n);
+                assert_eq!(finder.find(h, n), got);
+                Some(got)
+            })
+            .run();
+    }
+
     #[test]
     fn reverse() {
         crate::tests::substring::Runner::new()
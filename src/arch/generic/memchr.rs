@@ -145,88 +145,7 @@ impl<V: Vector> One<V> {
         start: *const u8,
         end: *const u8,
     ) -> Option<*const u8> {
-        // If we want to support vectors bigger than 256 bits, we probably
-        // need to move up to using a u64 for the masks used below. Currently
-        // they are 32 bits, which means we're SOL for vectors that need masks
-        // bigger than 32 bits. Overall unclear until there's a use case.
-        debug_assert!(V::BYTES <= 32, "vector cannot be bigger than 32 bytes");
-
-        let topos = V::Mask::first_offset;
-        let len = end.distance(start);
-        debug_assert!(
-            len >= V::BYTES,
-            "haystack has length {}, but must be at least {}",
-            len,
-            V::BYTES
-        );
-
-        // Search a possibly unaligned chunk at `start`. This covers any part
-        // of the haystack prior to where aligned loads can start.
-        if let Some(cur) = self.search_chunk(start, topos) {
-            return Some(cur);
-        }
-        // Set `cur` to the first V-aligned pointer greater than `start`.
-        let mut cur = start.add(V::BYTES - (start.as_usize() & V::ALIGN));
-        debug_assert!(cur > start && end.sub(V::BYTES) >= start);
-        if len >= Self::LOOP_SIZE {
-            while cur <= end.sub(Self::LOOP_SIZE) {
-                debug_assert_eq!(0, cur.as_usize() % V::BYTES);
-
-                let a = V::load_aligned(cur);
-                let b = V::load_aligned(cur.add(1 * V::BYTES));
-                let c = V::load_aligned(cur.add(2 * V::BYTES));
-                let d = V::load_aligned(cur.add(3 * V::BYTES));
-                let eqa = self.v1.cmpeq(a);
-                let eqb = self.v1.cmpeq(b);
-                let eqc = self.v1.cmpeq(c);
-                let eqd = self.v1.cmpeq(d);
-                let or1 = eqa.or(eqb);
-                let or2 = eqc.or(eqd);
-                let or3 = or1.or(or2);
-                if or3.movemask_will_have_non_zero() {
-                    let mask = eqa.movemask();
-                    if mask.has_non_zero() {
-                        return Some(cur.add(topos(mask)));
-                    }
-
-                    let mask = eqb.movemask();
-                    if mask.has_non_zero() {
-                        return Some(cur.add(1 * V::BYTES).add(topos(mask)));
-                    }
-
-                    let mask = eqc.movemask();
-                    if mask.has_non_zero() {
-                        return Some(cur.add(2 * V::BYTES).add(topos(mask)));
-                    }
-
-                    let mask = eqd.movemask();
-                    debug_assert!(mask.has_non_zero());
-                    return Some(cur.add(3 * V::BYTES).add(topos(mask)));
-                }
-                cur = cur.add(Self::LOOP_SIZE);
-            }
-        }
-        // Handle any leftovers after the aligned loop above. We use unaligned
-        // loads here, but I believe we are guaranteed that they are aligned
-        // since `cur` is aligned.
-        while cur <= end.sub(V::BYTES) {
-            debug_assert!(end.distance(cur) >= V::BYTES);
-            if let Some(cur) = self.search_chunk(cur, topos) {
-                return Some(cur);
-            }
-            cur = cur.add(V::BYTES);
-        }
-        // Finally handle any remaining bytes less than the size of V. In this
-        // case, our pointer may indeed be unaligned and the load may overlap
-        // with the previous one. But that's okay since we know the previous
-        // load didn't lead to a match (otherwise we wouldn't be here).
-        if cur < end {
-            debug_assert!(end.distance(cur) < V::BYTES);
-            cur = cur.sub(V::BYTES - end.distance(cur));
-            debug_assert_eq!(end.distance(cur), V::BYTES);
-            return self.search_chunk(cur, topos);
-        }
-        None
+        find_raw_with(start, end, |chunk| self.v1.cmpeq(chunk))
     }
 
     /// Return a pointer to the last occurrence of the needle in the given
@@ -1141,6 +1060,148 @@ pub(crate) unsafe fn search_slice_with_raw(
     Some(idx)
 }
 
+/// Return a pointer to the first position in the given haystack at which
+/// `matches` reports a match. If no such position exists, then `None` is
+/// returned.
+///
+/// `matches` is given a chunk of the haystack and should return a vector
+/// whose lanes have their most significant bit set if and only if the
+/// corresponding byte in the chunk matches. This is the aligned and unrolled
+/// search loop used by `One::find_raw`, but for an arbitrary predicate.
+///
+/// When a match is found, the pointer returned is `>= start` and `< end`
+/// as long as `matches` and the `MoveMask` implementation for `V` are
+/// correct. Otherwise, the pointer returned may be out of bounds, but it is
+/// never dereferenced.
+///
+/// # Safety
+///
+/// * `V::BYTES` must be a power of two.
+/// * It must be the case that `start < end` and that the distance between
+///   them is at least equal to `V::BYTES`. That is, it must always be valid
+///   to do at least an unaligned load of `V` at `start`.
+/// * Both `start` and `end` must be valid for reads.
+/// * Both `start` and `end` must point to an initialized value.
+/// * Both `start` and `end` must point to the same allocated object and
+///   must either be in bounds or at most one byte past the end of the
+///   allocated object.
+/// * Both `start` and `end` must be _derived from_ a pointer to the same
+///   object.
+/// * The distance between `start` and `end` must not overflow `isize`.
+/// * The distance being in bounds must not rely on "wrapping around" the
+///   address space.
+#[inline(always)]
+pub(crate) unsafe fn find_raw_with<V: Vector>(
+    start: *const u8,
+    end: *const u8,
+    matches: impl Fn(V) -> V,
+) -> Option<*const u8> {
+    debug_assert!(V::BYTES.is_power_of_two());
+
+    let loop_size = 4 * V::BYTES;
+    let topos = V::Mask::first_offset;
+    let len = end.distance(start);
+    debug_assert!(
+        len >= V::BYTES,
+        "haystack has length {}, but must be at least {}",
+        len,
+        V::BYTES
+    );
+
+    // Search a possibly unaligned chunk at `start`. This covers any part
+    // of the haystack prior to where aligned loads can start.
+    if let Some(cur) = search_chunk_with(start, &matches) {
+        return Some(cur);
+    }
+    // Set `cur` to the first V-aligned pointer greater than `start`.
+    let mut cur = start.add(V::BYTES - (start.as_usize() & V::ALIGN));
+    debug_assert!(cur > start && end.sub(V::BYTES) >= start);
+    if len >= loop_size {
+        while cur <= end.sub(loop_size) {
+            debug_assert_eq!(0, cur.as_usize() % V::BYTES);
+
+            let a = V::load_aligned(cur);
+            let b = V::load_aligned(cur.add(1 * V::BYTES));
+            let c = V::load_aligned(cur.add(2 * V::BYTES));
+            let d = V::load_aligned(cur.add(3 * V::BYTES));
+            let eqa = matches(a);
+            let eqb = matches(b);
+            let eqc = matches(c);
+            let eqd = matches(d);
+            let or1 = eqa.or(eqb);
+            let or2 = eqc.or(eqd);
+            let or3 = or1.or(or2);
+            if or3.movemask_will_have_non_zero() {
+                let mask = eqa.movemask();
+                if mask.has_non_zero() {
+                    return Some(cur.wrapping_add(topos(mask)));
+                }
+
+                let mask = eqb.movemask();
+                if mask.has_non_zero() {
+                    return Some(
+                        cur.add(1 * V::BYTES).wrapping_add(topos(mask)),
+                    );
+                }
+
+                let mask = eqc.movemask();
+                if mask.has_non_zero() {
+                    return Some(
+                        cur.add(2 * V::BYTES).wrapping_add(topos(mask)),
+                    );
+                }
+
+                let mask = eqd.movemask();
+                debug_assert!(mask.has_non_zero());
+                return Some(cur.add(3 * V::BYTES).wrapping_add(topos(mask)));
+            }
+            cur = cur.add(loop_size);
+        }
+    }
+    // Handle any leftovers after the aligned loop above. We use unaligned
+    // loads here, but I believe we are guaranteed that they are aligned
+    // since `cur` is aligned.
+    while cur <= end.sub(V::BYTES) {
+        debug_assert!(end.distance(cur) >= V::BYTES);
+        if let Some(cur) = search_chunk_with(cur, &matches) {
+            return Some(cur);
+        }
+        cur = cur.add(V::BYTES);
+    }
+    // Finally handle any remaining bytes less than the size of V. In this
+    // case, our pointer may indeed be unaligned and the load may overlap
+    // with the previous one. But that's okay since we know the previous
+    // load didn't lead to a match (otherwise we wouldn't be here).
+    if cur < end {
+        debug_assert!(end.distance(cur) < V::BYTES);
+        cur = cur.sub(V::BYTES - end.distance(cur));
+        debug_assert_eq!(end.distance(cur), V::BYTES);
+        return search_chunk_with(cur, &matches);
+    }
+    None
+}
+
+/// Search `V::BYTES` starting at `cur` via an unaligned load, and return a
+/// pointer to the first position at which `matches` reports a match.
+///
+/// # Safety
+///
+/// `cur` must be a valid pointer and it must be valid to do an unaligned
+/// load of size `V::BYTES` at `cur`.
+#[inline(always)]
+unsafe fn search_chunk_with<V: Vector>(
+    cur: *const u8,
+    matches: &impl Fn(V) -> V,
+) -> Option<*const u8> {
+    let chunk = V::load_unaligned(cur);
+    let mask = matches(chunk).movemask();
+    if mask.has_non_zero() {
+        Some(cur.wrapping_add(mask.first_offset()))
+    } else {
+        None
+    }
+}
+
 /// Performs a forward byte-at-a-time loop until either `ptr >= end_ptr` or
 /// until `confirm(*ptr)` returns `true`. If the former occurs, then `None` is
 /// returned. If the latter occurs, then the pointer at which `confirm` returns
@@ -12,3 +12,4 @@ write the code once (usually).
 
 pub(crate) mod memchr;
 pub(crate) mod packedpair;
+pub(crate) mod shortneedle;
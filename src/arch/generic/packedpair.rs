@@ -5,11 +5,18 @@ The "packed pair" algorithm is based on the [generic SIMD] algorithm. The main
 difference is that it (by default) uses a background distribution of byte
 frequencies to heuristically select the pair of bytes to search for.
 
+This also provides a variant of the prefilter that looks for three bytes
+instead of two, for haystacks in which even the rarest pair of bytes from a
+needle is common.
+
 [generic SIMD]: http://0x80.pl/articles/simd-strfind.html#first-and-last
 */
 
 use crate::{
-    arch::all::{is_equal_raw, packedpair::Pair},
+    arch::all::{
+        is_equal_raw,
+        packedpair::{Pair, Triple},
+    },
     ext::Pointer,
     vector::{MoveMask, Vector},
 };
@@ -299,6 +306,132 @@ impl<V: Vector> Finder<V> {
     }
 }
 
+/// A generic architecture dependent "packed triple" prefilter.
+///
+/// This is like [`Finder`], except that it looks for three bytes from the
+/// needle instead of two, and it only reports candidates. That is, it is only
+/// meant to be used as a prefilter. Requiring a third byte to match makes
+/// candidates much rarer in haystacks where even the rarest pair of bytes
+/// from the needle is common, like base64 or DNA sequences.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TripleFinder<V> {
+    triple: Triple,
+    v1: V,
+    v2: V,
+    v3: V,
+    min_haystack_len: usize,
+}
+
+impl<V: Vector> TripleFinder<V> {
+    /// Create a new triple prefilter.
+    ///
+    /// # Safety
+    ///
+    /// Callers must ensure that whatever vector type this routine is called
+    /// with is supported by the current environment.
+    ///
+    /// Callers must also ensure that the triple is valid for the needle
+    /// given.
+    #[inline(always)]
+    pub(crate) unsafe fn new(
+        needle: &[u8],
+        triple: Triple,
+    ) -> TripleFinder<V> {
+        let max_index =
+            triple.index1().max(triple.index2()).max(triple.index3());
+        let min_haystack_len =
+            core::cmp::max(needle.len(), usize::from(max_index) + V::BYTES);
+        let v1 = V::splat(needle[usize::from(triple.index1())]);
+        let v2 = V::splat(needle[usize::from(triple.index2())]);
+        let v3 = V::splat(needle[usize::from(triple.index3())]);
+        TripleFinder { triple, v1, v2, v3, min_haystack_len }
+    }
+
+    /// Searches the given haystack for offsets that represent candidate
+    /// matches of the `needle` given to this finder's constructor.
+    ///
+    /// # Panics
+    ///
+    /// When `haystack.len()` is less than [`TripleFinder::min_haystack_len`].
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Finder::find_prefilter`].
+    #[inline(always)]
+    pub(crate) unsafe fn find_prefilter(
+        &self,
+        haystack: &[u8],
+    ) -> Option<usize> {
+        assert!(
+            haystack.len() >= self.min_haystack_len,
+            "haystack too small, should be at least {} but got {}",
+            self.min_haystack_len,
+            haystack.len(),
+        );
+
+        let start = haystack.as_ptr();
+        let end = start.add(haystack.len());
+        let max = end.sub(self.min_haystack_len);
+        let mut cur = start;
+        while cur <= max {
+            if let Some(chunki) = self.find_prefilter_in_chunk(cur) {
+                return Some(matched(start, cur, chunki));
+            }
+            cur = cur.add(V::BYTES);
+        }
+        if cur < end {
+            // As with `Finder::find_prefilter`, no candidates occur before
+            // `cur`, so it's fine for the last chunk to overlap with them.
+            cur = max;
+            if let Some(chunki) = self.find_prefilter_in_chunk(cur) {
+                return Some(matched(start, cur, chunki));
+            }
+        }
+        None
+    }
+
+    /// Search for an occurrence of our triple of bytes from the needle in
+    /// the chunk pointed to by cur.
+    ///
+    /// # Safety
+    ///
+    /// It must be safe to do an unaligned read of size(V) bytes starting at
+    /// each of (cur + self.index1), (cur + self.index2) and
+    /// (cur + self.index3).
+    #[inline(always)]
+    unsafe fn find_prefilter_in_chunk(&self, cur: *const u8) -> Option<usize> {
+        let index1 = usize::from(self.triple.index1());
+        let index2 = usize::from(self.triple.index2());
+        let index3 = usize::from(self.triple.index3());
+        let eq1 = V::load_unaligned(cur.add(index1)).cmpeq(self.v1);
+        let eq2 = V::load_unaligned(cur.add(index2)).cmpeq(self.v2);
+        let eq3 = V::load_unaligned(cur.add(index3)).cmpeq(self.v3);
+
+        let offsets = eq1.and(eq2).and(eq3).movemask();
+        if !offsets.has_non_zero() {
+            return None;
+        }
+        Some(offsets.first_offset())
+    }
+
+    /// Returns the triple of offsets (into the needle) that this prefilter
+    /// looks for.
+    #[inline]
+    pub(crate) fn triple(&self) -> &Triple {
+        &self.triple
+    }
+
+    /// Returns the minimum haystack length that this `TripleFinder` can
+    /// search.
+    ///
+    /// Providing a haystack to this `TripleFinder` shorter than this length
+    /// is guaranteed to result in a panic.
+    #[inline(always)]
+    pub(crate) fn min_haystack_len(&self) -> usize {
+        self.min_haystack_len
+    }
+}
+
 /// Accepts a chunk-relative offset and returns a haystack relative offset.
 ///
 /// This used to be marked `#[cold]` and `#[inline(never)]`, but I couldn't
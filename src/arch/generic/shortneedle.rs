@@ -0,0 +1,197 @@
+/*!
+Generic crate-internal routines for searching for very short needles.
+
+For a needle of `N` bytes, each step of the search loads `N` vectors from the
+haystack, each shifted by one more byte than the last, and compares every one
+of them with the corresponding needle byte splatted across a vector. The
+comparisons are then combined with a bitwise AND, so that every bit set in
+the resulting mask corresponds to a position at which the entire needle
+occurs. Unlike the "packed pair" algorithm, there is no verification step.
+
+This only makes sense when `N` is small, since each step does `N` loads and
+`N` comparisons.
+*/
+
+use crate::{
+    ext::Pointer,
+    vector::{MoveMask, Vector},
+};
+
+/// A generic architecture dependent finder for needles of exactly `N` bytes.
+///
+/// This type is not meant to be exported and is instead meant to be used as
+/// the implementation for architecture specific facades. Like the "packed
+/// pair" finder, everything here has safety obligations because callers
+/// need to inline calls into routines marked with
+/// `#[target_feature(enable = "...")]`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Finder<V, const N: usize> {
+    vs: [V; N],
+}
+
+impl<V: Vector, const N: usize> Finder<V, N> {
+    /// Create a new finder for the given needle.
+    ///
+    /// # Safety
+    ///
+    /// Callers must ensure that whatever vector type this routine is called
+    /// with is supported by the current environment.
+    ///
+    /// Callers must also ensure that `N >= 1`.
+    #[inline(always)]
+    pub(crate) unsafe fn new(needle: [u8; N]) -> Finder<V, N> {
+        let mut vs = [V::splat(needle[0]); N];
+        for (v, &b) in vs.iter_mut().zip(needle.iter()).skip(1) {
+            *v = V::splat(b);
+        }
+        Finder { vs }
+    }
+
+    /// Returns the offset of the first occurrence of the needle in the given
+    /// haystack.
+    ///
+    /// # Panics
+    ///
+    /// When `haystack.len()` is less than [`Finder::min_haystack_len`].
+    ///
+    /// # Safety
+    ///
+    /// Since this is meant to be used with vector functions, callers need to
+    /// specialize this inside of a function with a `target_feature` attribute.
+    /// Therefore, callers must ensure that whatever target feature is being
+    /// used supports the vector functions that this function is specialized
+    /// for. (For the specific vector functions used, see the Vector trait
+    /// implementations.)
+    #[inline(always)]
+    pub(crate) unsafe fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let min_haystack_len = Finder::<V, N>::min_haystack_len();
+        assert!(
+            haystack.len() >= min_haystack_len,
+            "haystack too small, should be at least {} but got {}",
+            min_haystack_len,
+            haystack.len(),
+        );
+
+        let start = haystack.as_ptr();
+        let end = start.add(haystack.len());
+        // The last position at which a full chunk can be searched. That is,
+        // where it is safe to load the last of our shifted vectors.
+        let max = end.sub(min_haystack_len);
+        let mut cur = start;
+        while cur <= max {
+            let mask = self.find_in_chunk(cur);
+            if mask.has_non_zero() {
+                return Some(cur.distance(start) + mask.first_offset());
+            }
+            cur = cur.add(V::BYTES);
+        }
+        // Positions before `cur` are known not to match, so it's fine for
+        // the last chunk to overlap with them.
+        if cur < max.add(V::BYTES) {
+            let mask = self.find_in_chunk(max);
+            if mask.has_non_zero() {
+                return Some(max.distance(start) + mask.first_offset());
+            }
+        }
+        None
+    }
+
+    /// Returns the offset of the last occurrence of the needle in the given
+    /// haystack.
+    ///
+    /// # Panics
+    ///
+    /// When `haystack.len()` is less than [`Finder::min_haystack_len`].
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Finder::find`].
+    #[inline(always)]
+    pub(crate) unsafe fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+        let min_haystack_len = Finder::<V, N>::min_haystack_len();
+        assert!(
+            haystack.len() >= min_haystack_len,
+            "haystack too small, should be at least {} but got {}",
+            min_haystack_len,
+            haystack.len(),
+        );
+
+        let start = haystack.as_ptr();
+        let end = start.add(haystack.len());
+        let mut cur = end.sub(min_haystack_len);
+        let mask = self.find_in_chunk(cur);
+        if mask.has_non_zero() {
+            return Some(cur.distance(start) + mask.last_offset());
+        }
+        while cur.distance(start) >= V::BYTES {
+            cur = cur.sub(V::BYTES);
+            let mask = self.find_in_chunk(cur);
+            if mask.has_non_zero() {
+                return Some(cur.distance(start) + mask.last_offset());
+            }
+        }
+        // Positions at or after `cur` are known not to match, so it's fine
+        // for the first chunk to overlap with them.
+        if cur > start {
+            let mask = self.find_in_chunk(start);
+            if mask.has_non_zero() {
+                return Some(mask.last_offset());
+            }
+        }
+        None
+    }
+
+    /// Returns a mask with a bit set for every position in the chunk pointed
+    /// to by `cur` at which the needle occurs.
+    ///
+    /// # Safety
+    ///
+    /// It must be safe to do an unaligned read of size(V) bytes starting at
+    /// `cur + i` for every `i` in `0..N`.
+    #[inline(always)]
+    unsafe fn find_in_chunk(&self, cur: *const u8) -> V::Mask {
+        let mut eq = V::load_unaligned(cur).cmpeq(self.vs[0]);
+        for (i, &v) in self.vs.iter().enumerate().skip(1) {
+            eq = eq.and(V::load_unaligned(cur.add(i)).cmpeq(v));
+        }
+        eq.movemask()
+    }
+
+    /// Returns the minimum haystack length that this `Finder` can search.
+    ///
+    /// Providing a haystack to this `Finder` shorter than this length is
+    /// guaranteed to result in a panic.
+    #[inline(always)]
+    pub(crate) fn min_haystack_len() -> usize {
+        V::BYTES + N - 1
+    }
+}
+
+/// Returns the offset of the first occurrence of `needle` in `haystack`, by
+/// comparing one position at a time.
+///
+/// This is used by the architecture specific facades for haystacks that are
+/// too short for a vector search.
+#[inline(always)]
+pub(crate) fn find_scalar<const N: usize>(
+    needle: &[u8; N],
+    haystack: &[u8],
+) -> Option<usize> {
+    haystack.windows(N).position(|w| w == needle)
+}
+
+/// Returns the offset of the last occurrence of `needle` in `haystack`, by
+/// comparing one position at a time.
+///
+/// This is used by the architecture specific facades for haystacks that are
+/// too short for a vector search.
+#[inline(always)]
+pub(crate) fn rfind_scalar<const N: usize>(
+    needle: &[u8; N],
+    haystack: &[u8],
+) -> Option<usize> {
+    haystack.windows(N).rposition(|w| w == needle)
+}
+
+// If you're looking for tests, those are run for each instantiation of the
+// above code. So for example, see arch::x86_64::sse2::shortneedle.
@@ -5,11 +5,16 @@ These routines are useful as primitives for tasks not covered by the higher
 level crate API.
 */
 
+pub use self::searcher::{ByteSearcher, ByteSearcherIter, SubstringSearcher};
+
 pub mod all;
 pub(crate) mod generic;
+mod searcher;
 
 #[cfg(target_arch = "aarch64")]
 pub mod aarch64;
+#[cfg(feature = "portable-simd")]
+pub mod portable;
 #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
 pub mod wasm32;
 #[cfg(target_arch = "x86_64")]
@@ -0,0 +1,18 @@
+/*!
+Portable vector algorithms written on top of `core::simd`.
+
+The routines in this module work on any target, and use whatever vector
+instructions the compiler is able to lower `core::simd` operations to. This
+makes them useful on targets without a hand-written vector implementation in
+this crate, such as RISC-V or PowerPC. On targets that do have one, the
+hand-written implementations are usually at least as fast.
+
+The top-level routines in this crate don't use this module. It must be used
+explicitly.
+
+This module is only available when the `portable-simd` crate feature is
+enabled, which requires a nightly compiler since `core::simd` is unstable.
+*/
+
+pub mod memchr;
+pub mod packedpair;
@@ -0,0 +1,231 @@
+/*!
+A portable 128-bit vector implementation of the "packed pair" SIMD algorithm.
+
+The "packed pair" algorithm is based on the [generic SIMD] algorithm. The main
+difference is that it (by default) uses a background distribution of byte
+frequencies to heuristically select the pair of bytes to search for.
+
+[generic SIMD]: http://0x80.pl/articles/simd-strfind.html#first-and-last
+*/
+
+use core::simd::Simd;
+
+use crate::arch::{
+    all::packedpair::{Pair, Triple},
+    generic::packedpair,
+};
+
+/// A "packed pair" finder that uses portable 128-bit vector operations.
+///
+/// This finder picks two bytes that it believes have high predictive power
+/// for indicating an overall match of a needle. Depending on whether
+/// `Finder::find` or `Finder::find_prefilter` is used, it reports offsets
+/// where the needle matches or could match. In the prefilter case, candidates
+/// are reported whenever the [`Pair`] of bytes given matches.
+#[derive(Clone, Copy, Debug)]
+pub struct Finder(packedpair::Finder<Simd<u8, 16>>);
+
+impl Finder {
+    /// Create a new pair searcher. The searcher returned can either report
+    /// exact matches of `needle` or act as a prefilter and report candidate
+    /// positions of `needle`.
+    ///
+    /// If a [`Pair`] could not be constructed from the needle given, then
+    /// `None` is returned.
+    #[inline]
+    pub fn new(needle: &[u8]) -> Option<Finder> {
+        Some(Finder::with_pair(needle, Pair::new(needle)?))
+    }
+
+    /// Create a new "packed pair" finder using the pair of bytes given.
+    ///
+    /// This constructor permits callers to control precisely which pair of
+    /// bytes is used as a predicate.
+    #[inline]
+    pub fn with_pair(needle: &[u8], pair: Pair) -> Finder {
+        // SAFETY: Vectors from `core::simd` are always available. We are
+        // also guaranteed to have needle.len() > 1 because we have a valid
+        // Pair.
+        unsafe { Finder(packedpair::Finder::new(needle, pair)) }
+    }
+
+    /// Execute a search using portable vectors and routines.
+    ///
+    /// # Panics
+    ///
+    /// When `haystack.len()` is less than [`Finder::min_haystack_len`].
+    #[inline]
+    pub fn find(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        // SAFETY: Vectors from `core::simd` are always available.
+        unsafe { self.0.find(haystack, needle) }
+    }
+
+    /// Execute a prefilter search using portable vectors and routines.
+    ///
+    /// # Panics
+    ///
+    /// When `haystack.len()` is less than [`Finder::min_haystack_len`].
+    #[inline]
+    pub fn find_prefilter(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: Vectors from `core::simd` are always available.
+        unsafe { self.0.find_prefilter(haystack) }
+    }
+
+    /// Returns the pair of offsets (into the needle) used to check as a
+    /// predicate before confirming whether a needle exists at a particular
+    /// position.
+    #[inline]
+    pub fn pair(&self) -> &Pair {
+        self.0.pair()
+    }
+
+    /// Returns the minimum haystack length that this `Finder` can search.
+    ///
+    /// Using a haystack with length smaller than this in a search will result
+    /// in a panic. The reason for this restriction is that this finder is
+    /// meant to be a low-level component that is part of a larger substring
+    /// strategy. In that sense, it avoids trying to handle all cases and
+    /// instead only handles the cases that it can handle very well.
+    #[inline]
+    pub fn min_haystack_len(&self) -> usize {
+        self.0.min_haystack_len()
+    }
+}
+
+/// A "packed triple" prefilter that uses portable 128-bit vector operations.
+///
+/// This is like [`Finder`], except that it looks for three bytes from the
+/// needle instead of two, and only reports candidates. It is meant for
+/// haystacks in which even the rarest pair of bytes from the needle is
+/// common, like base64 or DNA sequences. Candidates are reported whenever
+/// the [`Triple`] of bytes given matches.
+#[derive(Clone, Copy, Debug)]
+pub struct TripleFinder(packedpair::TripleFinder<Simd<u8, 16>>);
+
+impl TripleFinder {
+    /// Create a new triple prefilter.
+    ///
+    /// If a [`Triple`] could not be constructed from the needle given, then
+    /// `None` is returned.
+    #[inline]
+    pub fn new(needle: &[u8]) -> Option<TripleFinder> {
+        TripleFinder::with_triple(needle, Triple::new(needle)?)
+    }
+
+    /// Create a new triple prefilter using the triple of bytes given.
+    ///
+    /// If the triple is invalid for the needle given, then `None` is
+    /// returned.
+    #[inline]
+    pub fn with_triple(needle: &[u8], triple: Triple) -> Option<TripleFinder> {
+        let (i1, i2, i3) = (triple.index1(), triple.index2(), triple.index3());
+        let triple = Triple::with_indices(needle, i1, i2, i3)?;
+        // SAFETY: Vectors from `core::simd` are always available, and we
+        // check that the triple is valid for the needle above.
+        unsafe {
+            Some(TripleFinder(packedpair::TripleFinder::new(needle, triple)))
+        }
+    }
+
+    /// Run this prefilter on the given haystack.
+    ///
+    /// If a candidate match is found, then an offset where the needle *could*
+    /// begin in the haystack is returned.
+    ///
+    /// # Panics
+    ///
+    /// When `haystack.len()` is less than [`TripleFinder::min_haystack_len`].
+    #[inline]
+    pub fn find_prefilter(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: Vectors from `core::simd` are always available.
+        unsafe { self.0.find_prefilter(haystack) }
+    }
+
+    /// Returns the triple of offsets (into the needle) that this prefilter
+    /// looks for.
+    #[inline]
+    pub fn triple(&self) -> &Triple {
+        self.0.triple()
+    }
+
+    /// Returns the minimum haystack length that this `TripleFinder` can
+    /// search.
+    ///
+    /// Using a haystack with length smaller than this in a search will result
+    /// in a panic.
+    #[inline]
+    pub fn min_haystack_len(&self) -> usize {
+        self.0.min_haystack_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<Option<usize>> {
+        let f = Finder::new(needle)?;
+        if haystack.len() < f.min_haystack_len() {
+            return None;
+        }
+        Some(f.find(haystack, needle))
+    }
+
+    define_substring_forward_quickcheck!(find);
+
+    fn find_triple(haystack: &[u8], needle: &[u8]) -> Option<Option<usize>> {
+        let f = TripleFinder::new(needle)?;
+        Some(crate::tests::packedpair::find_with_prefilter(
+            haystack,
+            needle,
+            f.min_haystack_len(),
+            |h| f.find_prefilter(h),
+        ))
+    }
+
+    #[test]
+    fn forward_substring_triple_prefilter() {
+        crate::tests::substring::Runner::new().fwd(find_triple).run()
+    }
+
+    #[test]
+    fn forward_substring() {
+        crate::tests::substring::Runner::new().fwd(find).run()
+    }
+
+    #[test]
+    fn forward_packedpair() {
+        fn find(
+            haystack: &[u8],
+            needle: &[u8],
+            index1: u8,
+            index2: u8,
+        ) -> Option<Option<usize>> {
+            let pair = Pair::with_indices(needle, index1, index2)?;
+            let f = Finder::with_pair(needle, pair);
+            if haystack.len() < f.min_haystack_len() {
+                return None;
+            }
+            Some(f.find(haystack, needle))
+        }
+        crate::tests::packedpair::Runner::new().fwd(find).run()
+    }
+
+    #[test]
+    fn forward_packedpair_prefilter() {
+        fn find(
+            haystack: &[u8],
+            needle: &[u8],
+            index1: u8,
+            index2: u8,
+        ) -> Option<Option<usize>> {
+            let pair = Pair::with_indices(needle, index1, index2)?;
+            let f = Finder::with_pair(needle, pair);
+            if haystack.len() < f.min_haystack_len() {
+                return None;
+            }
+            Some(f.find_prefilter(haystack))
+        }
+        crate::tests::packedpair::Runner::new().fwd(find).run()
+    }
+}
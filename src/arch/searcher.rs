@@ -0,0 +1,494 @@
+use crate::arch::generic::memchr as generic;
+
+/// A trait for searchers that find occurrences of one or more bytes.
+///
+/// This is implemented by the `One`, `Two` and `Three` searchers in each of
+/// the architecture specific `memchr` sub-modules, such as
+/// [`arch::all::memchr`](crate::arch::all::memchr) and
+/// `arch::x86_64::avx2::memchr` (on `x86_64`). It permits writing code that
+/// is generic over the specific implementation used, for example, to
+/// benchmark or test several of them in the same way.
+///
+/// A position in a haystack matches when the byte at that position is equal
+/// to any one of the needle bytes given to the searcher.
+///
+/// # Safety
+///
+/// Implementations must guarantee that every pointer returned by
+/// [`ByteSearcher::find_raw`] and [`ByteSearcher::rfind_raw`] is greater
+/// than or equal to the start pointer and less than the end pointer.
+/// Similarly, every offset returned by [`ByteSearcher::find`] and
+/// [`ByteSearcher::rfind`] must be a valid index into the haystack. Generic
+/// code, such as [`ByteSearcherIter`], relies on this for memory safety.
+///
+/// # Example
+///
+/// This shows how to write a routine that is generic over searchers, and
+/// then call it with a couple of different implementations.
+///
+/// ```
+/// use memchr::arch::{all::memchr::{One, Two}, ByteSearcher};
+///
+/// fn all<S: ByteSearcher>(searcher: &S, haystack: &[u8]) -> Vec<usize> {
+///     searcher.iter(haystack).collect()
+/// }
+///
+/// let haystack = b"foo bar baz";
+/// assert_eq!(all(&One::new(b'a'), haystack), vec![5, 9]);
+/// assert_eq!(all(&Two::new(b'a', b'z'), haystack), vec![5, 9, 10]);
+/// ```
+pub unsafe trait ByteSearcher {
+    /// Return the first occurrence of one of the needle bytes in the given
+    /// haystack. If no such occurrence exists, then `None` is returned.
+    ///
+    /// The occurrence is reported as an offset into `haystack`. Its maximum
+    /// value is `haystack.len() - 1`.
+    fn find(&self, haystack: &[u8]) -> Option<usize>;
+
+    /// Return the last occurrence of one of the needle bytes in the given
+    /// haystack. If no such occurrence exists, then `None` is returned.
+    ///
+    /// The occurrence is reported as an offset into `haystack`. Its maximum
+    /// value is `haystack.len() - 1`.
+    fn rfind(&self, haystack: &[u8]) -> Option<usize>;
+
+    /// Like `find`, but accepts and returns raw pointers.
+    ///
+    /// When a match is found, the pointer returned is guaranteed to be
+    /// `>= start` and `< end`.
+    ///
+    /// # Safety
+    ///
+    /// * Both `start` and `end` must be valid for reads.
+    /// * Both `start` and `end` must point to an initialized value.
+    /// * Both `start` and `end` must point to the same allocated object and
+    ///   must either be in bounds or at most one byte past the end of the
+    ///   allocated object.
+    /// * Both `start` and `end` must be _derived from_ a pointer to the same
+    ///   object.
+    /// * The distance between `start` and `end` must not overflow `isize`.
+    /// * The distance being in bounds must not rely on "wrapping around" the
+    ///   address space.
+    ///
+    /// Note that callers may pass a pair of pointers such that
+    /// `start >= end`. In that case, `None` will always be returned.
+    unsafe fn find_raw(
+        &self,
+        start: *const u8,
+        end: *const u8,
+    ) -> Option<*const u8>;
+
+    /// Like `rfind`, but accepts and returns raw pointers.
+    ///
+    /// When a match is found, the pointer returned is guaranteed to be
+    /// `>= start` and `< end`.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`ByteSearcher::find_raw`].
+    unsafe fn rfind_raw(
+        &self,
+        start: *const u8,
+        end: *const u8,
+    ) -> Option<*const u8>;
+
+    /// Counts all occurrences of the needle bytes in the given haystack.
+    ///
+    /// By default, this executes a search for each occurrence. Searchers
+    /// with a specialized counting routine use it instead.
+    fn count(&self, haystack: &[u8]) -> usize
+    where
+        Self: Sized,
+    {
+        self.iter(haystack).count()
+    }
+
+    /// Returns an iterator over all occurrences of the needle bytes in the
+    /// given haystack.
+    ///
+    /// The iterator returned implements `DoubleEndedIterator`. This means it
+    /// can also be used to find occurrences in reverse order.
+    fn iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> ByteSearcherIter<'a, 'h, Self>
+    where
+        Self: Sized,
+    {
+        ByteSearcherIter { searcher: self, it: generic::Iter::new(haystack) }
+    }
+}
+
+/// An iterator over all occurrences of the needle bytes of a
+/// [`ByteSearcher`] in a haystack.
+///
+/// This iterator implements `DoubleEndedIterator`, which means it can also be
+/// used to find occurrences in reverse order.
+///
+/// This iterator is created by the [`ByteSearcher::iter`] method.
+///
+/// The lifetime parameters are as follows:
+///
+/// * `'a` refers to the lifetime of the underlying searcher.
+/// * `'h` refers to the lifetime of the haystack being searched.
+#[derive(Clone, Debug)]
+pub struct ByteSearcherIter<'a, 'h, S> {
+    searcher: &'a S,
+    it: generic::Iter<'h>,
+}
+
+impl<'a, 'h, S: ByteSearcher> Iterator for ByteSearcherIter<'a, 'h, S> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        // SAFETY: We rely on the generic iterator to provide valid start
+        // and end pointers, and implementations of `ByteSearcher` guarantee
+        // that any pointer returned by 'find_raw' falls within the bounds of
+        // the start and end pointer.
+        unsafe { self.it.next(|s, e| self.searcher.find_raw(s, e)) }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.it.size_hint()
+    }
+}
+
+impl<'a, 'h, S: ByteSearcher> DoubleEndedIterator
+    for ByteSearcherIter<'a, 'h, S>
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        // SAFETY: We rely on the generic iterator to provide valid start
+        // and end pointers, and implementations of `ByteSearcher` guarantee
+        // that any pointer returned by 'rfind_raw' falls within the bounds of
+        // the start and end pointer.
+        unsafe { self.it.next_back(|s, e| self.searcher.rfind_raw(s, e)) }
+    }
+}
+
+impl<'a, 'h, S: ByteSearcher> core::iter::FusedIterator
+    for ByteSearcherIter<'a, 'h, S>
+{
+}
+
+/// A trait for searchers that find occurrences of a needle in a haystack.
+///
+/// This is implemented by the substring searchers in this crate, such as
+/// [`memmem::Finder`](crate::memmem::Finder),
+/// [`arch::all::twoway::Finder`](crate::arch::all::twoway::Finder),
+/// [`arch::all::rabinkarp::Finder`](crate::arch::all::rabinkarp::Finder)
+/// and the "packed pair" finders in the architecture specific sub-modules.
+/// It permits writing code that is generic over the specific implementation
+/// used.
+///
+/// Some searchers, like the "packed pair" finders, can only search haystacks
+/// of a minimum length. Callers must check
+/// [`SubstringSearcher::min_haystack_len`] before searching.
+///
+/// # Example
+///
+/// ```
+/// use memchr::arch::{all::{rabinkarp, twoway}, SubstringSearcher};
+///
+/// fn find<S: SubstringSearcher>(
+///     searcher: &S,
+///     haystack: &[u8],
+///     needle: &[u8],
+/// ) -> Option<usize> {
+///     if haystack.len() < searcher.min_haystack_len() {
+///         return memchr::memmem::find(haystack, needle);
+///     }
+///     searcher.find(haystack, needle)
+/// }
+///
+/// let (haystack, needle) = (b"foo bar baz", b"bar");
+/// assert_eq!(Some(4), find(&twoway::Finder::new(needle), haystack, needle));
+/// let rk = rabinkarp::Finder::new(needle);
+/// assert_eq!(Some(4), find(&rk, haystack, needle));
+/// ```
+pub trait SubstringSearcher {
+    /// Returns the first occurrence of `needle` in the given `haystack`, or
+    /// `None` if no such occurrence could be found.
+    ///
+    /// The `needle` given must be the same as the `needle` that this
+    /// searcher was built with. Searchers that store their needle may
+    /// ignore it.
+    ///
+    /// # Panics
+    ///
+    /// Implementations may panic when `haystack.len()` is less than
+    /// [`SubstringSearcher::min_haystack_len`].
+    fn find(&self, haystack: &[u8], needle: &[u8]) -> Option<usize>;
+
+    /// Returns the minimum haystack length that this searcher can search.
+    ///
+    /// By default, this returns `0`. That is, haystacks of any length can be
+    /// searched.
+    fn min_haystack_len(&self) -> usize {
+        0
+    }
+}
+
+/// Implements `ByteSearcher` for the given types by delegating to their
+/// inherent methods.
+///
+/// The `count` form is for types with a specialized `count` routine.
+macro_rules! impl_byte_searcher {
+    (count: $($ty:ty),+ $(,)?) => {
+        $(
+            impl_byte_searcher!(@imp $ty, {
+                #[inline]
+                fn count(&self, haystack: &[u8]) -> usize {
+                    <$ty>::count(self, haystack)
+                }
+            });
+        )+
+    };
+    ($($ty:ty),+ $(,)?) => {
+        $( impl_byte_searcher!(@imp $ty, {}); )+
+    };
+    (@imp $ty:ty, { $($count:tt)* }) => {
+        // SAFETY: The inherent search routines of the searchers in this
+        // crate uphold the guarantees required by `ByteSearcher`.
+        unsafe impl ByteSearcher for $ty {
+            #[inline]
+            fn find(&self, haystack: &[u8]) -> Option<usize> {
+                <$ty>::find(self, haystack)
+            }
+
+            #[inline]
+            fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+                <$ty>::rfind(self, haystack)
+            }
+
+            #[inline]
+            unsafe fn find_raw(
+                &self,
+                start: *const u8,
+                end: *const u8,
+            ) -> Option<*const u8> {
+                <$ty>::find_raw(self, start, end)
+            }
+
+            #[inline]
+            unsafe fn rfind_raw(
+                &self,
+                start: *const u8,
+                end: *const u8,
+            ) -> Option<*const u8> {
+                <$ty>::rfind_raw(self, start, end)
+            }
+
+            $($count)*
+        }
+    };
+}
+
+/// Implements `SubstringSearcher` for the given "packed pair" finders, which
+/// have a minimum haystack length.
+macro_rules! impl_substring_searcher_packedpair {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl SubstringSearcher for $ty {
+                #[inline]
+                fn find(
+                    &self,
+                    haystack: &[u8],
+                    needle: &[u8],
+                ) -> Option<usize> {
+                    <$ty>::find(self, haystack, needle)
+                }
+
+                #[inline]
+                fn min_haystack_len(&self) -> usize {
+                    <$ty>::min_haystack_len(self)
+                }
+            }
+        )+
+    };
+}
+
+impl_byte_searcher!(count: crate::arch::all::memchr::One);
+impl_byte_searcher!(count: crate::One, crate::Two, crate::Three);
+impl_byte_searcher!(
+    crate::arch::all::memchr::Two,
+    crate::arch::all::memchr::Three,
+);
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    use super::{ByteSearcher, SubstringSearcher};
+    use crate::arch::x86_64::{avx2, sse2};
+
+    impl_byte_searcher!(count: sse2::memchr::One, avx2::memchr::One);
+    impl_byte_searcher!(
+        sse2::memchr::Two,
+        sse2::memchr::Three,
+        avx2::memchr::Two,
+        avx2::memchr::Three,
+    );
+    impl_substring_searcher_packedpair!(
+        sse2::packedpair::Finder,
+        avx2::packedpair::Finder,
+    );
+}
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64 {
+    use super::{ByteSearcher, SubstringSearcher};
+    use crate::arch::aarch64::neon;
+
+    impl_byte_searcher!(count: neon::memchr::One);
+    impl_byte_searcher!(neon::memchr::Two, neon::memchr::Three);
+    impl_substring_searcher_packedpair!(neon::packedpair::Finder);
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod wasm32 {
+    use super::{ByteSearcher, SubstringSearcher};
+    use crate::arch::wasm32::simd128;
+
+    impl_byte_searcher!(count: simd128::memchr::One);
+    impl_byte_searcher!(simd128::memchr::Two, simd128::memchr::Three);
+    impl_substring_searcher_packedpair!(simd128::packedpair::Finder);
+}
+
+#[cfg(feature = "portable-simd")]
+mod portable {
+    use super::{ByteSearcher, SubstringSearcher};
+    use crate::arch::portable;
+
+    impl_byte_searcher!(count: portable::memchr::One);
+    impl_byte_searcher!(portable::memchr::Two, portable::memchr::Three);
+    impl_substring_searcher_packedpair!(portable::packedpair::Finder);
+}
+
+impl SubstringSearcher for crate::arch::all::twoway::Finder {
+    #[inline]
+    fn find(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        crate::arch::all::twoway::Finder::find(self, haystack, needle)
+    }
+}
+
+impl SubstringSearcher for crate::arch::all::rabinkarp::Finder {
+    #[inline]
+    fn find(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        crate::arch::all::rabinkarp::Finder::find(self, haystack, needle)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl SubstringSearcher for crate::arch::all::shiftor::Finder {
+    #[inline]
+    fn find(&self, haystack: &[u8], _needle: &[u8]) -> Option<usize> {
+        crate::arch::all::shiftor::Finder::find(self, haystack)
+    }
+}
+
+impl<'n> SubstringSearcher for crate::memmem::Finder<'n> {
+    #[inline]
+    fn find(&self, haystack: &[u8], _needle: &[u8]) -> Option<usize> {
+        crate::memmem::Finder::find(self, haystack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    /// Runs the forward, reverse and count memchr tests on the searcher
+    /// built by `new`, which is given the needle bytes.
+    fn bytes<S: ByteSearcher + 'static>(
+        len: usize,
+        new: fn(&[u8]) -> Option<S>,
+    ) {
+        let runner = || crate::tests::memchr::Runner::new(len);
+        // The runner may give fewer needle bytes than asked for.
+        let new = move |n: &[u8]| if n.len() < len { None } else { new(n) };
+        runner().forward_iter(move |h, n| Some(new(n)?.iter(h).collect()));
+        runner()
+            .reverse_iter(move |h, n| Some(new(n)?.iter(h).rev().collect()));
+        runner().forward_iter(move |h, n| {
+            let s = new(n)?;
+            let (mut got, mut at) = (Vec::new(), 0);
+            while let Some(i) = ByteSearcher::find(&s, &h[at..]) {
+                got.push(at + i);
+                at += i + 1;
+            }
+            Some(got)
+        });
+        runner()
+            .count_iter(move |h, n| Some(ByteSearcher::count(&new(n)?, h)));
+    }
+
+    /// Runs the substring tests on the searcher built by `new`, which is
+    /// given the needle.
+    fn substring<S: SubstringSearcher + 'static>(new: fn(&[u8]) -> Option<S>) {
+        crate::tests::substring::Runner::new()
+            .fwd(move |h, n| {
+                let s = new(n)?;
+                if h.len() < s.min_haystack_len() {
+                    return None;
+                }
+                Some(SubstringSearcher::find(&s, h, n))
+            })
+            .run();
+    }
+
+    #[test]
+    fn all() {
+        use crate::arch::all::memchr::{One, Three, Two};
+
+        bytes(1, |n| Some(One::new(n[0])));
+        bytes(2, |n| Some(Two::new(n[0], n[1])));
+        bytes(3, |n| Some(Three::new(n[0], n[1], n[2])));
+        substring(|n| Some(crate::arch::all::twoway::Finder::new(n)));
+        substring(|n| Some(crate::arch::all::rabinkarp::Finder::new(n)));
+        #[cfg(feature = "alloc")]
+        {
+            substring(crate::arch::all::shiftor::Finder::new);
+            substring(|n| Some(crate::memmem::Finder::new(n).into_owned()));
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn x86_64() {
+        use crate::arch::x86_64::{avx2, sse2};
+
+        bytes(1, |n| sse2::memchr::One::new(n[0]));
+        bytes(2, |n| sse2::memchr::Two::new(n[0], n[1]));
+        bytes(3, |n| sse2::memchr::Three::new(n[0], n[1], n[2]));
+        bytes(1, |n| avx2::memchr::One::new(n[0]));
+        bytes(2, |n| avx2::memchr::Two::new(n[0], n[1]));
+        bytes(3, |n| avx2::memchr::Three::new(n[0], n[1], n[2]));
+        substring(sse2::packedpair::Finder::new);
+        substring(avx2::packedpair::Finder::new);
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[test]
+    fn aarch64() {
+        use crate::arch::aarch64::neon;
+
+        bytes(1, |n| neon::memchr::One::new(n[0]));
+        bytes(2, |n| neon::memchr::Two::new(n[0], n[1]));
+        bytes(3, |n| neon::memchr::Three::new(n[0], n[1], n[2]));
+        substring(neon::packedpair::Finder::new);
+    }
+
+    #[cfg(feature = "portable-simd")]
+    #[test]
+    fn portable() {
+        use crate::arch::portable;
+
+        bytes(1, |n| Some(portable::memchr::One::new(n[0])));
+        bytes(2, |n| Some(portable::memchr::Two::new(n[0], n[1])));
+        bytes(3, |n| Some(portable::memchr::Three::new(n[0], n[1], n[2])));
+        substring(portable::packedpair::Finder::new);
+    }
+}
@@ -81,7 +81,8 @@ impl One {
     /// `One::new` will return a `None` value.
     ///
     /// Note also that for the lifetime of a single program, if this returns
-    /// true then it will always return true.
+    /// true then it will always return true, unless AVX2 is later disabled
+    /// with [`set_cpu_features`](crate::set_cpu_features).
     #[inline]
     pub fn is_available() -> bool {
         #[cfg(not(target_feature = "sse2"))]
@@ -96,14 +97,7 @@ impl One {
             }
             #[cfg(not(target_feature = "avx2"))]
             {
-                #[cfg(feature = "std")]
-                {
-                    std::is_x86_feature_detected!("avx2")
-                }
-                #[cfg(not(feature = "std"))]
-                {
-                    false
-                }
+                crate::dispatch::cpu_features().has_avx2()
             }
         }
     }
@@ -568,7 +562,8 @@ impl Two {
     /// `Two::new` will return a `None` value.
     ///
     /// Note also that for the lifetime of a single program, if this returns
-    /// true then it will always return true.
+    /// true then it will always return true, unless AVX2 is later disabled
+    /// with [`set_cpu_features`](crate::set_cpu_features).
     #[inline]
     pub fn is_available() -> bool {
         #[cfg(not(target_feature = "sse2"))]
@@ -583,14 +578,7 @@ impl Two {
             }
             #[cfg(not(target_feature = "avx2"))]
             {
-                #[cfg(feature = "std")]
-                {
-                    std::is_x86_feature_detected!("avx2")
-                }
-                #[cfg(not(feature = "std"))]
-                {
-                    false
-                }
+                crate::dispatch::cpu_features().has_avx2()
             }
         }
     }
@@ -951,7 +939,8 @@ impl Three {
     /// `Three::new` will return a `None` value.
     ///
     /// Note also that for the lifetime of a single program, if this returns
-    /// true then it will always return true.
+    /// true then it will always return true, unless AVX2 is later disabled
+    /// with [`set_cpu_features`](crate::set_cpu_features).
     #[inline]
     pub fn is_available() -> bool {
         #[cfg(not(target_feature = "sse2"))]
@@ -966,14 +955,7 @@ impl Three {
             }
             #[cfg(not(target_feature = "avx2"))]
             {
-                #[cfg(feature = "std")]
-                {
-                    std::is_x86_feature_detected!("avx2")
-                }
-                #[cfg(not(feature = "std"))]
-                {
-                    false
-                }
+                crate::dispatch::cpu_features().has_avx2()
             }
         }
     }
@@ -10,7 +10,10 @@ frequencies to heuristically select the pair of bytes to search for.
 
 use core::arch::x86_64::{__m128i, __m256i};
 
-use crate::arch::{all::packedpair::Pair, generic::packedpair};
+use crate::arch::{
+    all::packedpair::{Pair, Triple},
+    generic::packedpair,
+};
 
 /// A "packed pair" finder that uses 256-bit vector operations.
 ///
@@ -82,7 +85,8 @@ impl Finder {
     /// valid [`Pair`] can be found from the needle given.
     ///
     /// Note also that for the lifetime of a single program, if this returns
-    /// true then it will always return true.
+    /// true then it will always return true, unless AVX2 is later disabled
+    /// with [`set_cpu_features`](crate::set_cpu_features).
     #[inline]
     pub fn is_available() -> bool {
         #[cfg(not(target_feature = "sse2"))]
@@ -97,14 +101,7 @@ impl Finder {
             }
             #[cfg(not(target_feature = "avx2"))]
             {
-                #[cfg(feature = "std")]
-                {
-                    std::is_x86_feature_detected!("avx2")
-                }
-                #[cfg(not(feature = "std"))]
-                {
-                    false
-                }
+                crate::dispatch::cpu_features().has_avx2()
             }
         }
     }
@@ -212,6 +209,131 @@ impl Finder {
     }
 }
 
+/// A "packed triple" prefilter that uses 256-bit vector operations.
+///
+/// This is like [`Finder`], except that it looks for three bytes from the
+/// needle instead of two, and only reports candidates. It is meant for
+/// haystacks in which even the rarest pair of bytes from the needle is
+/// common, like base64 or DNA sequences. Candidates are reported whenever
+/// the [`Triple`] of bytes given matches.
+#[derive(Clone, Copy, Debug)]
+pub struct TripleFinder {
+    sse2: packedpair::TripleFinder<__m128i>,
+    avx2: packedpair::TripleFinder<__m256i>,
+}
+
+impl TripleFinder {
+    /// Create a new triple prefilter.
+    ///
+    /// If AVX2 is unavailable in the current environment or if a [`Triple`]
+    /// could not be constructed from the needle given, then `None` is
+    /// returned.
+    #[inline]
+    pub fn new(needle: &[u8]) -> Option<TripleFinder> {
+        TripleFinder::with_triple(needle, Triple::new(needle)?)
+    }
+
+    /// Create a new triple prefilter using the triple of bytes given.
+    ///
+    /// If AVX2 is unavailable in the current environment or if the triple
+    /// is invalid for the needle given, then `None` is returned.
+    #[inline]
+    pub fn with_triple(needle: &[u8], triple: Triple) -> Option<TripleFinder> {
+        let (i1, i2, i3) = (triple.index1(), triple.index2(), triple.index3());
+        let triple = Triple::with_indices(needle, i1, i2, i3)?;
+        if TripleFinder::is_available() {
+            // SAFETY: we check that sse2/avx2 is available above, and that
+            // the triple is valid for the needle.
+            unsafe { Some(TripleFinder::with_triple_impl(needle, triple)) }
+        } else {
+            None
+        }
+    }
+
+    /// Create a new `TripleFinder` specific to AVX2 vectors and routines.
+    ///
+    /// # Safety
+    ///
+    /// Same as the safety for `packedpair::TripleFinder::new`, and callers
+    /// must also ensure that both SSE2 and AVX2 are available.
+    #[target_feature(enable = "sse2", enable = "avx2")]
+    #[inline]
+    unsafe fn with_triple_impl(needle: &[u8], triple: Triple) -> TripleFinder {
+        let sse2 = packedpair::TripleFinder::<__m128i>::new(needle, triple);
+        let avx2 = packedpair::TripleFinder::<__m256i>::new(needle, triple);
+        TripleFinder { sse2, avx2 }
+    }
+
+    /// Returns true when this implementation is available in the current
+    /// environment.
+    ///
+    /// When this is true, it is guaranteed that [`TripleFinder::with_triple`]
+    /// will return a `Some` value for a valid triple.
+    ///
+    /// Note also that for the lifetime of a single program, if this returns
+    /// true then it will always return true, unless AVX2 is later disabled
+    /// with [`set_cpu_features`](crate::set_cpu_features).
+    #[inline]
+    pub fn is_available() -> bool {
+        Finder::is_available()
+    }
+
+    /// Run this prefilter on the given haystack.
+    ///
+    /// If a candidate match is found, then an offset where the needle *could*
+    /// begin in the haystack is returned.
+    ///
+    /// # Panics
+    ///
+    /// When `haystack.len()` is less than [`TripleFinder::min_haystack_len`].
+    #[inline]
+    pub fn find_prefilter(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: Building a `TripleFinder` means it's safe to call 'sse2'
+        // and 'avx2' routines.
+        unsafe { self.find_prefilter_impl(haystack) }
+    }
+
+    /// Execute a prefilter search using AVX2 vectors and routines.
+    ///
+    /// # Panics
+    ///
+    /// When `haystack.len()` is less than [`TripleFinder::min_haystack_len`].
+    ///
+    /// # Safety
+    ///
+    /// (The target feature safety obligation is automatically fulfilled by
+    /// virtue of being a method on `TripleFinder`, which can only be
+    /// constructed when it is safe to call `sse2` and `avx2` routines.)
+    #[target_feature(enable = "sse2", enable = "avx2")]
+    #[inline]
+    unsafe fn find_prefilter_impl(&self, haystack: &[u8]) -> Option<usize> {
+        if haystack.len() < self.avx2.min_haystack_len() {
+            self.sse2.find_prefilter(haystack)
+        } else {
+            self.avx2.find_prefilter(haystack)
+        }
+    }
+
+    /// Returns the triple of offsets (into the needle) that this prefilter
+    /// looks for.
+    #[inline]
+    pub fn triple(&self) -> &Triple {
+        self.avx2.triple()
+    }
+
+    /// Returns the minimum haystack length that this `TripleFinder` can
+    /// search.
+    ///
+    /// Using a haystack with length smaller than this in a search will result
+    /// in a panic.
+    #[inline]
+    pub fn min_haystack_len(&self) -> usize {
+        // Like `Finder`, haystacks that are too short for AVX2 are searched
+        // with SSE2 instead.
+        self.sse2.min_haystack_len()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -226,6 +348,21 @@ mod tests {
 
     define_substring_forward_quickcheck!(find);
 
+    fn find_triple(haystack: &[u8], needle: &[u8]) -> Option<Option<usize>> {
+        let f = TripleFinder::new(needle)?;
+        Some(crate::tests::packedpair::find_with_prefilter(
+            haystack,
+            needle,
+            f.min_haystack_len(),
+            |h| f.find_prefilter(h),
+        ))
+    }
+
+    #[test]
+    fn forward_substring_triple_prefilter() {
+        crate::tests::substring::Runner::new().fwd(find_triple).run()
+    }
+
     #[test]
     fn forward_substring() {
         crate::tests::substring::Runner::new().fwd(find).run()
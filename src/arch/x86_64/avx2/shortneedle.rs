@@ -0,0 +1,250 @@
+/*!
+A 256-bit vector implementation of substring search for very short needles.
+
+This compares shifted vector loads of the haystack with each byte of the
+needle directly, so that every position reported is a match. There is no
+verification step.
+*/
+
+use core::arch::x86_64::{__m128i, __m256i};
+
+use crate::arch::generic::shortneedle;
+
+/// A finder for needles of exactly `N` bytes that uses 256-bit vector
+/// operations.
+///
+/// Only needles of `2`, `3` or `4` bytes are supported. Unlike the "packed
+/// pair" finder, haystacks of any length can be searched. Haystacks that are
+/// too short for a 256-bit vector search use 128-bit vectors instead, and
+/// those that are too short for either are searched one position at a time.
+///
+/// # Example
+///
+/// ```
+/// use memchr::arch::x86_64::avx2::shortneedle::Finder;
+///
+/// if let Some(finder) = Finder::new(*b"=>") {
+///     let haystack = b"match x { 1 => a, _ => b }";
+///     assert_eq!(Some(12), finder.find(haystack));
+///     assert_eq!(Some(20), finder.rfind(haystack));
+/// }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Finder<const N: usize> {
+    needle: [u8; N],
+    avx2: shortneedle::Finder<__m256i, N>,
+}
+
+impl<const N: usize> Finder<N> {
+    /// Create a new finder for the given needle.
+    ///
+    /// If AVX2 is unavailable in the current environment or if `N` isn't
+    /// one of `2`, `3` or `4`, then `None` is returned.
+    #[inline]
+    pub fn new(needle: [u8; N]) -> Option<Finder<N>> {
+        if Finder::<N>::is_available() && (2..=4).contains(&N) {
+            // SAFETY: we check that sse2/avx2 is available above, and that
+            // the needle is not empty.
+            unsafe { Some(Finder::new_impl(needle)) }
+        } else {
+            None
+        }
+    }
+
+    /// Create a new `Finder` specific to AVX2 vectors and routines.
+    ///
+    /// # Safety
+    ///
+    /// Same as the safety for `shortneedle::Finder::new`, and callers must
+    /// also ensure that both SSE2 and AVX2 are available.
+    #[target_feature(enable = "sse2", enable = "avx2")]
+    #[inline]
+    unsafe fn new_impl(needle: [u8; N]) -> Finder<N> {
+        let avx2 = shortneedle::Finder::<__m256i, N>::new(needle);
+        Finder { needle, avx2 }
+    }
+
+    /// Returns true when this implementation is available in the current
+    /// environment.
+    ///
+    /// When this is true, it is guaranteed that [`Finder::new`] will return
+    /// a `Some` value for needles of `2`, `3` or `4` bytes. Similarly, when
+    /// it is false, it is guaranteed that `Finder::new` will return a `None`
+    /// value.
+    ///
+    /// Note also that for the lifetime of a single program, if this returns
+    /// true then it will always return true, unless AVX2 is later disabled
+    /// with [`set_cpu_features`](crate::set_cpu_features).
+    #[inline]
+    pub fn is_available() -> bool {
+        #[cfg(not(target_feature = "sse2"))]
+        {
+            false
+        }
+        #[cfg(target_feature = "sse2")]
+        {
+            #[cfg(target_feature = "avx2")]
+            {
+                true
+            }
+            #[cfg(not(target_feature = "avx2"))]
+            {
+                crate::dispatch::cpu_features().has_avx2()
+            }
+        }
+    }
+
+    /// Returns the offset of the first occurrence of the needle in the given
+    /// haystack. If no such occurrence exists, then `None` is returned.
+    #[inline]
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: Building a `Finder` means it's safe to call 'sse2' and
+        // 'avx2' routines.
+        unsafe { self.find_impl(haystack) }
+    }
+
+    /// Returns the offset of the last occurrence of the needle in the given
+    /// haystack. If no such occurrence exists, then `None` is returned.
+    #[inline]
+    pub fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: Building a `Finder` means it's safe to call 'sse2' and
+        // 'avx2' routines.
+        unsafe { self.rfind_impl(haystack) }
+    }
+
+    /// Execute a forward search using AVX2 vectors and routines.
+    ///
+    /// # Safety
+    ///
+    /// (The target feature safety obligation is automatically fulfilled by
+    /// virtue of being a method on `Finder`, which can only be constructed
+    /// when it is safe to call `sse2` and `avx2` routines.)
+    #[target_feature(enable = "sse2", enable = "avx2")]
+    #[inline]
+    unsafe fn find_impl(&self, haystack: &[u8]) -> Option<usize> {
+        if haystack.len() < Finder::<N>::min_haystack_len() {
+            shortneedle::find_scalar(&self.needle, haystack)
+        } else if haystack.len()
+            < shortneedle::Finder::<__m256i, N>::min_haystack_len()
+        {
+            self.sse2().find(haystack)
+        } else {
+            self.avx2.find(haystack)
+        }
+    }
+
+    /// Execute a reverse search using AVX2 vectors and routines.
+    ///
+    /// # Safety
+    ///
+    /// (The target feature safety obligation is automatically fulfilled by
+    /// virtue of being a method on `Finder`, which can only be constructed
+    /// when it is safe to call `sse2` and `avx2` routines.)
+    #[target_feature(enable = "sse2", enable = "avx2")]
+    #[inline]
+    unsafe fn rfind_impl(&self, haystack: &[u8]) -> Option<usize> {
+        if haystack.len() < Finder::<N>::min_haystack_len() {
+            shortneedle::rfind_scalar(&self.needle, haystack)
+        } else if haystack.len()
+            < shortneedle::Finder::<__m256i, N>::min_haystack_len()
+        {
+            self.sse2().rfind(haystack)
+        } else {
+            self.avx2.rfind(haystack)
+        }
+    }
+
+    /// Returns a finder for 128-bit vectors, which is used for haystacks that
+    /// are too short for 256-bit vectors.
+    ///
+    /// This isn't stored in the `Finder` since splatting a few needle bytes
+    /// is cheap, and it would make every `Finder` much bigger.
+    ///
+    /// # Safety
+    ///
+    /// Callers must ensure that SSE2 is available.
+    #[target_feature(enable = "sse2")]
+    #[inline]
+    unsafe fn sse2(&self) -> shortneedle::Finder<__m128i, N> {
+        shortneedle::Finder::<__m128i, N>::new(self.needle)
+    }
+
+    /// Returns the needle that this finder searches for.
+    #[inline]
+    pub fn needle(&self) -> &[u8; N] {
+        &self.needle
+    }
+
+    /// Returns the minimum haystack length that is searched with vector
+    /// operations. Shorter haystacks are searched one position at a time.
+    #[inline]
+    pub fn min_haystack_len() -> usize {
+        // Haystacks that are too short for AVX2 are searched with SSE2.
+        shortneedle::Finder::<__m128i, N>::min_haystack_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<Option<usize>> {
+        let got = match needle.len() {
+            2 => Finder::<2>::new(needle.try_into().ok()?)?.find(haystack),
+            3 => Finder::<3>::new(needle.try_into().ok()?)?.find(haystack),
+            4 => Finder::<4>::new(needle.try_into().ok()?)?.find(haystack),
+            _ => return None,
+        };
+        Some(got)
+    }
+
+    fn rfind(haystack: &[u8], needle: &[u8]) -> Option<Option<usize>> {
+        let got = match needle.len() {
+            2 => Finder::<2>::new(needle.try_into().ok()?)?.rfind(haystack),
+            3 => Finder::<3>::new(needle.try_into().ok()?)?.rfind(haystack),
+            4 => Finder::<4>::new(needle.try_into().ok()?)?.rfind(haystack),
+            _ => return None,
+        };
+        Some(got)
+    }
+
+    define_substring_forward_quickcheck!(find);
+    define_substring_reverse_quickcheck!(rfind);
+
+    #[test]
+    fn forward_substring() {
+        crate::tests::substring::Runner::new().fwd(find).run()
+    }
+
+    #[test]
+    fn reverse_substring() {
+        crate::tests::substring::Runner::new().rev(rfind).run()
+    }
+
+    #[test]
+    fn every_position() {
+        let finder = match Finder::new(*b"::=") {
+            None => return,
+            Some(finder) => finder,
+        };
+        for len in 3..100 {
+            for i in 0..=len - 3 {
+                // Partial matches are everywhere, but the only match is
+                // at `i`.
+                let mut haystack = alloc::vec![b':'; len];
+                haystack[i + 2] = b'=';
+                assert_eq!(Some(i), finder.find(&haystack));
+                assert_eq!(Some(i), finder.rfind(&haystack));
+            }
+            assert_eq!(None, finder.find(&alloc::vec![b':'; len]));
+            assert_eq!(None, finder.rfind(&alloc::vec![b':'; len]));
+        }
+    }
+
+    #[test]
+    fn unsupported_lengths() {
+        assert!(Finder::<0>::new([]).is_none());
+        assert!(Finder::<1>::new([b'a']).is_none());
+        assert!(Finder::<5>::new(*b"abcde").is_none());
+    }
+}
@@ -0,0 +1,72 @@
+/*!
+CPU feature detection for `x86_64` that only depends on `core`.
+
+This is used to detect AVX2 support when the `std` feature is disabled, since
+`std::is_x86_feature_detected!` isn't available in that case. It queries the
+CPU directly with the `cpuid` instruction, and checks that the operating
+system has enabled the AVX register state with the `xgetbv` instruction.
+*/
+
+/// Returns true when the CPU supports AVX2 and the operating system has
+/// enabled the register state it requires.
+///
+/// This always returns false in SGX enclaves, where `cpuid` isn't permitted.
+#[cfg(not(target_env = "sgx"))]
+#[allow(unused_unsafe)]
+pub(crate) fn is_avx2_detected() -> bool {
+    use core::arch::x86_64::{__cpuid, __cpuid_count};
+
+    // SAFETY: The `cpuid` instruction is available on every `x86_64` CPU.
+    let max_leaf = unsafe { __cpuid(0) }.eax;
+    if max_leaf < 7 {
+        return false;
+    }
+    // SAFETY: As above.
+    let leaf1 = unsafe { __cpuid(1) };
+    let osxsave = leaf1.ecx & (1 << 27) != 0;
+    let avx = leaf1.ecx & (1 << 28) != 0;
+    if !osxsave || !avx {
+        return false;
+    }
+    // SAFETY: `xgetbv` is enabled because the OSXSAVE bit is set.
+    let xcr0 = unsafe { xgetbv0() };
+    // Both the SSE (bit 1) and AVX (bit 2) register state must be saved by
+    // the operating system on context switches.
+    if xcr0 & 0b110 != 0b110 {
+        return false;
+    }
+    // SAFETY: We checked above that leaf 7 is supported.
+    let leaf7 = unsafe { __cpuid_count(7, 0) };
+    leaf7.ebx & (1 << 5) != 0
+}
+
+/// Returns true when the CPU supports AVX2 and the operating system has
+/// enabled the register state it requires.
+///
+/// This always returns false in SGX enclaves, where `cpuid` isn't permitted.
+#[cfg(target_env = "sgx")]
+pub(crate) fn is_avx2_detected() -> bool {
+    false
+}
+
+/// Reads the `XCR0` extended control register.
+///
+/// # Safety
+///
+/// Callers must ensure that the OS has enabled `xgetbv`, as indicated by the
+/// OSXSAVE bit reported by `cpuid`.
+#[cfg(not(target_env = "sgx"))]
+#[target_feature(enable = "xsave")]
+unsafe fn xgetbv0() -> u64 {
+    core::arch::x86_64::_xgetbv(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_std() {
+        assert_eq!(std::is_x86_feature_detected!("avx2"), is_avx2_detected());
+    }
+}
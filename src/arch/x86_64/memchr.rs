@@ -5,6 +5,8 @@ These routines efficiently dispatch to the best implementation based on what
 the CPU supports.
 */
 
+use crate::dispatch::Resolved;
+
 /// Provides a way to run a memchr-like function while amortizing the cost of
 /// runtime CPU feature detection.
 ///
@@ -55,10 +57,14 @@ the CPU supports.
 ///
 /// Callers must also ensure that the `$hay_start` and `$hay_end` identifiers
 /// correspond to valid pointers.
+///
+/// The implementation chosen is recorded in `$resolved`, so that it can be
+/// reported by `crate::dispatch_info`.
 macro_rules! unsafe_ifunc {
     (
         $memchrty:ident,
         $memchrfind:ident,
+        $resolved:ident,
         $fnty:ty,
         $retty:ty,
         $hay_start:ident,
@@ -111,31 +117,41 @@ macro_rules! unsafe_ifunc {
             $hay_start: *const u8,
             $hay_end: *const u8,
         ) -> $retty {
-            let fun = {
+            use crate::Implementation;
+
+            let (fun, imp) = {
                 #[cfg(not(target_feature = "sse2"))]
                 {
                     debug!(
                         "no sse2 feature available, using fallback for {}",
                         stringify!($memchrty),
                     );
-                    find_fallback as RealFn
+                    (find_fallback as RealFn, Implementation::Fallback)
                 }
                 #[cfg(target_feature = "sse2")]
                 {
-                    use crate::arch::x86_64::{sse2, avx2};
-                    if avx2::memchr::$memchrty::is_available() {
+                    use crate::{
+                        arch::x86_64::{sse2, avx2},
+                        dispatch::is_permitted,
+                    };
+                    if avx2::memchr::$memchrty::is_available()
+                        && is_permitted(Implementation::Avx2)
+                    {
                         debug!("chose AVX2 for {}", stringify!($memchrty));
-                        find_avx2 as RealFn
-                    } else if sse2::memchr::$memchrty::is_available() {
+                        (find_avx2 as RealFn, Implementation::Avx2)
+                    } else if sse2::memchr::$memchrty::is_available()
+                        && is_permitted(Implementation::Sse2)
+                    {
                         debug!("chose SSE2 for {}", stringify!($memchrty));
-                        find_sse2 as RealFn
+                        (find_sse2 as RealFn, Implementation::Sse2)
                     } else {
                         debug!("chose fallback for {}", stringify!($memchrty));
-                        find_fallback as RealFn
+                        (find_fallback as RealFn, Implementation::Fallback)
                     }
                 }
             };
             FN.store(fun as Fn, Ordering::Relaxed);
+            $resolved.set(imp);
             // SAFETY: The only thing we need to uphold here is the
             // `#[target_feature]` requirements. Since we check is_available
             // above before using the corresponding implementation, we are
@@ -165,6 +181,16 @@ macro_rules! unsafe_ifunc {
 // choice into a global function pointer. Subsequent invocations then just call
 // the appropriate function directly.
 
+// The implementations chosen by each of the routines below. The ones for the
+// forward routines are reported by `crate::dispatch_info`.
+pub(crate) static MEMCHR: Resolved = Resolved::new();
+static MEMRCHR: Resolved = Resolved::new();
+pub(crate) static MEMCHR2: Resolved = Resolved::new();
+static MEMRCHR2: Resolved = Resolved::new();
+pub(crate) static MEMCHR3: Resolved = Resolved::new();
+static MEMRCHR3: Resolved = Resolved::new();
+static COUNT: Resolved = Resolved::new();
+
 /// memchr, but using raw pointers to represent the haystack.
 ///
 /// # Safety
@@ -180,6 +206,7 @@ pub(crate) unsafe fn memchr_raw(
     unsafe_ifunc!(
         One,
         find_raw,
+        MEMCHR,
         unsafe fn(u8, *const u8, *const u8) -> Option<*const u8>,
         Option<*const u8>,
         start,
@@ -203,6 +230,7 @@ pub(crate) unsafe fn memrchr_raw(
     unsafe_ifunc!(
         One,
         rfind_raw,
+        MEMRCHR,
         unsafe fn(u8, *const u8, *const u8) -> Option<*const u8>,
         Option<*const u8>,
         start,
@@ -227,6 +255,7 @@ pub(crate) unsafe fn memchr2_raw(
     unsafe_ifunc!(
         Two,
         find_raw,
+        MEMCHR2,
         unsafe fn(u8, u8, *const u8, *const u8) -> Option<*const u8>,
         Option<*const u8>,
         start,
@@ -252,6 +281,7 @@ pub(crate) unsafe fn memrchr2_raw(
     unsafe_ifunc!(
         Two,
         rfind_raw,
+        MEMRCHR2,
         unsafe fn(u8, u8, *const u8, *const u8) -> Option<*const u8>,
         Option<*const u8>,
         start,
@@ -278,6 +308,7 @@ pub(crate) unsafe fn memchr3_raw(
     unsafe_ifunc!(
         Three,
         find_raw,
+        MEMCHR3,
         unsafe fn(u8, u8, u8, *const u8, *const u8) -> Option<*const u8>,
         Option<*const u8>,
         start,
@@ -305,6 +336,7 @@ pub(crate) unsafe fn memrchr3_raw(
     unsafe_ifunc!(
         Three,
         rfind_raw,
+        MEMRCHR3,
         unsafe fn(u8, u8, u8, *const u8, *const u8) -> Option<*const u8>,
         Option<*const u8>,
         start,
@@ -330,6 +362,7 @@ pub(crate) unsafe fn count_raw(
     unsafe_ifunc!(
         One,
         count_raw,
+        COUNT,
         unsafe fn(u8, *const u8, *const u8) -> usize,
         usize,
         start,
@@ -5,4 +5,6 @@ Vector algorithms for the `x86_64` target.
 pub mod avx2;
 pub mod sse2;
 
+#[cfg(any(test, not(feature = "std")))]
+pub(crate) mod cpuid;
 pub(crate) mod memchr;
@@ -10,7 +10,10 @@ frequencies to heuristically select the pair of bytes to search for.
 
 use core::arch::x86_64::__m128i;
 
-use crate::arch::{all::packedpair::Pair, generic::packedpair};
+use crate::arch::{
+    all::packedpair::{Pair, Triple},
+    generic::packedpair,
+};
 
 /// A "packed pair" finder that uses 128-bit vector operations.
 ///
@@ -175,6 +178,116 @@ impl Finder {
     }
 }
 
+/// A "packed triple" prefilter that uses 128-bit vector operations.
+///
+/// This is like [`Finder`], except that it looks for three bytes from the
+/// needle instead of two, and only reports candidates. It is meant for
+/// haystacks in which even the rarest pair of bytes from the needle is
+/// common, like base64 or DNA sequences. Candidates are reported whenever
+/// the [`Triple`] of bytes given matches.
+#[derive(Clone, Copy, Debug)]
+pub struct TripleFinder(packedpair::TripleFinder<__m128i>);
+
+impl TripleFinder {
+    /// Create a new triple prefilter.
+    ///
+    /// If SSE2 is unavailable in the current environment or if a [`Triple`]
+    /// could not be constructed from the needle given, then `None` is
+    /// returned.
+    #[inline]
+    pub fn new(needle: &[u8]) -> Option<TripleFinder> {
+        TripleFinder::with_triple(needle, Triple::new(needle)?)
+    }
+
+    /// Create a new triple prefilter using the triple of bytes given.
+    ///
+    /// If SSE2 is unavailable in the current environment or if the triple
+    /// is invalid for the needle given, then `None` is returned.
+    #[inline]
+    pub fn with_triple(needle: &[u8], triple: Triple) -> Option<TripleFinder> {
+        let (i1, i2, i3) = (triple.index1(), triple.index2(), triple.index3());
+        let triple = Triple::with_indices(needle, i1, i2, i3)?;
+        if TripleFinder::is_available() {
+            // SAFETY: we check that sse2 is available above, and that the
+            // triple is valid for the needle.
+            unsafe { Some(TripleFinder::with_triple_impl(needle, triple)) }
+        } else {
+            None
+        }
+    }
+
+    /// Create a new `TripleFinder` specific to SSE2 vectors and routines.
+    ///
+    /// # Safety
+    ///
+    /// Same as the safety for `packedpair::TripleFinder::new`, and callers
+    /// must also ensure that SSE2 is available.
+    #[target_feature(enable = "sse2")]
+    #[inline]
+    unsafe fn with_triple_impl(needle: &[u8], triple: Triple) -> TripleFinder {
+        TripleFinder(packedpair::TripleFinder::<__m128i>::new(needle, triple))
+    }
+
+    /// Returns true when this implementation is available in the current
+    /// environment.
+    ///
+    /// When this is true, it is guaranteed that [`TripleFinder::with_triple`]
+    /// will return a `Some` value for a valid triple.
+    #[inline]
+    pub fn is_available() -> bool {
+        Finder::is_available()
+    }
+
+    /// Run this prefilter on the given haystack.
+    ///
+    /// If a candidate match is found, then an offset where the needle *could*
+    /// begin in the haystack is returned.
+    ///
+    /// # Panics
+    ///
+    /// When `haystack.len()` is less than [`TripleFinder::min_haystack_len`].
+    #[inline]
+    pub fn find_prefilter(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: Building a `TripleFinder` means it's safe to call 'sse2'
+        // routines.
+        unsafe { self.find_prefilter_impl(haystack) }
+    }
+
+    /// Execute a prefilter search using SSE2 vectors and routines.
+    ///
+    /// # Panics
+    ///
+    /// When `haystack.len()` is less than [`TripleFinder::min_haystack_len`].
+    ///
+    /// # Safety
+    ///
+    /// (The target feature safety obligation is automatically fulfilled by
+    /// virtue of being a method on `TripleFinder`, which can only be
+    /// constructed when it is safe to call `sse2` routines.)
+    #[target_feature(enable = "sse2")]
+    #[inline]
+    unsafe fn find_prefilter_impl(&self, haystack: &[u8]) -> Option<usize> {
+        self.0.find_prefilter(haystack)
+    }
+
+    /// Returns the triple of offsets (into the needle) that this prefilter
+    /// looks for.
+    #[inline]
+    pub fn triple(&self) -> &Triple {
+        self.0.triple()
+    }
+
+    /// Returns the minimum haystack length that this `TripleFinder` can
+    /// search.
+    ///
+    /// Using a haystack with length smaller than this in a search will result
+    /// in a panic.
+    #[inline]
+    pub fn min_haystack_len(&self) -> usize {
+        self.0.min_haystack_len()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,6 +302,21 @@ mod tests {
 
     define_substring_forward_quickcheck!(find);
 
+    fn find_triple(haystack: &[u8], needle: &[u8]) -> Option<Option<usize>> {
+        let f = TripleFinder::new(needle)?;
+        Some(crate::tests::packedpair::find_with_prefilter(
+            haystack,
+            needle,
+            f.min_haystack_len(),
+            |h| f.find_prefilter(h),
+        ))
+    }
+
+    #[test]
+    fn forward_substring_triple_prefilter() {
+        crate::tests::substring::Runner::new().fwd(find_triple).run()
+    }
+
     #[test]
     fn forward_substring() {
         crate::tests::substring::Runner::new().fwd(find).run()
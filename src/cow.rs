@@ -40,6 +40,12 @@ impl<'a> CowBytes<'a> {
         CowBytes(Imp::new(bytes.as_ref()))
     }
 
+    /// Create a new borrowed CowBytes from a slice in a `const` context.
+    #[inline(always)]
+    pub(crate) const fn from_slice(bytes: &'a [u8]) -> CowBytes<'a> {
+        CowBytes(Imp::new(bytes))
+    }
+
     /// Create a new owned CowBytes.
     #[cfg(feature = "alloc")]
     #[inline(always)]
@@ -74,7 +80,7 @@ impl<'a> CowBytes<'a> {
 
 impl<'a> Imp<'a> {
     #[inline(always)]
-    pub fn new(bytes: &'a [u8]) -> Imp<'a> {
+    pub const fn new(bytes: &'a [u8]) -> Imp<'a> {
         #[cfg(feature = "alloc")]
         {
             Imp::Borrowed(bytes)
@@ -0,0 +1,652 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// An implementation of a search routine that may be chosen at runtime.
+///
+/// The top-level routines in this crate, such as [`memchr`](crate::memchr())
+/// and [`memmem::Finder`](crate::memmem::Finder), dispatch to the best
+/// implementation available for the current target. On some targets, like
+/// `x86_64`, this choice depends on the CPU features detected at runtime.
+/// This type describes which implementation was chosen.
+///
+/// This is principally useful for debugging performance problems. For
+/// example, a search that is unexpectedly slow might be using the portable
+/// fallback implementation because a vector implementation isn't available.
+///
+/// See [`dispatch_info`] for the implementations used by the `memchr`
+/// family of routines, and [`memmem::Finder::strategy`] for the strategy
+/// used by a substring searcher.
+///
+/// [`memmem::Finder::strategy`]: crate::memmem::Finder::strategy
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Implementation {
+    /// The portable implementation that works on all targets. It doesn't
+    /// use any explicit vector instructions.
+    Fallback,
+    /// An implementation using `x86_64` SSE2 vector instructions.
+    Sse2,
+    /// An implementation using `x86_64` AVX2 vector instructions.
+    Avx2,
+    /// An implementation using `wasm32` simd128 vector instructions.
+    Simd128,
+    /// An implementation using `aarch64` neon vector instructions.
+    Neon,
+}
+
+impl Implementation {
+    /// Returns true if this implementation uses vector instructions.
+    #[inline]
+    pub fn is_vector(&self) -> bool {
+        !matches!(*self, Implementation::Fallback)
+    }
+
+    /// Returns the level of this implementation. Dispatch caps compare
+    /// implementations by their level.
+    fn level(&self) -> u8 {
+        match *self {
+            Implementation::Fallback => 0,
+            Implementation::Sse2
+            | Implementation::Simd128
+            | Implementation::Neon => 1,
+            Implementation::Avx2 => 2,
+        }
+    }
+
+    /// Returns the implementation with the given name, ignoring case.
+    #[cfg(feature = "std")]
+    fn from_name(name: &str) -> Option<Implementation> {
+        let all = [
+            ("fallback", Implementation::Fallback),
+            ("sse2", Implementation::Sse2),
+            ("avx2", Implementation::Avx2),
+            ("simd128", Implementation::Simd128),
+            ("neon", Implementation::Neon),
+        ];
+        all.iter()
+            .find(|&&(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, imp)| imp)
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Implementation::Fallback => 0,
+            Implementation::Sse2 => 1,
+            Implementation::Avx2 => 2,
+            Implementation::Simd128 => 3,
+            Implementation::Neon => 4,
+        }
+    }
+
+    fn from_u8(n: u8) -> Option<Implementation> {
+        match n {
+            0 => Some(Implementation::Fallback),
+            1 => Some(Implementation::Sse2),
+            2 => Some(Implementation::Avx2),
+            3 => Some(Implementation::Simd128),
+            4 => Some(Implementation::Neon),
+            _ => None,
+        }
+    }
+}
+
+/// The current dispatch cap, as set by `set_dispatch_cap` or the
+/// `MEMCHR_FORCE` environment variable.
+///
+/// This is either an `Implementation` encoded by `Implementation::to_u8`,
+/// `CAP_NONE` or `CAP_UNINIT`. The latter indicates that the environment
+/// hasn't been consulted yet.
+static CAP: AtomicU8 = AtomicU8::new(CAP_UNINIT);
+
+/// Indicates that there is no dispatch cap.
+const CAP_NONE: u8 = 254;
+
+/// Indicates that the dispatch cap hasn't been initialized yet.
+const CAP_UNINIT: u8 = 255;
+
+/// Sets the most capable implementation that the routines in this crate are
+/// permitted to dispatch to.
+///
+/// This is intended for testing and benchmarking. For example, it permits
+/// running the SSE2 or fallback implementations on a CPU that supports AVX2.
+/// When `None` is given, the cap is removed and the best implementation
+/// available is used.
+///
+/// Implementations are compared by their capability. On `x86_64`, a cap of
+/// [`Implementation::Avx2`] permits every implementation, a cap of
+/// [`Implementation::Sse2`] permits SSE2 and the fallback, and a cap of
+/// [`Implementation::Fallback`] only permits the fallback. On other targets,
+/// only a cap of [`Implementation::Fallback`] has any effect.
+///
+/// The cap can also be set with the `MEMCHR_FORCE` environment variable,
+/// which is consulted the first time the cap is needed (when the `std`
+/// feature is enabled). Its value should be the name of an implementation,
+/// such as `fallback`, `sse2` or `avx2`. A cap set with this function takes
+/// precedence over the environment.
+///
+/// # Limitations
+///
+/// The top-level `memchr` family of routines (including counting) chooses
+/// an implementation the first time each routine is called, and never
+/// changes it after that. So this should be called before executing any
+/// searches, typically at the start of a program. [`dispatch_info`] reports
+/// what each routine chose. A cap always applies to
+/// [`memmem::Finder`](crate::memmem::Finder) values built after it is set.
+///
+/// On `aarch64` and `wasm32`, the `memchr` family of routines always uses
+/// its vector implementation, since it doesn't choose one at runtime.
+///
+/// # Example
+///
+/// ```
+/// use memchr::{memmem::Finder, Implementation};
+///
+/// memchr::set_dispatch_cap(Some(Implementation::Fallback));
+/// assert_eq!(Some(2), memchr::memchr(b'c', b"abc"));
+/// if cfg!(target_arch = "x86_64") {
+///     let imp = memchr::dispatch_info().memchr();
+///     assert_eq!(Some(Implementation::Fallback), imp);
+/// }
+/// assert!(!memchr::dispatch_info().memmem().is_vector());
+/// assert_eq!(Some(4), Finder::new("bar").find(b"foo bar"));
+/// ```
+#[inline]
+pub fn set_dispatch_cap(cap: Option<Implementation>) {
+    CAP.store(cap.map_or(CAP_NONE, Implementation::to_u8), Ordering::Relaxed);
+}
+
+/// Returns the current dispatch cap, if one is set.
+///
+/// See [`set_dispatch_cap`] for more details.
+#[inline]
+pub fn dispatch_cap() -> Option<Implementation> {
+    let cap = CAP.load(Ordering::Relaxed);
+    #[cfg(feature = "std")]
+    let cap = if cap == CAP_UNINIT {
+        let env = cap_from_env().map_or(CAP_NONE, Implementation::to_u8);
+        // If the cap was set concurrently, then that takes precedence.
+        match CAP.compare_exchange(
+            CAP_UNINIT,
+            env,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => env,
+            Err(cap) => cap,
+        }
+    } else {
+        cap
+    };
+    Implementation::from_u8(cap)
+}
+
+/// Returns true when the given implementation is permitted by the current
+/// dispatch cap.
+#[inline]
+pub(crate) fn is_permitted(imp: Implementation) -> bool {
+    permits(dispatch_cap(), imp)
+}
+
+/// Returns true when the given implementation is permitted by the given
+/// dispatch cap.
+fn permits(cap: Option<Implementation>, imp: Implementation) -> bool {
+    cap.map_or(true, |cap| imp.level() <= cap.level())
+}
+
+/// Reads the dispatch cap from the `MEMCHR_FORCE` environment variable.
+#[cfg(feature = "std")]
+fn cap_from_env() -> Option<Implementation> {
+    let value = std::env::var_os("MEMCHR_FORCE")?;
+    let cap = value.to_str().and_then(Implementation::from_name);
+    if cap.is_none() {
+        debug!("ignoring unrecognized MEMCHR_FORCE value {:?}", value);
+    }
+    cap
+}
+
+/// A set of optional CPU features that the routines in this crate may use.
+///
+/// Features that are enabled at compile time (for example, with
+/// `-C target-feature=+avx2`) are always used, regardless of what this
+/// reports. Otherwise, the features are detected at runtime the first time
+/// they are needed. When the `std` feature is enabled, this uses the
+/// standard library's detection. Otherwise, on `x86_64`, the CPU is queried
+/// directly with the `cpuid` instruction.
+///
+/// Environments with their own means of detecting CPU features, such as
+/// kernels or firmware, can provide them with [`set_cpu_features`] instead.
+///
+/// Currently, only AVX2 on `x86_64` is detected at runtime. On `aarch64`,
+/// the neon implementation is chosen at compile time, since neon is part of
+/// the baseline for every target that permits its use. (Reading the
+/// `aarch64` feature registers generally isn't permitted outside of the
+/// kernel, so no runtime detection is attempted there.)
+///
+/// # Example
+///
+/// ```
+/// use memchr::CpuFeatures;
+///
+/// let features = memchr::cpu_features();
+/// println!("AVX2 available: {:?}", features.has_avx2());
+///
+/// let none = CpuFeatures::new();
+/// assert!(!none.has_avx2());
+/// assert!(none.avx2(true).has_avx2());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct CpuFeatures {
+    avx2: bool,
+}
+
+impl CpuFeatures {
+    /// Create a set of CPU features that doesn't contain any optional
+    /// features.
+    #[inline]
+    pub fn new() -> CpuFeatures {
+        CpuFeatures::default()
+    }
+
+    /// Detect the CPU features of the current environment.
+    ///
+    /// This always runs detection, and ignores any features set with
+    /// [`set_cpu_features`]. Use [`cpu_features`] to get the features that
+    /// are actually used by this crate.
+    #[inline]
+    pub fn detect() -> CpuFeatures {
+        CpuFeatures { avx2: detect_avx2() }
+    }
+
+    /// Set whether AVX2 is available.
+    ///
+    /// This only has an effect on `x86_64` targets.
+    #[inline]
+    pub fn avx2(mut self, yes: bool) -> CpuFeatures {
+        self.avx2 = yes;
+        self
+    }
+
+    /// Returns true if AVX2 is available.
+    #[inline]
+    pub fn has_avx2(&self) -> bool {
+        self.avx2
+    }
+
+    fn to_u8(self) -> u8 {
+        let mut bits = 0;
+        if self.avx2 {
+            bits |= FEATURE_AVX2;
+        }
+        bits
+    }
+
+    fn from_u8(bits: u8) -> CpuFeatures {
+        CpuFeatures { avx2: bits & FEATURE_AVX2 != 0 }
+    }
+}
+
+/// The CPU features used by this crate, as set by `set_cpu_features` or
+/// detected at runtime.
+///
+/// This is either a `CpuFeatures` encoded by `CpuFeatures::to_u8` or
+/// `FEATURES_UNINIT`. The latter indicates that detection hasn't run yet.
+static FEATURES: AtomicU8 = AtomicU8::new(FEATURES_UNINIT);
+
+/// Indicates that the CPU features haven't been detected yet.
+const FEATURES_UNINIT: u8 = 255;
+
+/// The bit set when AVX2 is available.
+const FEATURE_AVX2: u8 = 1 << 0;
+
+/// Sets the CPU features that the routines in this crate may use, instead of
+/// detecting them at runtime.
+///
+/// This is principally useful in `no_std` environments that have their own
+/// means of detecting CPU features. It can also be used to disable a
+/// feature, although [`set_dispatch_cap`] is usually a better fit for that.
+///
+/// Like [`set_dispatch_cap`], the top-level `memchr` family of routines
+/// chooses an implementation the first time each routine is called. So this
+/// should be called before executing any searches.
+///
+/// # Safety
+///
+/// Every feature reported as available must actually be supported by the
+/// CPU and enabled by the operating system. Otherwise, executing a search
+/// may result in undefined behavior, such as executing an illegal
+/// instruction.
+///
+/// # Example
+///
+/// ```
+/// use memchr::CpuFeatures;
+///
+/// // Claiming that a feature is available is unsafe, but saying that it
+/// // isn't is always fine.
+/// let features = CpuFeatures::detect().avx2(false);
+/// // SAFETY: No features are reported beyond what was detected.
+/// unsafe { memchr::set_cpu_features(features) };
+/// assert!(!memchr::cpu_features().has_avx2());
+/// ```
+#[inline]
+pub unsafe fn set_cpu_features(features: CpuFeatures) {
+    FEATURES.store(features.to_u8(), Ordering::Relaxed);
+}
+
+/// Returns the CPU features that the routines in this crate may use.
+///
+/// These are either the features given to [`set_cpu_features`], or
+/// otherwise, the features detected at runtime. Detection only runs the
+/// first time this is called.
+#[inline]
+pub fn cpu_features() -> CpuFeatures {
+    let mut bits = FEATURES.load(Ordering::Relaxed);
+    if bits == FEATURES_UNINIT {
+        let detected = CpuFeatures::detect().to_u8();
+        // If the features were set concurrently, then those take precedence.
+        bits = match FEATURES.compare_exchange(
+            FEATURES_UNINIT,
+            detected,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => detected,
+            Err(bits) => bits,
+        };
+    }
+    CpuFeatures::from_u8(bits)
+}
+
+/// Detects whether AVX2 is available at runtime.
+fn detect_avx2() -> bool {
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    {
+        std::is_x86_feature_detected!("avx2")
+    }
+    #[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+    {
+        crate::arch::x86_64::cpuid::is_avx2_detected()
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        false
+    }
+}
+
+/// The implementation that a routine chose at runtime.
+///
+/// This is set by a routine the first time it's called, and holds
+/// `UNRESOLVED` until then.
+#[cfg(target_arch = "x86_64")]
+#[derive(Debug)]
+pub(crate) struct Resolved(AtomicU8);
+
+/// Indicates that a routine hasn't chosen an implementation yet.
+#[cfg(target_arch = "x86_64")]
+const UNRESOLVED: u8 = 255;
+
+#[cfg(target_arch = "x86_64")]
+impl Resolved {
+    pub(crate) const fn new() -> Resolved {
+        Resolved(AtomicU8::new(UNRESOLVED))
+    }
+
+    pub(crate) fn set(&self, imp: Implementation) {
+        self.0.store(imp.to_u8(), Ordering::Relaxed);
+    }
+
+    fn get(&self) -> Option<Implementation> {
+        Implementation::from_u8(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// A description of the implementations that the top-level routines in this
+/// crate dispatch to.
+///
+/// This is created by [`dispatch_info`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DispatchInfo {
+    memchr: Option<Implementation>,
+    memchr2: Option<Implementation>,
+    memchr3: Option<Implementation>,
+    memmem: Implementation,
+}
+
+impl DispatchInfo {
+    /// Returns the implementation used by [`memchr`](crate::memchr()), or
+    /// `None` if it hasn't chosen one yet.
+    ///
+    /// [`memrchr`](crate::memrchr()) and the corresponding iterators choose
+    /// an implementation in the same way, but independently.
+    #[inline]
+    pub fn memchr(&self) -> Option<Implementation> {
+        self.memchr
+    }
+
+    /// Returns the implementation used by [`memchr2`](crate::memchr2()), or
+    /// `None` if it hasn't chosen one yet.
+    ///
+    /// [`memrchr2`](crate::memrchr2()) and the corresponding iterators
+    /// choose an implementation in the same way, but independently.
+    #[inline]
+    pub fn memchr2(&self) -> Option<Implementation> {
+        self.memchr2
+    }
+
+    /// Returns the implementation used by [`memchr3`](crate::memchr3()), or
+    /// `None` if it hasn't chosen one yet.
+    ///
+    /// [`memrchr3`](crate::memrchr3()) and the corresponding iterators
+    /// choose an implementation in the same way, but independently.
+    #[inline]
+    pub fn memchr3(&self) -> Option<Implementation> {
+        self.memchr3
+    }
+
+    /// Returns the vector implementation that a
+    /// [`memmem::Finder`](crate::memmem::Finder) built now uses when it
+    /// chooses a vector algorithm.
+    ///
+    /// Whether a vector algorithm is actually used depends on the needle.
+    /// Use [`memmem::Finder::strategy`](crate::memmem::Finder::strategy) to
+    /// find out what a particular finder uses. When this returns
+    /// [`Implementation::Fallback`], substring search never uses a vector
+    /// algorithm.
+    #[inline]
+    pub fn memmem(&self) -> Implementation {
+        self.memmem
+    }
+}
+
+/// Returns the implementations that the top-level routines in this crate
+/// dispatch to on the current target.
+///
+/// On `x86_64`, each of the `memchr` family of routines chooses an
+/// implementation the first time it's called, based on the CPU features
+/// available (see [`cpu_features`]) and the dispatch cap at that time (see
+/// [`set_dispatch_cap`]). The implementation reported for a routine is the
+/// one it chose, or `None` if it hasn't been called yet. On other targets,
+/// the implementation is chosen at compile time, so it's always reported.
+///
+/// Substring search instead chooses an implementation every time a finder
+/// is built, so [`DispatchInfo::memmem`] reflects the current dispatch cap.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(Some(1), memchr::memchr(b'b', b"abc"));
+///
+/// let info = memchr::dispatch_info();
+/// assert!(info.memchr().is_some());
+/// println!("memchr uses {:?}", info.memchr());
+/// println!("memmem uses {:?}", info.memmem());
+/// ```
+#[inline]
+pub fn dispatch_info() -> DispatchInfo {
+    DispatchInfo {
+        memchr: memchr_implementation(1),
+        memchr2: memchr_implementation(2),
+        memchr3: memchr_implementation(3),
+        memmem: memmem_implementation(),
+    }
+}
+
+/// Returns the implementation used for searching for the given number of
+/// bytes, if one has been chosen.
+fn memchr_implementation(count: usize) -> Option<Implementation> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        use crate::arch::x86_64::memchr::{MEMCHR, MEMCHR2, MEMCHR3};
+
+        match count {
+            1 => MEMCHR.get(),
+            2 => MEMCHR2.get(),
+            _ => MEMCHR3.get(),
+        }
+    }
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    {
+        let _ = count;
+        Some(Implementation::Simd128)
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        let _ = count;
+        Some(Implementation::Neon)
+    }
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        all(target_arch = "wasm32", target_feature = "simd128"),
+        target_arch = "aarch64"
+    )))]
+    {
+        let _ = count;
+        Some(Implementation::Fallback)
+    }
+}
+
+/// Returns the vector implementation used for substring search.
+fn memmem_implementation() -> Implementation {
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    {
+        use crate::arch::x86_64::{avx2, sse2};
+
+        if avx2::packedpair::Finder::is_available()
+            && is_permitted(Implementation::Avx2)
+        {
+            Implementation::Avx2
+        } else if sse2::packedpair::Finder::is_available()
+            && is_permitted(Implementation::Sse2)
+        {
+            Implementation::Sse2
+        } else {
+            Implementation::Fallback
+        }
+    }
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    {
+        if crate::arch::wasm32::simd128::packedpair::Finder::is_available()
+            && is_permitted(Implementation::Simd128)
+        {
+            Implementation::Simd128
+        } else {
+            Implementation::Fallback
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if crate::arch::aarch64::neon::packedpair::Finder::is_available()
+            && is_permitted(Implementation::Neon)
+        {
+            Implementation::Neon
+        } else {
+            Implementation::Fallback
+        }
+    }
+    #[cfg(not(any(
+        all(target_arch = "x86_64", target_feature = "sse2"),
+        all(target_arch = "wasm32", target_feature = "simd128"),
+        target_arch = "aarch64"
+    )))]
+    {
+        Implementation::Fallback
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cap() {
+        use self::Implementation::*;
+
+        for imp in [Fallback, Sse2, Avx2, Simd128, Neon] {
+            assert!(permits(None, imp));
+            assert!(permits(Some(Avx2), imp));
+            assert_eq!(imp == Fallback, permits(Some(Fallback), imp));
+            assert_eq!(imp != Avx2, permits(Some(Sse2), imp));
+            assert_eq!(Some(imp), Implementation::from_u8(imp.to_u8()));
+        }
+        assert_eq!(None, Implementation::from_u8(CAP_NONE));
+        assert_eq!(None, Implementation::from_u8(CAP_UNINIT));
+    }
+
+    #[test]
+    fn features() {
+        for avx2 in [false, true] {
+            let features = CpuFeatures::new().avx2(avx2);
+            assert_eq!(avx2, features.has_avx2());
+            assert_eq!(features, CpuFeatures::from_u8(features.to_u8()));
+            assert_ne!(FEATURES_UNINIT, features.to_u8());
+        }
+        assert_eq!(CpuFeatures::detect(), cpu_features());
+    }
+
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    #[test]
+    fn features_x86_64() {
+        use crate::arch::x86_64::avx2;
+
+        let avx2 = cpu_features().has_avx2() || cfg!(target_feature = "avx2");
+        assert_eq!(avx2, avx2::memchr::One::is_available());
+        assert_eq!(avx2, avx2::packedpair::Finder::is_available());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn names() {
+        assert_eq!(
+            Some(Implementation::Avx2),
+            Implementation::from_name("avx2")
+        );
+        assert_eq!(
+            Some(Implementation::Sse2),
+            Implementation::from_name("SSE2")
+        );
+        assert_eq!(
+            Some(Implementation::Fallback),
+            Implementation::from_name("Fallback")
+        );
+        assert_eq!(None, Implementation::from_name("avx512"));
+        assert_eq!(None, Implementation::from_name(""));
+    }
+
+    #[test]
+    fn consistent() {
+        assert_eq!(Some(0), crate::memchr(b'a', b"a"));
+        assert_eq!(Some(0), crate::memchr2(b'a', b'b', b"a"));
+        assert_eq!(Some(0), crate::memchr3(b'a', b'b', b'c', b"a"));
+        let info = dispatch_info();
+        assert_eq!(info, dispatch_info());
+        assert!(info.memchr().is_some());
+        #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+        {
+            if dispatch_cap().is_none() {
+                assert!(info.memchr().unwrap().is_vector());
+            }
+            assert_eq!(info.memchr(), info.memchr2());
+            assert_eq!(info.memchr(), info.memchr3());
+            assert_eq!(info.memchr(), Some(info.memmem()));
+        }
+    }
+}
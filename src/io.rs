@@ -0,0 +1,467 @@
+/*!
+This module provides routines for searching streams.
+
+The routines in this module read from a stream in fixed size blocks and search
+each block with the same vectorized routines that are used for searching
+slices. This makes it possible to search arbitrarily large streams (such as
+files) without reading them entirely into memory.
+
+This module is only available when the `std` feature is enabled.
+
+When the `futures-io` feature is enabled, this module also provides adapters
+for searching asynchronous streams that implement `AsyncBufRead`:
+`find_async`, `read_until_seq` and `SplitSeq`. These find matches that
+straddle the boundary between two reads from the stream, which is useful for
+parsing protocols framed by multi-byte delimiters. Users of `tokio` can use
+these via the compatibility layer in `tokio-util`. When used with a finder
+that only reports whole word matches, word boundaries are determined by the
+bytes on either side of each match, even when they come from different reads.
+
+# Example: finding the last lines of a file
+
+This example shows how to find the offset at which the last two lines of a
+stream begin, similar to `tail -n 2`. Since the search proceeds backwards
+from the end of the stream, only the end of the stream is read.
+
+```
+use std::io::Cursor;
+
+use memchr::io::MemrchrReader;
+
+let rdr = Cursor::new(b"line 1\nline 2\nline 3\nline 4\n".to_vec());
+let mut it = MemrchrReader::new(b'\n', rdr);
+// Skip the line terminator at the very end.
+assert_eq!(27, it.next().unwrap()?);
+assert_eq!(20, it.next().unwrap()?);
+// The last two lines begin just after this line terminator.
+assert_eq!(13, it.next().unwrap()?);
+# Ok::<(), std::io::Error>(())
+```
+*/
+
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    vec::Vec,
+};
+
+use crate::memmem::FinderRev;
+
+#[cfg(feature = "futures-io")]
+pub use crate::io::futures::{
+    find_async, read_until_seq, FindAsync, ReadUntilSeq, SplitSeq,
+};
+
+#[cfg(feature = "futures-io")]
+mod futures;
+
+/// The default size of each block read from a stream, in bytes.
+const DEFAULT_CAPACITY: usize = 64 * (1 << 10);
+
+/// A reverse iterator over all occurrences of a single byte in a seekable
+/// stream.
+///
+/// This reads fixed size blocks starting from the end of the stream and
+/// searches each with [`memrchr`](crate::memrchr). Occurrences are reported
+/// by their absolute offset in the stream, in descending order.
+///
+/// The end of the stream is determined by seeking to its end when iteration
+/// begins. The position of the stream after iteration is unspecified.
+///
+/// If an I/O error occurs, then it is yielded and iteration stops.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use memchr::io::MemrchrReader;
+///
+/// let rdr = Cursor::new(b"a,b,c".to_vec());
+/// let offsets = MemrchrReader::with_capacity(2, b',', rdr)
+///     .collect::<Result<Vec<u64>, _>>()?;
+/// assert_eq!(offsets, vec![3, 1]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct MemrchrReader<R> {
+    needle1: u8,
+    rdr: RevBlockReader<R>,
+}
+
+impl<R: Read + Seek> MemrchrReader<R> {
+    /// Create a new reverse searcher for the given byte in the given stream.
+    ///
+    /// This uses a default block size of 64 KiB.
+    pub fn new(needle1: u8, rdr: R) -> MemrchrReader<R> {
+        MemrchrReader::with_capacity(DEFAULT_CAPACITY, needle1, rdr)
+    }
+
+    /// Create a new reverse searcher for the given byte in the given stream,
+    /// which reads blocks of at most `capacity` bytes at a time.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `capacity` is `0`.
+    pub fn with_capacity(
+        capacity: usize,
+        needle1: u8,
+        rdr: R,
+    ) -> MemrchrReader<R> {
+        let rdr = RevBlockReader::new(capacity, 1, false, rdr);
+        MemrchrReader { needle1, rdr }
+    }
+
+    /// Consumes this searcher and returns the underlying stream.
+    pub fn into_inner(self) -> R {
+        self.rdr.rdr
+    }
+}
+
+impl<R: Read + Seek> Iterator for MemrchrReader<R> {
+    type Item = io::Result<u64>;
+
+    fn next(&mut self) -> Option<io::Result<u64>> {
+        let needle1 = self.needle1;
+        self.rdr.next(|buf, end| crate::memrchr(needle1, &buf[..end]))
+    }
+}
+
+/// A reverse iterator over all non-overlapping occurrences of a substring in
+/// a seekable stream.
+///
+/// This reads fixed size blocks starting from the end of the stream and
+/// searches each with a [`FinderRev`]. Matches that straddle the boundary
+/// between two blocks are found by carrying the first `needle.len() - 1`
+/// bytes of each block over to the search of the preceding block. Matches
+/// are reported by their absolute starting offset in the stream, in
+/// descending order. The matches reported are exactly the same as the ones
+/// reported by [`FinderRev::rfind_iter`] on the entire contents of the
+/// stream. In particular, when the finder only reports whole word matches,
+/// word boundaries are determined by the bytes on either side of each match
+/// even when they belong to a different block.
+///
+/// The end of the stream is determined by seeking to its end when iteration
+/// begins. The position of the stream after iteration is unspecified.
+///
+/// If an I/O error occurs, then it is yielded and iteration stops.
+///
+/// # Example
+///
+/// This finds the last footer in an archive-like stream:
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use memchr::{io::FindRevReader, memmem::FinderRev};
+///
+/// let data = b"DATA..FOOTER:1DATA..FOOTER:2DATA".to_vec();
+/// let finder = FinderRev::new("FOOTER:");
+/// let mut it = FindRevReader::with_capacity(4, finder, Cursor::new(data));
+/// assert_eq!(20, it.next().unwrap()?);
+/// assert_eq!(6, it.next().unwrap()?);
+/// assert!(it.next().is_none());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct FindRevReader<'n, R> {
+    finder: FinderRev<'n>,
+    rdr: RevBlockReader<R>,
+}
+
+impl<'n, R: Read + Seek> FindRevReader<'n, R> {
+    /// Create a new reverse searcher for the needle of the given finder in
+    /// the given stream.
+    ///
+    /// This uses a default block size of 64 KiB.
+    pub fn new(finder: FinderRev<'n>, rdr: R) -> FindRevReader<'n, R> {
+        FindRevReader::with_capacity(DEFAULT_CAPACITY, finder, rdr)
+    }
+
+    /// Create a new reverse searcher for the needle of the given finder in
+    /// the given stream, which reads blocks of at most `capacity` bytes at a
+    /// time.
+    ///
+    /// Note that, in addition to each block, up to `needle.len() - 1` bytes
+    /// from the following block are retained in memory in order to detect
+    /// matches that straddle block boundaries. When the finder only reports
+    /// whole word matches, up to `needle.len() + 1` bytes are retained
+    /// instead.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `capacity` is `0`.
+    pub fn with_capacity(
+        capacity: usize,
+        finder: FinderRev<'n>,
+        rdr: R,
+    ) -> FindRevReader<'n, R> {
+        let (len, lookaround) = (finder.needle().len(), finder.whole_word);
+        FindRevReader {
+            finder,
+            rdr: RevBlockReader::new(capacity, len, lookaround, rdr),
+        }
+    }
+
+    /// Consumes this searcher and returns the underlying stream.
+    pub fn into_inner(self) -> R {
+        self.rdr.rdr
+    }
+}
+
+impl<'n, R: Read + Seek> Iterator for FindRevReader<'n, R> {
+    type Item = io::Result<u64>;
+
+    fn next(&mut self) -> Option<io::Result<u64>> {
+        let finder = &self.finder;
+        self.rdr.next(|buf, end| finder.rfind_to(buf, end))
+    }
+}
+
+/// The block reading state shared by reverse stream searchers.
+///
+/// The buffer always contains the bytes at the absolute offsets
+/// `start..start + buf.len()` of the stream, and only the bytes before
+/// `limit` may be part of a match that hasn't been reported yet.
+#[derive(Debug)]
+struct RevBlockReader<R> {
+    rdr: R,
+    buf: Vec<u8>,
+    capacity: usize,
+    /// The length of the needle being searched for.
+    needle_len: usize,
+    /// The maximum number of bytes to retain from a block when reading the
+    /// block preceding it.
+    carry: usize,
+    /// Set when a match can only be confirmed by looking at the bytes
+    /// immediately before and after it, as with whole word matches. In that
+    /// case, a match at the very beginning of the buffer is only reported
+    /// once the byte preceding it has been read, and the byte at the limit
+    /// is retained along with the bytes before it.
+    lookaround: bool,
+    /// The absolute offset of the beginning of `buf`.
+    start: u64,
+    /// The absolute offset of the end of the stream. This is only valid
+    /// once the end of the stream has been found.
+    end: u64,
+    /// The absolute offset at which the next match must end at or before.
+    /// This is `None` before the end of the stream has been found.
+    limit: Option<u64>,
+    /// Set when iteration has finished, either because the beginning of the
+    /// stream was reached or because an error occurred.
+    done: bool,
+}
+
+impl<R: Read + Seek> RevBlockReader<R> {
+    fn new(
+        capacity: usize,
+        needle_len: usize,
+        lookaround: bool,
+        rdr: R,
+    ) -> RevBlockReader<R> {
+        assert!(capacity > 0, "block capacity must be non-zero");
+        // A match at the beginning of a block is only reported after reading
+        // the preceding block when looking around matches, so we need to
+        // retain all of it and the byte after it.
+        let carry = if lookaround {
+            needle_len + 1
+        } else {
+            needle_len.saturating_sub(1)
+        };
+        RevBlockReader {
+            rdr,
+            buf: Vec::new(),
+            capacity,
+            needle_len,
+            carry,
+            lookaround,
+            start: 0,
+            end: 0,
+            limit: None,
+            done: false,
+        }
+    }
+
+    /// Returns the next match reported by `rfind`, which should return the
+    /// last occurrence in the slice given that ends at or before the offset
+    /// given. Bytes at or after that offset may only be consulted to confirm
+    /// a match.
+    fn next(
+        &mut self,
+        mut rfind: impl FnMut(&[u8], usize) -> Option<usize>,
+    ) -> Option<io::Result<u64>> {
+        if self.done {
+            return None;
+        }
+        match self.try_next(&mut rfind) {
+            Ok(Some(offset)) => Some(Ok(offset)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+
+    fn try_next(
+        &mut self,
+        rfind: &mut impl FnMut(&[u8], usize) -> Option<usize>,
+    ) -> io::Result<Option<u64>> {
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => {
+                let end = self.rdr.seek(SeekFrom::End(0))?;
+                self.start = end;
+                self.end = end;
+                end
+            }
+        };
+        loop {
+            // The limit is only ever before the start of the buffer after an
+            // empty needle matches at the start of the buffer. It may also be
+            // past the end of the buffer, but any match that ends at or before
+            // the limit and that hasn't been reported yet is always entirely
+            // within the buffer.
+            if limit >= self.start {
+                let mut len = u64::try_from(self.buf.len()).unwrap();
+                // Matches that end at the end of the buffer can't be
+                // confirmed unless the buffer ends the stream. Those matches
+                // were already searched before the buffer was truncated.
+                if self.lookaround && self.start + len < self.end {
+                    len -= 1;
+                }
+                let end =
+                    usize::try_from((limit - self.start).min(len)).unwrap();
+                // A match at the beginning of the buffer can't be confirmed
+                // until the byte before it is read. Since it's the earliest
+                // possible match, there is nothing else to search for.
+                let found = rfind(&self.buf, end)
+                    .filter(|&i| !self.lookaround || i > 0 || self.start == 0);
+                if let Some(i) = found {
+                    let offset = self.start + u64::try_from(i).unwrap();
+                    // An empty needle may match at the limit itself, so we
+                    // move the limit back by one to guarantee progress.
+                    if self.needle_len > 0 {
+                        self.limit = Some(offset);
+                    } else if offset == 0 {
+                        self.done = true;
+                    } else {
+                        self.limit = Some(offset - 1);
+                    }
+                    return Ok(Some(offset));
+                }
+            }
+            if self.start == 0 {
+                return Ok(None);
+            }
+            self.read_prev(limit)?;
+            self.limit = Some(limit);
+        }
+    }
+
+    /// Reads the block preceding the current buffer. The first bytes of the
+    /// current buffer that could be part of a match straddling the boundary
+    /// between the blocks are retained after the new block.
+    fn read_prev(&mut self, limit: u64) -> io::Result<()> {
+        let cap = u64::try_from(self.capacity).unwrap();
+        let new_start = self.start.saturating_sub(cap);
+        let len = usize::try_from(self.start - new_start).unwrap();
+        // The distance to the limit may not fit in a `usize` on 32-bit
+        // targets, so we clamp it to the buffer's length first.
+        let buf_len = u64::try_from(self.buf.len()).unwrap();
+        let usable = limit.saturating_sub(self.start).min(buf_len);
+        let usable =
+            usize::try_from(usable).unwrap() + usize::from(self.lookaround);
+        let carry = self.carry.min(usable).min(self.buf.len());
+        self.buf.truncate(carry);
+        self.buf.resize(len + carry, 0);
+        self.buf.copy_within(0..carry, len);
+        self.rdr.seek(SeekFrom::Start(new_start))?;
+        self.rdr.read_exact(&mut self.buf[..len])?;
+        self.start = new_start;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, vec::Vec};
+
+    use crate::memmem::FinderBuilder;
+
+    use super::*;
+
+    #[test]
+    fn memrchr_blocks() {
+        let haystacks: &[&[u8]] =
+            &[b"", b"a", b"ab", b"aaaa", b"xaxxxaxxxxxxa", b"bbbbbbbbbbbb"];
+        for &haystack in haystacks {
+            let expected: Vec<u64> = crate::memrchr_iter(b'a', haystack)
+                .map(|i| u64::try_from(i).unwrap())
+                .collect();
+            for cap in 1..=haystack.len() + 1 {
+                let rdr = Cursor::new(haystack);
+                let got = MemrchrReader::with_capacity(cap, b'a', rdr)
+                    .collect::<io::Result<Vec<u64>>>()
+                    .unwrap();
+                assert_eq!(expected, got, "capacity: {}", cap);
+            }
+        }
+    }
+
+    #[test]
+    fn find_rev_blocks() {
+        let haystacks: &[&[u8]] = &[
+            b"",
+            b"a",
+            b"abc",
+            b"aaaaaaa",
+            b"abcabcabcab",
+            b"xxxxfooxxxxxfooxfoofoo",
+            b"xfoo foox",
+            b"foo foo,foo_foo foo",
+        ];
+        let needles: &[&[u8]] =
+            &[b"", b"a", b"aa", b"aaa", b"abc", b"bca", b"foo", b"xfoof"];
+        for &haystack in haystacks {
+            for &needle in needles {
+                let expected: Vec<u64> =
+                    crate::memmem::rfind_iter(haystack, needle)
+                        .map(|i| u64::try_from(i).unwrap())
+                        .collect();
+                for cap in 1..=haystack.len() + 1 {
+                    let finder = FinderRev::new(needle);
+                    let rdr = Cursor::new(haystack);
+                    let got = FindRevReader::with_capacity(cap, finder, rdr)
+                        .collect::<io::Result<Vec<u64>>>()
+                        .unwrap();
+                    assert_eq!(
+                        expected, got,
+                        "haystack: {:?}, needle: {:?}, capacity: {}",
+                        haystack, needle, cap,
+                    );
+
+                    let finder = FinderBuilder::new()
+                        .whole_word(true)
+                        .build_reverse(needle);
+                    let expected: Vec<u64> = finder
+                        .rfind_iter(haystack)
+                        .map(|i| u64::try_from(i).unwrap())
+                        .collect();
+                    let rdr = Cursor::new(haystack);
+                    let got = FindRevReader::with_capacity(cap, finder, rdr)
+                        .collect::<io::Result<Vec<u64>>>()
+                        .unwrap();
+                    assert_eq!(
+                        expected, got,
+                        "whole word, haystack: {:?}, needle: {:?}, \
+                         capacity: {}",
+                        haystack, needle, cap,
+                    );
+                }
+            }
+        }
+    }
+}
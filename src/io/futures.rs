@@ -0,0 +1,698 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use std::{io, vec::Vec};
+
+use futures_core::Stream;
+use futures_io::AsyncBufRead;
+
+use crate::memmem::{searcher::PrefilterState, Finder};
+
+/// Returns a future that reads from the given stream until the first
+/// occurrence of the finder's needle, and resolves to the offset at which
+/// that occurrence begins.
+///
+/// The offset is relative to the position of the stream when the future is
+/// first polled. When a match is found, the stream is consumed up to and
+/// including the match, and any data following it remains buffered in the
+/// stream. If no match is found, then the stream is consumed in its entirety
+/// and the future resolves to `None`.
+///
+/// Only up to `needle.len() - 1` bytes of the stream are retained in memory
+/// at any point in time, in addition to what the stream itself buffers. When
+/// the finder only reports whole word matches, up to `needle.len() + 1`
+/// bytes are retained instead, so that the bytes on either side of a match
+/// are known even when they come from different reads.
+///
+/// This is only available when the `futures-io` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// # futures::executor::block_on(async {
+/// use memchr::{io::find_async, memmem::Finder};
+///
+/// let mut rdr: &[u8] = b"EHLO example.com\r\n\r\nDATA";
+/// let finder = Finder::new("\r\n\r\n");
+/// assert_eq!(Some(16), find_async(&mut rdr, &finder).await?);
+/// assert_eq!(rdr, b"DATA");
+/// # Ok::<(), std::io::Error>(())
+/// # }).unwrap();
+/// ```
+pub fn find_async<'a, 'n, R: AsyncBufRead + Unpin + ?Sized>(
+    rdr: &'a mut R,
+    finder: &'a Finder<'n>,
+) -> FindAsync<'a, 'n, R> {
+    FindAsync {
+        rdr,
+        finder,
+        prestate: PrefilterState::new(),
+        buf: Vec::new(),
+        offset: 0,
+    }
+}
+
+/// Returns a future that reads from the given stream into `buf` until the
+/// first occurrence of the finder's needle, and resolves to the number of
+/// bytes read.
+///
+/// This is like `AsyncBufReadExt::read_until`, except the delimiter is a
+/// sequence of bytes. Bytes are appended to `buf` up to and including the
+/// delimiter, and any data following the delimiter remains buffered in the
+/// stream. A delimiter that straddles two reads from the stream is still
+/// found. If the end of the stream is reached first, then all remaining bytes
+/// are appended to `buf`. In particular, this resolves to `0` only if the
+/// stream is at its end or if the needle is empty.
+///
+/// When the finder only reports whole word matches, the position of the
+/// stream when the future is first polled is treated as a word boundary,
+/// since the bytes preceding it are unknown.
+///
+/// This is only available when the `futures-io` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// # futures::executor::block_on(async {
+/// use memchr::{io::read_until_seq, memmem::Finder};
+///
+/// let mut rdr: &[u8] = b"HELO a\r\nMAIL FROM:<b>\r\nQUIT";
+/// let finder = Finder::new("\r\n");
+/// let mut buf = vec![];
+/// assert_eq!(8, read_until_seq(&mut rdr, &finder, &mut buf).await?);
+/// assert_eq!(buf, b"HELO a\r\n");
+///
+/// buf.clear();
+/// assert_eq!(15, read_until_seq(&mut rdr, &finder, &mut buf).await?);
+/// assert_eq!(buf, b"MAIL FROM:<b>\r\n");
+///
+/// buf.clear();
+/// assert_eq!(4, read_until_seq(&mut rdr, &finder, &mut buf).await?);
+/// assert_eq!(buf, b"QUIT");
+///
+/// buf.clear();
+/// assert_eq!(0, read_until_seq(&mut rdr, &finder, &mut buf).await?);
+/// # Ok::<(), std::io::Error>(())
+/// # }).unwrap();
+/// ```
+pub fn read_until_seq<'a, 'n, R: AsyncBufRead + Unpin + ?Sized>(
+    rdr: &'a mut R,
+    finder: &'a Finder<'n>,
+    buf: &'a mut Vec<u8>,
+) -> ReadUntilSeq<'a, 'n, R> {
+    let start = buf.len();
+    ReadUntilSeq { rdr, finder, prestate: PrefilterState::new(), buf, start }
+}
+
+/// A future that resolves to the offset of the first occurrence of a needle
+/// in a stream.
+///
+/// This future is created by [`find_async`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct FindAsync<'a, 'n, R: ?Sized> {
+    rdr: &'a mut R,
+    finder: &'a Finder<'n>,
+    prestate: PrefilterState,
+    /// The bytes that may be part of a match that straddles the boundary
+    /// of the next read.
+    buf: Vec<u8>,
+    /// The offset in the stream at which `buf` begins.
+    offset: u64,
+}
+
+impl<'a, 'n, R: AsyncBufRead + Unpin + ?Sized> Future
+    for FindAsync<'a, 'n, R>
+{
+    type Output = io::Result<Option<u64>>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<Option<u64>>> {
+        let this = self.get_mut();
+        let needle_len = this.finder.needle().len();
+        let keep = if this.finder.whole_word {
+            needle_len + 1
+        } else {
+            needle_len.saturating_sub(1)
+        };
+        loop {
+            let rdr = Pin::new(&mut *this.rdr);
+            let step = poll_chunk(
+                rdr,
+                cx,
+                this.finder,
+                &mut this.prestate,
+                &mut this.buf,
+                0,
+                0,
+            );
+            match step {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Ready(Ok(Step::Found(end))) => {
+                    let start = end - needle_len;
+                    let start = this.offset + u64::try_from(start).unwrap();
+                    return Poll::Ready(Ok(Some(start)));
+                }
+                Poll::Ready(Ok(Step::Eof)) => return Poll::Ready(Ok(None)),
+                Poll::Ready(Ok(Step::NotFound)) => {
+                    let drain = this.buf.len().saturating_sub(keep);
+                    this.buf.drain(..drain);
+                    this.offset += u64::try_from(drain).unwrap();
+                }
+            }
+        }
+    }
+}
+
+/// A future that reads bytes from a stream up to and including a delimiter.
+///
+/// This future is created by [`read_until_seq`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct ReadUntilSeq<'a, 'n, R: ?Sized> {
+    rdr: &'a mut R,
+    finder: &'a Finder<'n>,
+    prestate: PrefilterState,
+    buf: &'a mut Vec<u8>,
+    /// The length of `buf` before any bytes were read.
+    start: usize,
+}
+
+impl<'a, 'n, R: AsyncBufRead + Unpin + ?Sized> Future
+    for ReadUntilSeq<'a, 'n, R>
+{
+    type Output = io::Result<usize>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            let rdr = Pin::new(&mut *this.rdr);
+            let step = poll_chunk(
+                rdr,
+                cx,
+                this.finder,
+                &mut this.prestate,
+                this.buf,
+                this.start,
+                this.start,
+            );
+            match step {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Ready(Ok(Step::Found(_) | Step::Eof)) => {
+                    return Poll::Ready(Ok(this.buf.len() - this.start));
+                }
+                Poll::Ready(Ok(Step::NotFound)) => {}
+            }
+        }
+    }
+}
+
+/// A stream over the segments of a stream that are separated by a multi-byte
+/// delimiter.
+///
+/// Each segment is yielded without its delimiter. If the stream ends with a
+/// delimiter, then no empty segment is yielded after it. This mirrors the
+/// behavior of [`std::io::BufRead::split`], except the delimiter is a
+/// sequence of bytes. A delimiter that straddles two reads from the stream is
+/// still found.
+///
+/// This is only available when the `futures-io` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// # futures::executor::block_on(async {
+/// use futures::stream::TryStreamExt;
+/// use memchr::{io::SplitSeq, memmem::Finder};
+///
+/// let rdr: &[u8] = b"a--b----c--";
+/// let segments: Vec<Vec<u8>> =
+///     SplitSeq::new(rdr, Finder::new("--")).try_collect().await?;
+/// assert_eq!(segments, vec![&b"a"[..], b"b", b"", b"c"]);
+/// # Ok::<(), std::io::Error>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct SplitSeq<'n, R> {
+    rdr: R,
+    finder: Finder<'n>,
+    prestate: PrefilterState,
+    /// The bytes read from the stream that haven't been yielded yet.
+    buf: Vec<u8>,
+    /// The offset in `buf` at which the current segment begins. When the
+    /// finder only reports whole word matches, the last byte of the previous
+    /// delimiter is kept before the segment, so that it's known whether a
+    /// delimiter at the start of the segment begins on a word boundary.
+    start: usize,
+    done: bool,
+}
+
+impl<'n, R: AsyncBufRead + Unpin> SplitSeq<'n, R> {
+    /// Create a new stream that splits the given stream on occurrences of
+    /// the given finder's needle.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the finder's needle is empty.
+    pub fn new(rdr: R, finder: Finder<'n>) -> SplitSeq<'n, R> {
+        assert!(!finder.needle().is_empty(), "delimiter must be non-empty");
+        SplitSeq {
+            rdr,
+            finder,
+            prestate: PrefilterState::new(),
+            buf: Vec::new(),
+            start: 0,
+            done: false,
+        }
+    }
+
+    /// Consumes this splitter and returns the underlying stream.
+    ///
+    /// Any bytes that were read from the stream but not yet yielded as part
+    /// of a segment are lost.
+    pub fn into_inner(self) -> R {
+        self.rdr
+    }
+}
+
+impl<'n, R: AsyncBufRead + Unpin> Stream for SplitSeq<'n, R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<io::Result<Vec<u8>>>> {
+        let this = self.get_mut();
+        while !this.done {
+            let step = poll_chunk(
+                Pin::new(&mut this.rdr),
+                cx,
+                &this.finder,
+                &mut this.prestate,
+                &mut this.buf,
+                0,
+                this.start,
+            );
+            match step {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(err)) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(err)));
+                }
+                Poll::Ready(Ok(Step::Found(end))) => {
+                    let mut segment = core::mem::take(&mut this.buf);
+                    if this.finder.whole_word {
+                        this.buf.push(segment[end - 1]);
+                    }
+                    segment.truncate(end - this.finder.needle().len());
+                    segment.drain(..this.start);
+                    this.start = this.buf.len();
+                    return Poll::Ready(Some(Ok(segment)));
+                }
+                Poll::Ready(Ok(Step::Eof)) => {
+                    this.done = true;
+                    if this.buf.len() > this.start {
+                        let mut segment = core::mem::take(&mut this.buf);
+                        segment.drain(..this.start);
+                        return Poll::Ready(Some(Ok(segment)));
+                    }
+                }
+                Poll::Ready(Ok(Step::NotFound)) => {}
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+/// The outcome of reading a single chunk from a stream.
+enum Step {
+    /// A match was found that ends at the given offset in the buffer. The
+    /// buffer is truncated to this offset, and the stream is consumed up to
+    /// the end of the match.
+    Found(usize),
+    /// The chunk was appended to the buffer and consumed, and it contains no
+    /// match.
+    NotFound,
+    /// The stream has no more bytes.
+    Eof,
+}
+
+/// Appends the next chunk of bytes buffered by the given stream to `buf` and
+/// looks for a match that begins at or after `start` in `buf`. The bytes
+/// before `base` don't belong to the stream, so they are never searched.
+///
+/// Since `buf` is assumed to have no matches before the chunk is appended,
+/// only the chunk and the `needle.len() - 1` bytes preceding it are
+/// searched. This is what permits finding matches that straddle chunks.
+///
+/// When the finder only reports whole word matches, a match is only reported
+/// once the bytes on either side of it are known. So the search also covers
+/// one more byte preceding the chunk, and a match that ends at the end of
+/// `buf` is only reported after the next chunk is read (or the stream ends).
+fn poll_chunk<R: AsyncBufRead + ?Sized>(
+    mut rdr: Pin<&mut R>,
+    cx: &mut Context<'_>,
+    finder: &Finder,
+    prestate: &mut PrefilterState,
+    buf: &mut Vec<u8>,
+    base: usize,
+    start: usize,
+) -> Poll<io::Result<Step>> {
+    let needle = finder.needle();
+    if needle.is_empty() {
+        buf.truncate(start);
+        return Poll::Ready(Ok(Step::Found(start)));
+    }
+    let chunk = match rdr.as_mut().poll_fill_buf(cx) {
+        Poll::Pending => return Poll::Pending,
+        Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+        Poll::Ready(Ok(chunk)) => chunk,
+    };
+    if chunk.is_empty() {
+        // Now that we know nothing follows the end of `buf`, a whole word
+        // match ending there can be confirmed.
+        if finder.whole_word {
+            let at = buf.len().saturating_sub(needle.len()).max(start);
+            let haystack = &buf[base..];
+            let found =
+                finder.find_in(prestate, haystack, at - base, haystack.len());
+            if let Some(pos) = found {
+                return Poll::Ready(Ok(Step::Found(
+                    base + pos + needle.len(),
+                )));
+            }
+        }
+        return Poll::Ready(Ok(Step::Eof));
+    }
+    let old = buf.len();
+    let len = chunk.len();
+    buf.extend_from_slice(chunk);
+    let (at, end) = if finder.whole_word {
+        (old.saturating_sub(needle.len()), buf.len() - 1)
+    } else {
+        (old.saturating_sub(needle.len() - 1), buf.len())
+    };
+    let at = at.max(start);
+    match finder.find_in(prestate, &buf[base..], at - base, end - base) {
+        Some(pos) => {
+            let end = base + pos + needle.len();
+            buf.truncate(end);
+            rdr.consume(end - old);
+            Poll::Ready(Ok(Step::Found(end)))
+        }
+        None => {
+            rdr.consume(len);
+            Poll::Ready(Ok(Step::NotFound))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use std::{
+        future::Future,
+        io,
+        sync::Arc,
+        task::{Wake, Waker},
+        vec::Vec,
+    };
+
+    use futures_core::Stream;
+    use futures_io::{AsyncBufRead, AsyncRead};
+
+    use super::*;
+
+    /// A stream that yields its bytes in chunks of a fixed size, and that
+    /// is pending every other time it's polled.
+    struct Chunked<'a> {
+        data: &'a [u8],
+        chunk: usize,
+        pending: bool,
+    }
+
+    impl<'a> Chunked<'a> {
+        fn new(data: &'a [u8], chunk: usize) -> Chunked<'a> {
+            Chunked { data, chunk, pending: false }
+        }
+    }
+
+    impl<'a> AsyncRead for Chunked<'a> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            out: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            let chunk = match self.as_mut().poll_fill_buf(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(result) => result?,
+            };
+            let n = chunk.len().min(out.len());
+            out[..n].copy_from_slice(&chunk[..n]);
+            self.consume(n);
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    impl<'a> AsyncBufRead for Chunked<'a> {
+        fn poll_fill_buf(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<io::Result<&[u8]>> {
+            let this = self.get_mut();
+            this.pending = !this.pending;
+            if this.pending {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let n = this.chunk.min(this.data.len());
+            Poll::Ready(Ok(&this.data[..n]))
+        }
+
+        fn consume(self: Pin<&mut Self>, amt: usize) {
+            let this = self.get_mut();
+            this.data = &this.data[amt..];
+        }
+    }
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let waker = Waker::from(Arc::new(Noop));
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = std::boxed::Box::pin(fut);
+        loop {
+            if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    fn collect<S: Stream + Unpin>(mut stream: S) -> Vec<S::Item> {
+        let waker = Waker::from(Arc::new(Noop));
+        let mut cx = Context::from_waker(&waker);
+        let mut items = Vec::new();
+        loop {
+            match Pin::new(&mut stream).poll_next(&mut cx) {
+                Poll::Pending => {}
+                Poll::Ready(None) => return items,
+                Poll::Ready(Some(item)) => items.push(item),
+            }
+        }
+    }
+
+    const HAYSTACKS: &[&[u8]] = &[
+        b"",
+        b"a",
+        b"abc",
+        b"aaaaaaa",
+        b"abcabcabcab",
+        b"xx\r\nyy\r\n\r\nzz\r\n\r\n",
+    ];
+
+    const NEEDLES: &[&[u8]] =
+        &[b"a", b"aa", b"aaa", b"abc", b"bca", b"\r\n", b"\r\n\r\n"];
+
+    #[test]
+    fn find() {
+        for &haystack in HAYSTACKS {
+            for &needle in NEEDLES {
+                let finder = Finder::new(needle);
+                let expected = finder.find(haystack);
+                for chunk in 1..=haystack.len() + 1 {
+                    let mut rdr = Chunked::new(haystack, chunk);
+                    let got = block_on(find_async(&mut rdr, &finder)).unwrap();
+                    assert_eq!(
+                        expected.map(|i| u64::try_from(i).unwrap()),
+                        got,
+                        "haystack: {:?}, needle: {:?}, chunk: {}",
+                        haystack,
+                        needle,
+                        chunk,
+                    );
+                    let rest = match expected {
+                        None => &b""[..],
+                        Some(i) => &haystack[i + needle.len()..],
+                    };
+                    assert_eq!(rest, rdr.data);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn read_until_and_split() {
+        for &haystack in HAYSTACKS {
+            for &needle in NEEDLES {
+                let finder = Finder::new(needle);
+                // Compute the expected segments, with and without the
+                // delimiters, from the entire haystack.
+                let mut lines = Vec::new();
+                let mut segments = Vec::new();
+                let mut last = 0;
+                for i in finder.find_iter(haystack) {
+                    lines.push(haystack[last..i + needle.len()].to_vec());
+                    segments.push(haystack[last..i].to_vec());
+                    last = i + needle.len();
+                }
+                if last < haystack.len() {
+                    lines.push(haystack[last..].to_vec());
+                    segments.push(haystack[last..].to_vec());
+                }
+                for chunk in 1..=haystack.len() + 1 {
+                    let mut rdr = Chunked::new(haystack, chunk);
+                    let mut got = Vec::new();
+                    loop {
+                        let mut buf = b"prefix".to_vec();
+                        let fut = read_until_seq(&mut rdr, &finder, &mut buf);
+                        let n = block_on(fut).unwrap();
+                        if n == 0 {
+                            break;
+                        }
+                        assert_eq!(n, buf.len() - 6);
+                        got.push(buf[6..].to_vec());
+                    }
+                    assert_eq!(
+                        lines, got,
+                        "haystack: {:?}, needle: {:?}, chunk: {}",
+                        haystack, needle, chunk,
+                    );
+
+                    let rdr = Chunked::new(haystack, chunk);
+                    let got = collect(SplitSeq::new(rdr, finder.as_ref()))
+                        .into_iter()
+                        .collect::<io::Result<Vec<Vec<u8>>>>()
+                        .unwrap();
+                    assert_eq!(
+                        segments, got,
+                        "haystack: {:?}, needle: {:?}, chunk: {}",
+                        haystack, needle, chunk,
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn whole_word() {
+        use crate::memmem::FinderBuilder;
+
+        // Every chunk size puts a read boundary right before or after some
+        // of the matches and non-matches below.
+        let haystacks: &[&[u8]] = &[
+            b"foo",
+            b"xfoo foox",
+            b"foo foo,foo_foo foo",
+            b"foofoo foo",
+            b"a foo b",
+            b"x--y----z--",
+        ];
+        let needles: &[&[u8]] = &[b"foo", b"o", b"--"];
+        for &haystack in haystacks {
+            for &needle in needles {
+                let finder = FinderBuilder::new()
+                    .whole_word(true)
+                    .build_forward(needle);
+                let expected = finder.find(haystack);
+                let mut segments = Vec::new();
+                let mut last = 0;
+                for i in finder.find_iter(haystack) {
+                    segments.push(haystack[last..i].to_vec());
+                    last = i + needle.len();
+                }
+                if last < haystack.len() {
+                    segments.push(haystack[last..].to_vec());
+                }
+                // Each call to `read_until_seq` treats the position at which
+                // it starts reading as a word boundary.
+                let mut lines = Vec::new();
+                let mut last = 0;
+                while let Some(i) = finder.find(&haystack[last..]) {
+                    let end = last + i + needle.len();
+                    lines.push(haystack[last..end].to_vec());
+                    last = end;
+                }
+                if last < haystack.len() {
+                    lines.push(haystack[last..].to_vec());
+                }
+                for chunk in 1..=haystack.len() + 1 {
+                    let mut rdr = Chunked::new(haystack, chunk);
+                    let got = block_on(find_async(&mut rdr, &finder)).unwrap();
+                    assert_eq!(
+                        expected.map(|i| u64::try_from(i).unwrap()),
+                        got,
+                        "haystack: {:?}, needle: {:?}, chunk: {}",
+                        haystack,
+                        needle,
+                        chunk,
+                    );
+
+                    let mut rdr = Chunked::new(haystack, chunk);
+                    let mut got = Vec::new();
+                    loop {
+                        let mut buf = b"prefix".to_vec();
+                        let fut = read_until_seq(&mut rdr, &finder, &mut buf);
+                        if block_on(fut).unwrap() == 0 {
+                            break;
+                        }
+                        got.push(buf[6..].to_vec());
+                    }
+                    assert_eq!(
+                        lines, got,
+                        "haystack: {:?}, needle: {:?}, chunk: {}",
+                        haystack, needle, chunk,
+                    );
+
+                    let rdr = Chunked::new(haystack, chunk);
+                    let got = collect(SplitSeq::new(rdr, finder.as_ref()))
+                        .into_iter()
+                        .collect::<io::Result<Vec<Vec<u8>>>>()
+                        .unwrap();
+                    assert_eq!(
+                        segments, got,
+                        "haystack: {:?}, needle: {:?}, chunk: {}",
+                        haystack, needle, chunk,
+                    );
+                }
+            }
+        }
+    }
+}
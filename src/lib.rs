@@ -8,9 +8,18 @@ This section gives a brief high level overview of what this crate offers.
 * The top-level module provides routines for searching for 1, 2 or 3 bytes
   in the forward or reverse direction. When searching for more than one byte,
   positions are considered a match if the byte at that position matches any
-  of the bytes.
+  of the bytes. The [`One`], [`Two`] and [`Three`] searchers can be reused
+  for many haystacks, and choose the best implementation for the current
+  target only once, when they are built.
 * The [`memmem`] sub-module provides forward and reverse substring search
   routines.
+* The `io` sub-module, available when the `std` feature is enabled, provides
+  routines for searching seekable streams in reverse.
+* The `par` sub-module, available when the `rayon` feature is enabled,
+  provides routines for searching very large haystacks in parallel.
+* The [`vector`] sub-module exposes a small set of vector operations, along
+  with the vectorized loop used by this crate, for finding the first byte
+  matching an arbitrary predicate.
 
 In all such cases, routines operate on `&[u8]` without regard to encoding. This
 is exactly what you want when searching either UTF-8 or arbitrary bytes.
@@ -134,32 +143,48 @@ library haven't quite been worked out yet.
 **NOTE:** Currently, only `x86_64`, `wasm32` and `aarch64` targets have vector
 accelerated implementations of `memchr` (and friends) and `memmem`.
 
+The implementations chosen on the current target are reported by
+[`dispatch_info`]. For testing and benchmarking, the most capable
+implementation that may be chosen can be capped with [`set_dispatch_cap`], or
+by setting the `MEMCHR_FORCE` environment variable to `fallback`, `sse2` or
+`avx2`.
+
 # Crate features
 
 * **std** - When enabled (the default), this will permit features specific to
-the standard library. Currently, the only thing used from the standard library
-is runtime SIMD CPU feature detection. This means that this feature must be
-enabled to get AVX2 accelerated routines on `x86_64` targets without enabling
-the `avx2` feature at compile time, for example. When `std` is not enabled,
-this crate will still attempt to use SSE2 accelerated routines on `x86_64`. It
-will also use AVX2 accelerated routines when the `avx2` feature is enabled at
-compile time. In general, enable this feature if you can.
+  the standard library. Currently, this is runtime SIMD CPU feature detection
+  via the standard library, reading the `MEMCHR_FORCE` environment variable
+  and the stream search routines in the `io` sub-module. When `std` is not
+  enabled, this crate will still attempt to use SSE2 accelerated routines on
+  `x86_64`, and detects AVX2 support by querying the CPU directly with the
+  `cpuid` instruction. Environments with their own means of detecting CPU
+  features can provide them with [`set_cpu_features`]. In general, enable this
+  feature if you can.
 * **alloc** - When enabled (the default), APIs in this crate requiring some
 kind of allocation will become available. For example, the
 [`memmem::Finder::into_owned`](crate::memmem::Finder::into_owned) API and the
 [`arch::all::shiftor`](crate::arch::all::shiftor) substring search
 implementation. Otherwise, this crate is designed from the ground up to be
 usable in core-only contexts, so the `alloc` feature doesn't add much
-currently. Notably, disabling `std` but enabling `alloc` will **not** result
-in the use of AVX2 on `x86_64` targets unless the `avx2` feature is enabled
-at compile time. (With `std` enabled, AVX2 can be used even without the `avx2`
-feature enabled at compile time by way of runtime CPU feature detection.)
+currently.
 * **logging** - When enabled (disabled by default), the `log` crate is used
 to emit log messages about what kinds of `memchr` and `memmem` algorithms
 are used. Namely, both `memchr` and `memmem` have a number of different
 implementation choices depending on the target and CPU, and the log messages
 can help show what specific implementations are being used. Generally, this is
 useful for debugging performance issues.
+* **rayon** - When enabled (disabled by default), the `rayon` crate is used
+  to provide routines for searching very large haystacks in parallel. See the
+  `par` sub-module and `memmem::Finder::par_find_all`. This implies the `std`
+  feature.
+* **futures-io** - When enabled (disabled by default), the `io` sub-module
+  provides adapters for searching asynchronous streams that implement the
+  traits in the `futures-io` crate. This implies the `std` feature.
+* **portable-simd** - When enabled (disabled by default), the
+  `arch::portable` sub-module provides vector implementations of `memchr` and
+  friends, along with the "packed pair" substring search algorithm, written
+  on top of `core::simd`. These work on any target the compiler can vectorize,
+  but require a nightly Rust compiler.
 * **libc** - **DEPRECATED**. Previously, this enabled the use of the target's
 `memchr` function from whatever `libc` was linked into the program. This
 feature is now a no-op because this crate's implementation of `memchr` should
@@ -171,6 +196,7 @@ Originally, this crate was literally just a safe wrapper function around the
 
 #![deny(missing_docs)]
 #![no_std]
+#![cfg_attr(feature = "portable-simd", feature(portable_simd))]
 // It's just not worth trying to squash all dead code warnings. Pretty
 // unfortunate IMO. Not really sure how to fix this other than to either
 // live with it or sprinkle a whole mess of `cfg` annotations everywhere.
@@ -200,11 +226,18 @@ extern crate std;
 #[cfg(any(test, feature = "alloc"))]
 extern crate alloc;
 
+pub use crate::dispatch::{
+    cpu_features, dispatch_cap, dispatch_info, set_cpu_features,
+    set_dispatch_cap, CpuFeatures, DispatchInfo, Implementation,
+};
 pub use crate::memchr::{
-    memchr, memchr2, memchr2_iter, memchr3, memchr3_iter, memchr_iter,
-    memrchr, memrchr2, memrchr2_iter, memrchr3, memrchr3_iter, memrchr_iter,
-    Memchr, Memchr2, Memchr3,
+    memchr, memchr2, memchr2_iter, memchr3, memchr3_iter, memchr_const,
+    memchr_in_batch, memchr_iter, memchr_segmented, memchr_segmented_iter,
+    memrchr, memrchr2, memrchr2_iter, memrchr3, memrchr3_iter, memrchr_const,
+    memrchr_iter, BatchMatch, Memchr, Memchr2, Memchr3, MemchrBatch,
+    MemchrSegmented, OwnedMemchr, OwnedMemchr2, OwnedMemchr3, SegmentPos,
 };
+pub use crate::searcher::{One, OneIter, Three, ThreeIter, Two, TwoIter};
 
 #[macro_use]
 mod macros;
@@ -215,7 +248,13 @@ mod tests;
 
 pub mod arch;
 mod cow;
+mod dispatch;
 mod ext;
+#[cfg(feature = "std")]
+pub mod io;
 mod memchr;
 pub mod memmem;
-mod vector;
+#[cfg(feature = "rayon")]
+pub mod par;
+mod searcher;
+pub mod vector;
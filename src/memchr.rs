@@ -66,6 +66,67 @@ pub fn memrchr(needle: u8, haystack: &[u8]) -> Option<usize> {
     }
 }
 
+/// Search for the first occurrence of a byte in a slice in a `const` context.
+///
+/// This returns the same result as [`memchr`], but it can be called in a
+/// `const` context, for example, to compute an offset into data included
+/// with `include_bytes!` at compile time. Since vector operations can't be
+/// used in a `const` context, this examines one byte at a time. Outside of
+/// a `const` context, [`memchr`] should be used instead. (Stable Rust doesn't
+/// provide a way for [`memchr`] itself to detect that it is being called in
+/// a `const` context and dispatch to this routine.)
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_const;
+///
+/// const DATA: &[u8] = b"key=value";
+/// const EQUALS: usize = match memchr_const(b'=', DATA) {
+///     Some(i) => i,
+///     None => panic!("missing '='"),
+/// };
+/// assert_eq!(EQUALS, 3);
+/// ```
+#[inline]
+pub const fn memchr_const(needle: u8, haystack: &[u8]) -> Option<usize> {
+    let mut i = 0;
+    while i < haystack.len() {
+        if haystack[i] == needle {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Search for the last occurrence of a byte in a slice in a `const` context.
+///
+/// This returns the same result as [`memrchr`], but it can be called in a
+/// `const` context. Since vector operations can't be used in a `const`
+/// context, this examines one byte at a time. Outside of a `const` context,
+/// [`memrchr`] should be used instead.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memrchr_const;
+///
+/// const LAST_SLASH: Option<usize> = memrchr_const(b'/', b"a/b/c");
+/// assert_eq!(LAST_SLASH, Some(3));
+/// ```
+#[inline]
+pub const fn memrchr_const(needle: u8, haystack: &[u8]) -> Option<usize> {
+    let mut i = haystack.len();
+    while i > 0 {
+        i -= 1;
+        if haystack[i] == needle {
+            return Some(i);
+        }
+    }
+    None
+}
+
 /// Search for the first occurrence of two possible bytes in a haystack.
 ///
 /// This returns the index corresponding to the first occurrence of one of the
@@ -274,6 +335,127 @@ pub fn memrchr3_iter(
     Memchr3::new(needle1, needle2, needle3, haystack).rev()
 }
 
+/// Search for the first occurrence of a byte in a sequence of segments that
+/// are treated as one logical haystack.
+///
+/// This is useful when a haystack isn't stored contiguously in memory, for
+/// example, a ring buffer like `VecDeque<u8>` or a chain of `IoSlice`s. It
+/// avoids the need to copy all of the segments into a single slice before
+/// searching. Each segment may be any type that dereferences to `[u8]`.
+///
+/// The position returned includes both the offset into the logical haystack
+/// and the segment (along with the offset into that segment) at which the
+/// byte occurs. Empty segments are permitted and never contain a match.
+///
+/// # Example
+///
+/// This shows how to search a `VecDeque<u8>` without copying it:
+///
+/// ```
+/// use std::collections::VecDeque;
+///
+/// use memchr::memchr_segmented;
+///
+/// let mut deque = VecDeque::with_capacity(8);
+/// deque.extend(b"xxxxxx");
+/// deque.drain(..4);
+/// deque.extend(b"abz");
+///
+/// let (a, b) = deque.as_slices();
+/// let pos = memchr_segmented(b'z', &[a, b]).unwrap();
+/// assert_eq!(4, pos.offset());
+/// ```
+///
+/// And this shows how to search a chain of `IoSlice`s:
+///
+/// ```
+/// use std::io::IoSlice;
+///
+/// use memchr::memchr_segmented;
+///
+/// let chain = [IoSlice::new(b"GET / HTTP/1.1"), IoSlice::new(b"\r\n")];
+/// let pos = memchr_segmented(b'\n', &chain).unwrap();
+/// assert_eq!(15, pos.offset());
+/// assert_eq!((1, 1), (pos.segment(), pos.segment_offset()));
+/// ```
+#[inline]
+pub fn memchr_segmented<S: core::ops::Deref<Target = [u8]>>(
+    needle: u8,
+    segments: &[S],
+) -> Option<SegmentPos> {
+    memchr_segmented_iter(needle, segments).next()
+}
+
+/// Returns an iterator over all occurrences of the needle in a sequence of
+/// segments that are treated as one logical haystack.
+///
+/// See [`memchr_segmented`] for more details.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_segmented_iter;
+///
+/// let segments: [&[u8]; 3] = [b"a,b", b"", b",c,"];
+/// let offsets: Vec<usize> = memchr_segmented_iter(b',', &segments)
+///     .map(|pos| pos.offset())
+///     .collect();
+/// assert_eq!(offsets, vec![1, 3, 5]);
+/// ```
+#[inline]
+pub fn memchr_segmented_iter<S: core::ops::Deref<Target = [u8]>>(
+    needle: u8,
+    segments: &[S],
+) -> MemchrSegmented<'_, S> {
+    MemchrSegmented::new(needle, segments)
+}
+
+/// Returns an iterator over the rows of a columnar batch of byte strings that
+/// contain the needle, along with the position of its first occurrence in
+/// each such row.
+///
+/// The batch is stored as in the Apache Arrow columnar format: `values` is
+/// the contiguous concatenation of every row, and `offsets` contains one more
+/// element than there are rows, such that row `i` is
+/// `values[offsets[i]..offsets[i + 1]]`. Offsets may be any integer type that
+/// converts to `usize`, such as the `i32` and `i64` offsets used by Arrow.
+///
+/// Instead of searching each row separately, this searches the values buffer
+/// in one pass and maps each occurrence back to the row containing it. After
+/// a row's first occurrence is found, the search skips to the next row.
+///
+/// # Panics
+///
+/// This panics if the offsets are not non-decreasing, or if any offset is
+/// negative or greater than the length of `values`.
+///
+/// # Example
+///
+/// This shows how to build a bitmap of the rows that contain a `,`:
+///
+/// ```
+/// use memchr::memchr_in_batch;
+///
+/// let values = b"a,bcdef,,g";
+/// let offsets: [i32; 5] = [0, 3, 4, 8, 10];
+/// let mut bitmap = vec![false; offsets.len() - 1];
+/// for m in memchr_in_batch(b',', values, &offsets) {
+///     bitmap[m.row()] = true;
+/// }
+/// assert_eq!(bitmap, vec![true, false, true, true]);
+/// ```
+#[inline]
+pub fn memchr_in_batch<'h, 'o, O: Copy>(
+    needle: u8,
+    values: &'h [u8],
+    offsets: &'o [O],
+) -> MemchrBatch<'h, 'o, O>
+where
+    usize: TryFrom<O>,
+{
+    MemchrBatch::new(needle, values, offsets)
+}
+
 /// An iterator over all occurrences of a single byte in a haystack.
 ///
 /// This iterator implements `DoubleEndedIterator`, which means it can also be
@@ -495,6 +677,569 @@ impl<'h> DoubleEndedIterator for Memchr3<'h> {
 
 impl<'h> core::iter::FusedIterator for Memchr3<'h> {}
 
+/// An iterator over all occurrences of a single byte in a haystack that it
+/// owns.
+///
+/// This is like [`Memchr`], except the haystack is owned by the iterator
+/// instead of borrowed. The haystack may be any type that can be cheaply
+/// converted into a `&[u8]`, such as `Vec<u8>`, `Box<[u8]>` or `Arc<[u8]>`.
+/// When the haystack type is `'static`, then so is this iterator. This makes
+/// it possible to return this iterator from a function or store it in a
+/// long-lived data structure without borrowing the haystack.
+///
+/// This iterator implements `DoubleEndedIterator`, which means it can also be
+/// used to find occurrences in reverse order.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use memchr::OwnedMemchr;
+///
+/// fn newlines(haystack: Arc<[u8]>) -> OwnedMemchr<Arc<[u8]>> {
+///     OwnedMemchr::new(b'\n', haystack)
+/// }
+///
+/// let it = newlines(Arc::from(&b"a\nb\nc"[..]));
+/// assert_eq!(it.collect::<Vec<usize>>(), vec![1, 3]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct OwnedMemchr<H> {
+    needle1: u8,
+    it: OwnedIter<H>,
+}
+
+impl<H: AsRef<[u8]>> OwnedMemchr<H> {
+    /// Returns an iterator over all occurrences of the needle byte in the
+    /// given haystack, which is owned by the iterator.
+    #[inline]
+    pub fn new(needle1: u8, haystack: H) -> OwnedMemchr<H> {
+        OwnedMemchr { needle1, it: OwnedIter::new(haystack) }
+    }
+
+    /// Returns a reference to the haystack owned by this iterator.
+    #[inline]
+    pub fn haystack(&self) -> &H {
+        &self.it.haystack
+    }
+
+    /// Consumes this iterator and returns the haystack it owns.
+    #[inline]
+    pub fn into_haystack(self) -> H {
+        self.it.haystack
+    }
+}
+
+impl<H: AsRef<[u8]>> Iterator for OwnedMemchr<H> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        let needle1 = self.needle1;
+        self.it.next(|h| memchr(needle1, h))
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        memchr_iter(self.needle1, self.it.remaining()).count()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.it.size_hint()
+    }
+}
+
+impl<H: AsRef<[u8]>> DoubleEndedIterator for OwnedMemchr<H> {
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        let needle1 = self.needle1;
+        self.it.next_back(|h| memrchr(needle1, h))
+    }
+}
+
+impl<H: AsRef<[u8]>> core::iter::FusedIterator for OwnedMemchr<H> {}
+
+/// An iterator over all occurrences of two possible bytes in a haystack that
+/// it owns.
+///
+/// This is like [`Memchr2`], except the haystack is owned by the iterator
+/// instead of borrowed. See [`OwnedMemchr`] for more details.
+#[derive(Clone, Debug)]
+pub struct OwnedMemchr2<H> {
+    needle1: u8,
+    needle2: u8,
+    it: OwnedIter<H>,
+}
+
+impl<H: AsRef<[u8]>> OwnedMemchr2<H> {
+    /// Returns an iterator over all occurrences of the needle bytes in the
+    /// given haystack, which is owned by the iterator.
+    #[inline]
+    pub fn new(needle1: u8, needle2: u8, haystack: H) -> OwnedMemchr2<H> {
+        OwnedMemchr2 { needle1, needle2, it: OwnedIter::new(haystack) }
+    }
+
+    /// Returns a reference to the haystack owned by this iterator.
+    #[inline]
+    pub fn haystack(&self) -> &H {
+        &self.it.haystack
+    }
+
+    /// Consumes this iterator and returns the haystack it owns.
+    #[inline]
+    pub fn into_haystack(self) -> H {
+        self.it.haystack
+    }
+}
+
+impl<H: AsRef<[u8]>> Iterator for OwnedMemchr2<H> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        let (needle1, needle2) = (self.needle1, self.needle2);
+        self.it.next(|h| memchr2(needle1, needle2, h))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.it.size_hint()
+    }
+}
+
+impl<H: AsRef<[u8]>> DoubleEndedIterator for OwnedMemchr2<H> {
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        let (needle1, needle2) = (self.needle1, self.needle2);
+        self.it.next_back(|h| memrchr2(needle1, needle2, h))
+    }
+}
+
+impl<H: AsRef<[u8]>> core::iter::FusedIterator for OwnedMemchr2<H> {}
+
+/// An iterator over all occurrences of three possible bytes in a haystack
+/// that it owns.
+///
+/// This is like [`Memchr3`], except the haystack is owned by the iterator
+/// instead of borrowed. See [`OwnedMemchr`] for more details.
+#[derive(Clone, Debug)]
+pub struct OwnedMemchr3<H> {
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    it: OwnedIter<H>,
+}
+
+impl<H: AsRef<[u8]>> OwnedMemchr3<H> {
+    /// Returns an iterator over all occurrences of the needle bytes in the
+    /// given haystack, which is owned by the iterator.
+    #[inline]
+    pub fn new(
+        needle1: u8,
+        needle2: u8,
+        needle3: u8,
+        haystack: H,
+    ) -> OwnedMemchr3<H> {
+        OwnedMemchr3 {
+            needle1,
+            needle2,
+            needle3,
+            it: OwnedIter::new(haystack),
+        }
+    }
+
+    /// Returns a reference to the haystack owned by this iterator.
+    #[inline]
+    pub fn haystack(&self) -> &H {
+        &self.it.haystack
+    }
+
+    /// Consumes this iterator and returns the haystack it owns.
+    #[inline]
+    pub fn into_haystack(self) -> H {
+        self.it.haystack
+    }
+}
+
+impl<H: AsRef<[u8]>> Iterator for OwnedMemchr3<H> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        let (n1, n2, n3) = (self.needle1, self.needle2, self.needle3);
+        self.it.next(|h| memchr3(n1, n2, n3, h))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.it.size_hint()
+    }
+}
+
+impl<H: AsRef<[u8]>> DoubleEndedIterator for OwnedMemchr3<H> {
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        let (n1, n2, n3) = (self.needle1, self.needle2, self.needle3);
+        self.it.next_back(|h| memrchr3(n1, n2, n3, h))
+    }
+}
+
+impl<H: AsRef<[u8]>> core::iter::FusedIterator for OwnedMemchr3<H> {}
+
+/// A position in a haystack made up of a sequence of segments.
+///
+/// This is reported by routines that search segmented haystacks, such as
+/// [`memchr_segmented`] and
+/// [`memmem::Finder::find_segmented`](crate::memmem::Finder::find_segmented).
+/// It records both the offset in the logical haystack (that is, as if all of
+/// the segments were concatenated) and the segment containing that offset.
+///
+/// For a match, the segment is the one containing the first byte of the
+/// match. The only time a position can refer to no byte at all is for a
+/// match of an empty needle at the very end of the logical haystack. In that
+/// case, the segment is equivalent to the number of segments and the offset
+/// into that segment is `0`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SegmentPos {
+    offset: usize,
+    segment: usize,
+    segment_offset: usize,
+}
+
+impl SegmentPos {
+    #[inline]
+    pub(crate) fn new(
+        offset: usize,
+        segment: usize,
+        segment_offset: usize,
+    ) -> SegmentPos {
+        SegmentPos { offset, segment, segment_offset }
+    }
+
+    /// Returns the offset of this position in the logical haystack.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the index of the segment containing this position.
+    #[inline]
+    pub fn segment(&self) -> usize {
+        self.segment
+    }
+
+    /// Returns the offset of this position relative to the beginning of the
+    /// segment containing it.
+    #[inline]
+    pub fn segment_offset(&self) -> usize {
+        self.segment_offset
+    }
+}
+
+/// An iterator over all occurrences of a single byte in a haystack made up of
+/// a sequence of segments.
+///
+/// This iterator is created by the [`memchr_segmented_iter`] function.
+///
+/// The lifetime parameter `'s` refers to the lifetime of the segments being
+/// searched, while `S` is the type of each segment.
+#[derive(Clone, Debug)]
+pub struct MemchrSegmented<'s, S> {
+    needle1: u8,
+    segments: &'s [S],
+    /// The index of the segment currently being searched.
+    segment: usize,
+    /// The offset into the current segment at which to resume searching.
+    at: usize,
+    /// The offset of the beginning of the current segment in the logical
+    /// haystack.
+    base: usize,
+}
+
+impl<'s, S: core::ops::Deref<Target = [u8]>> MemchrSegmented<'s, S> {
+    /// Returns an iterator over all occurrences of the needle byte in the
+    /// given segments.
+    #[inline]
+    pub fn new(needle1: u8, segments: &'s [S]) -> MemchrSegmented<'s, S> {
+        MemchrSegmented { needle1, segments, segment: 0, at: 0, base: 0 }
+    }
+}
+
+impl<'s, S: core::ops::Deref<Target = [u8]>> Iterator
+    for MemchrSegmented<'s, S>
+{
+    type Item = SegmentPos;
+
+    #[inline]
+    fn next(&mut self) -> Option<SegmentPos> {
+        while let Some(segment) = self.segments.get(self.segment) {
+            if let Some(i) = memchr(self.needle1, &segment[self.at..]) {
+                let segment_offset = self.at + i;
+                self.at = segment_offset + 1;
+                return Some(SegmentPos::new(
+                    self.base + segment_offset,
+                    self.segment,
+                    segment_offset,
+                ));
+            }
+            self.base += segment.len();
+            self.segment += 1;
+            self.at = 0;
+        }
+        None
+    }
+}
+
+impl<'s, S: core::ops::Deref<Target = [u8]>> core::iter::FusedIterator
+    for MemchrSegmented<'s, S>
+{
+}
+
+/// A match in a columnar batch of byte strings.
+///
+/// This is reported by routines that search batches, such as
+/// [`memchr_in_batch`] and
+/// [`memmem::Finder::find_in_batch`](crate::memmem::Finder::find_in_batch).
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct BatchMatch {
+    row: usize,
+    offset: usize,
+}
+
+impl BatchMatch {
+    #[inline]
+    pub(crate) fn new(row: usize, offset: usize) -> BatchMatch {
+        BatchMatch { row, offset }
+    }
+
+    /// Returns the index of the row containing this match.
+    #[inline]
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    /// Returns the offset at which this match begins, relative to the
+    /// beginning of its row.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+/// An iterator over the rows of a columnar batch of byte strings that contain
+/// a single byte.
+///
+/// This iterator is created by the [`memchr_in_batch`] function.
+///
+/// The lifetime parameter `'h` refers to the lifetime of the values buffer,
+/// while `'o` refers to the lifetime of the offsets.
+#[derive(Clone, Debug)]
+pub struct MemchrBatch<'h, 'o, O> {
+    needle1: u8,
+    values: &'h [u8],
+    rows: BatchRows<'o, O>,
+}
+
+impl<'h, 'o, O: Copy> MemchrBatch<'h, 'o, O>
+where
+    usize: TryFrom<O>,
+{
+    /// Returns an iterator over the rows in the given batch that contain the
+    /// needle byte.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the offsets are not non-decreasing, or if any offset
+    /// is negative or greater than the length of `values`.
+    #[inline]
+    pub fn new(
+        needle1: u8,
+        values: &'h [u8],
+        offsets: &'o [O],
+    ) -> MemchrBatch<'h, 'o, O> {
+        let rows = BatchRows::new(values.len(), offsets);
+        MemchrBatch { needle1, values, rows }
+    }
+}
+
+impl<'h, 'o, O: Copy> Iterator for MemchrBatch<'h, 'o, O>
+where
+    usize: TryFrom<O>,
+{
+    type Item = BatchMatch;
+
+    #[inline]
+    fn next(&mut self) -> Option<BatchMatch> {
+        let start = self.rows.start()?;
+        let end = self.rows.end();
+        let pos = start + memchr(self.needle1, &self.values[start..end])?;
+        let (row, row_start, _) = self.rows.seek(pos);
+        self.rows.advance();
+        Some(BatchMatch::new(row, pos - row_start))
+    }
+}
+
+impl<'h, 'o, O: Copy> core::iter::FusedIterator for MemchrBatch<'h, 'o, O> where
+    usize: TryFrom<O>
+{
+}
+
+/// A cursor over the rows of a columnar batch, as described by its offsets.
+///
+/// This is shared by the batch search iterators. Each iterator searches the
+/// values buffer from the start of the current row, and then uses this to
+/// find the row containing whatever it found.
+#[derive(Clone, Debug)]
+pub(crate) struct BatchRows<'o, O> {
+    offsets: &'o [O],
+    /// The index of the current row. Every row before it has been searched.
+    row: usize,
+}
+
+impl<'o, O: Copy> BatchRows<'o, O>
+where
+    usize: TryFrom<O>,
+{
+    /// Creates a cursor over the rows described by the given offsets into
+    /// a values buffer of the given length.
+    ///
+    /// This panics if the offsets are not non-decreasing, or if any offset
+    /// is invalid or out of bounds. Checking this once up front is what
+    /// permits searching the values buffer in one pass.
+    #[inline]
+    pub(crate) fn new(
+        values_len: usize,
+        offsets: &'o [O],
+    ) -> BatchRows<'o, O> {
+        let rows = BatchRows { offsets, row: 0 };
+        let mut prev = 0;
+        for index in 0..offsets.len() {
+            let offset = rows.offset(index);
+            if offset < prev {
+                panic!("batch offset at index {} is decreasing", index);
+            }
+            if offset > values_len {
+                panic!("batch offset at index {} is out of bounds", index);
+            }
+            prev = offset;
+        }
+        rows
+    }
+
+    /// Returns the offset at the given index.
+    #[inline]
+    fn offset(&self, index: usize) -> usize {
+        match usize::try_from(self.offsets[index]) {
+            Ok(offset) => offset,
+            Err(_) => panic!("batch offset at index {} is invalid", index),
+        }
+    }
+
+    /// Returns the index of the current row, or `None` if all rows have been
+    /// searched.
+    #[inline]
+    pub(crate) fn row(&self) -> Option<usize> {
+        if self.row + 1 < self.offsets.len() {
+            Some(self.row)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the offset at which the current row begins, or `None` if all
+    /// rows have been searched.
+    #[inline]
+    pub(crate) fn start(&self) -> Option<usize> {
+        self.row().map(|row| self.offset(row))
+    }
+
+    /// Returns the bounds of the given row in the values buffer.
+    #[inline]
+    pub(crate) fn bounds(&self, row: usize) -> (usize, usize) {
+        (self.offset(row), self.offset(row + 1))
+    }
+
+    /// Returns the offset at which the last row ends. This must only be
+    /// called when there is at least one row left.
+    #[inline]
+    pub(crate) fn end(&self) -> usize {
+        self.offset(self.offsets.len() - 1)
+    }
+
+    /// Moves to the row containing the given offset, and returns its index
+    /// along with its bounds. The offset must be at or after the beginning
+    /// of the current row and before the end of the last row.
+    #[inline]
+    pub(crate) fn seek(&mut self, pos: usize) -> (usize, usize, usize) {
+        let ends = &self.offsets[self.row + 1..];
+        self.row += ends.partition_point(|&end| {
+            usize::try_from(end).map_or(false, |end| end <= pos)
+        });
+        let (start, end) = self.bounds(self.row);
+        (self.row, start, end)
+    }
+
+    /// Moves to the row following the current one.
+    #[inline]
+    pub(crate) fn advance(&mut self) {
+        self.row += 1;
+    }
+}
+
+/// The state shared by all of the owned haystack iterators.
+///
+/// Unlike the generic iterator used by the borrowing iterators, this tracks
+/// the remaining part of the haystack with offsets instead of pointers. This
+/// is because the haystack may move along with the iterator (for example, an
+/// array), so we can't hold on to pointers into it.
+#[derive(Clone, Debug)]
+struct OwnedIter<H> {
+    haystack: H,
+    start: usize,
+    end: usize,
+}
+
+impl<H: AsRef<[u8]>> OwnedIter<H> {
+    #[inline]
+    fn new(haystack: H) -> OwnedIter<H> {
+        let end = haystack.as_ref().len();
+        OwnedIter { haystack, start: 0, end }
+    }
+
+    /// Returns the part of the haystack that hasn't been searched yet.
+    #[inline]
+    fn remaining(&self) -> &[u8] {
+        self.haystack.as_ref().get(self.start..self.end).unwrap_or(&[])
+    }
+
+    #[inline]
+    fn next(
+        &mut self,
+        find: impl FnOnce(&[u8]) -> Option<usize>,
+    ) -> Option<usize> {
+        let i = self.start + find(self.remaining())?;
+        self.start = i + 1;
+        Some(i)
+    }
+
+    #[inline]
+    fn next_back(
+        &mut self,
+        rfind: impl FnOnce(&[u8]) -> Option<usize>,
+    ) -> Option<usize> {
+        let i = self.start + rfind(self.remaining())?;
+        self.end = i;
+        Some(i)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end.saturating_sub(self.start)))
+    }
+}
+
 /// memchr, but using raw pointers to represent the haystack.
 ///
 /// # Safety
@@ -786,6 +1531,20 @@ mod tests {
         )
     }
 
+    #[test]
+    fn forward1_const() {
+        crate::tests::memchr::Runner::new(1).forward_oneshot(
+            |haystack, needles| Some(memchr_const(needles[0], haystack)),
+        )
+    }
+
+    #[test]
+    fn reverse1_const() {
+        crate::tests::memchr::Runner::new(1).reverse_oneshot(
+            |haystack, needles| Some(memrchr_const(needles[0], haystack)),
+        )
+    }
+
     #[test]
     fn count1_iter() {
         crate::tests::memchr::Runner::new(1).count_iter(|haystack, needles| {
@@ -885,6 +1644,178 @@ mod tests {
         )
     }
 
+    #[test]
+    fn forward1_owned_iter() {
+        crate::tests::memchr::Runner::new(1).forward_iter(
+            |haystack, needles| {
+                let haystack = alloc::vec::Vec::from(haystack);
+                Some(OwnedMemchr::new(needles[0], haystack).collect())
+            },
+        )
+    }
+
+    #[test]
+    fn reverse1_owned_iter() {
+        crate::tests::memchr::Runner::new(1).reverse_iter(
+            |haystack, needles| {
+                let haystack = alloc::boxed::Box::<[u8]>::from(haystack);
+                Some(OwnedMemchr::new(needles[0], haystack).rev().collect())
+            },
+        )
+    }
+
+    #[test]
+    fn count1_owned_iter() {
+        crate::tests::memchr::Runner::new(1).count_iter(|haystack, needles| {
+            Some(OwnedMemchr::new(needles[0], haystack).count())
+        })
+    }
+
+    #[test]
+    fn forward2_owned_iter() {
+        crate::tests::memchr::Runner::new(2).forward_iter(
+            |haystack, needles| {
+                let n1 = needles.first().copied()?;
+                let n2 = needles.get(1).copied()?;
+                let haystack = alloc::vec::Vec::from(haystack);
+                Some(OwnedMemchr2::new(n1, n2, haystack).collect())
+            },
+        )
+    }
+
+    #[test]
+    fn reverse2_owned_iter() {
+        crate::tests::memchr::Runner::new(2).reverse_iter(
+            |haystack, needles| {
+                let n1 = needles.first().copied()?;
+                let n2 = needles.get(1).copied()?;
+                let haystack = alloc::vec::Vec::from(haystack);
+                Some(OwnedMemchr2::new(n1, n2, haystack).rev().collect())
+            },
+        )
+    }
+
+    #[test]
+    fn forward3_owned_iter() {
+        crate::tests::memchr::Runner::new(3).forward_iter(
+            |haystack, needles| {
+                let n1 = needles.first().copied()?;
+                let n2 = needles.get(1).copied()?;
+                let n3 = needles.get(2).copied()?;
+                let haystack = alloc::sync::Arc::<[u8]>::from(haystack);
+                Some(OwnedMemchr3::new(n1, n2, n3, haystack).collect())
+            },
+        )
+    }
+
+    #[test]
+    fn reverse3_owned_iter() {
+        crate::tests::memchr::Runner::new(3).reverse_iter(
+            |haystack, needles| {
+                let n1 = needles.first().copied()?;
+                let n2 = needles.get(1).copied()?;
+                let n3 = needles.get(2).copied()?;
+                let haystack = alloc::sync::Arc::<[u8]>::from(haystack);
+                Some(OwnedMemchr3::new(n1, n2, n3, haystack).rev().collect())
+            },
+        )
+    }
+
+    #[test]
+    fn forward1_segmented_iter() {
+        crate::tests::memchr::Runner::new(1).forward_iter(
+            |haystack, needles| {
+                // Split the haystack into segments of varying lengths,
+                // including empty segments.
+                let mut segments = alloc::vec::Vec::new();
+                let mut rest = haystack;
+                for len in [0, 3, 1, 0, 7, 2].iter().cycle() {
+                    if rest.is_empty() {
+                        break;
+                    }
+                    let (segment, tail) =
+                        rest.split_at((*len).min(rest.len()));
+                    segments.push(segment);
+                    rest = tail;
+                }
+                let it = memchr_segmented_iter(needles[0], &segments);
+                Some(
+                    it.map(|pos| {
+                        let segment = segments[pos.segment()];
+                        assert_eq!(
+                            haystack[pos.offset()],
+                            segment[pos.segment_offset()],
+                        );
+                        pos.offset()
+                    })
+                    .collect(),
+                )
+            },
+        )
+    }
+
+    #[test]
+    fn forward1_batch() {
+        // When every row is a single byte, every occurrence is the first one
+        // in its row, and the row index is the offset in the haystack.
+        crate::tests::memchr::Runner::new(1).forward_iter(
+            |haystack, needles| {
+                let offsets: alloc::vec::Vec<usize> =
+                    (0..=haystack.len()).collect();
+                let it = memchr_in_batch(needles[0], haystack, &offsets);
+                Some(
+                    it.map(|m| {
+                        assert_eq!(0, m.offset());
+                        m.row()
+                    })
+                    .collect(),
+                )
+            },
+        )
+    }
+
+    #[test]
+    fn batch_rows() {
+        let rows: &[&[u8]] = &[b"", b"a,b", b",", b"", b"abc", b"x,,y", b"z"];
+        let mut values = alloc::vec::Vec::new();
+        let mut offsets: alloc::vec::Vec<i32> = alloc::vec![0];
+        for row in rows {
+            values.extend_from_slice(row);
+            offsets.push(i32::try_from(values.len()).unwrap());
+        }
+        let got: alloc::vec::Vec<(usize, usize)> =
+            memchr_in_batch(b',', &values, &offsets)
+                .map(|m| (m.row(), m.offset()))
+                .collect();
+        assert_eq!(got, [(1, 1), (2, 0), (5, 1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "decreasing")]
+    fn batch_decreasing_offsets() {
+        let offsets: [i32; 4] = [0, 8, 3, 10];
+        memchr_in_batch(b',', b"a,bcdef,,g", &offsets).for_each(drop);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn batch_out_of_bounds_offsets() {
+        let offsets: [i32; 3] = [0, 3, 11];
+        memchr_in_batch(b',', b"a,bcdef,,g", &offsets).for_each(drop);
+    }
+
+    #[test]
+    fn owned_double_ended() {
+        let mut it = OwnedMemchr::new(b'a', *b"abacada");
+        assert_eq!(Some(0), it.next());
+        assert_eq!(Some(6), it.next_back());
+        assert_eq!(Some(2), it.next());
+        assert_eq!(Some(4), it.next_back());
+        assert_eq!(None, it.next());
+        assert_eq!(None, it.next_back());
+        assert_eq!(b"abacada", it.haystack());
+    }
+
     // Prior to memchr 2.6, the memchr iterators both implemented Send and
     // Sync. But in memchr 2.6, the iterator changed to use raw pointers
     // internally and I didn't add explicit Send/Sync impls. This ended up
@@ -898,6 +1829,9 @@ mod tests {
         fn assert_send_sync<T: Send + Sync + UnwindSafe + RefUnwindSafe>() {}
         assert_send_sync::<Memchr>();
         assert_send_sync::<Memchr2>();
-        assert_send_sync::<Memchr3>()
+        assert_send_sync::<Memchr3>();
+        assert_send_sync::<OwnedMemchr<alloc::vec::Vec<u8>>>();
+        assert_send_sync::<OwnedMemchr2<alloc::vec::Vec<u8>>>();
+        assert_send_sync::<OwnedMemchr3<alloc::vec::Vec<u8>>>()
     }
 }
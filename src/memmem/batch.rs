@@ -0,0 +1,190 @@
+use crate::{
+    memchr::{BatchMatch, BatchRows},
+    memmem::{is_word_match, searcher::PrefilterState, Finder},
+};
+
+/// An iterator over the rows of a columnar batch of byte strings that contain
+/// a substring.
+///
+/// Each row containing a match is reported exactly once, along with the
+/// position of its first match. Matches never cross row boundaries.
+///
+/// This iterator is created by [`Finder::find_in_batch`].
+///
+/// `'h` is the lifetime of the values buffer, `'o` is the lifetime of the
+/// offsets and `'n` is the lifetime of the needle.
+#[derive(Clone, Debug)]
+pub struct FindBatchIter<'h, 'o, 'n, O> {
+    values: &'h [u8],
+    rows: BatchRows<'o, O>,
+    prestate: PrefilterState,
+    finder: Finder<'n>,
+}
+
+impl<'h, 'o, 'n, O: Copy> FindBatchIter<'h, 'o, 'n, O>
+where
+    usize: TryFrom<O>,
+{
+    #[inline]
+    pub(crate) fn new(
+        values: &'h [u8],
+        offsets: &'o [O],
+        finder: Finder<'n>,
+    ) -> FindBatchIter<'h, 'o, 'n, O> {
+        FindBatchIter {
+            values,
+            rows: BatchRows::new(values.len(), offsets),
+            prestate: PrefilterState::new(),
+            finder,
+        }
+    }
+
+    /// Returns the next row containing a match of an empty needle. Since an
+    /// empty needle matches every row (unless only whole word matches are
+    /// reported), there's nothing to gain by searching the values buffer in
+    /// one pass.
+    fn next_empty(&mut self) -> Option<BatchMatch> {
+        while let Some(row) = self.rows.row() {
+            let (start, end) = self.rows.bounds(row);
+            self.rows.advance();
+            if let Some(offset) = self.finder.find(&self.values[start..end]) {
+                return Some(BatchMatch::new(row, offset));
+            }
+        }
+        None
+    }
+}
+
+impl<'h, 'o, 'n, O: Copy> Iterator for FindBatchIter<'h, 'o, 'n, O>
+where
+    usize: TryFrom<O>,
+{
+    type Item = BatchMatch;
+
+    fn next(&mut self) -> Option<BatchMatch> {
+        let needle = self.finder.needle();
+        if needle.is_empty() {
+            return self.next_empty();
+        }
+        let mut at = self.rows.start()?;
+        let end = self.rows.end();
+        loop {
+            let pos = at
+                + self.finder.searcher.find(
+                    &mut self.prestate,
+                    self.values.get(at..end)?,
+                    needle,
+                )?;
+            let (row, row_start, row_end) = self.rows.seek(pos);
+            if pos + needle.len() > row_end {
+                // The match crosses into the next row. Every other candidate
+                // in this row begins after it, and so must also cross into
+                // the next row.
+                self.rows.advance();
+                at = row_end;
+                continue;
+            }
+            let offset = pos - row_start;
+            if self.finder.whole_word {
+                let bytes = &self.values[row_start..row_end];
+                if !is_word_match(bytes, offset, offset + needle.len()) {
+                    at = pos + 1;
+                    continue;
+                }
+            }
+            self.rows.advance();
+            return Some(BatchMatch::new(row, offset));
+        }
+    }
+}
+
+impl<'h, 'o, 'n, O: Copy> core::iter::FusedIterator
+    for FindBatchIter<'h, 'o, 'n, O>
+where
+    usize: TryFrom<O>,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{memchr::BatchMatch, memmem::FinderBuilder};
+
+    /// Returns the concatenation of the given rows along with their offsets.
+    fn batch(rows: &[&[u8]]) -> (Vec<u8>, Vec<i64>) {
+        let mut values = Vec::new();
+        let mut offsets = alloc::vec![0];
+        for row in rows {
+            values.extend_from_slice(row);
+            offsets.push(i64::try_from(values.len()).unwrap());
+        }
+        (values, offsets)
+    }
+
+    #[test]
+    fn batch_matches_each_row() {
+        let rows: &[&[u8]] = &[
+            b"",
+            b"foo",
+            b"fo",
+            b"o",
+            b"",
+            b"xfoo foo",
+            b"foofoo",
+            b"ofo",
+            b"of",
+            b"oo",
+            b"bar_foo foo",
+            b"f",
+        ];
+        let needles: &[&[u8]] = &[b"", b"f", b"o", b"fo", b"foo", b"oof"];
+        let (values, offsets) = batch(rows);
+        let mut builder = FinderBuilder::new();
+        for whole_word in [false, true] {
+            builder.whole_word(whole_word);
+            for &needle in needles {
+                let finder = builder.build_forward(needle);
+                let expected: Vec<BatchMatch> = rows
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, row)| {
+                        Some(BatchMatch::new(i, finder.find(row)?))
+                    })
+                    .collect();
+                let got: Vec<BatchMatch> =
+                    finder.find_in_batch(&values, &offsets).collect();
+                assert_eq!(
+                    expected, got,
+                    "needle: {:?}, whole word: {:?}",
+                    needle, whole_word,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn batch_subset_of_values() {
+        // The offsets need not start at zero or end at the end of the
+        // values buffer.
+        let values = b"foo|foo|bar|foo";
+        let offsets: [u32; 3] = [4, 8, 12];
+        let finder = FinderBuilder::new().build_forward("foo");
+        let got: Vec<BatchMatch> =
+            finder.find_in_batch(values, &offsets).collect();
+        assert_eq!(got, [BatchMatch::new(0, 0)]);
+
+        let offsets: [u32; 1] = [0];
+        assert_eq!(None, finder.find_in_batch(values, &offsets).next());
+        let offsets: [u32; 0] = [];
+        assert_eq!(None, finder.find_in_batch(values, &offsets).next());
+    }
+
+    #[test]
+    #[should_panic(expected = "decreasing")]
+    fn batch_decreasing_offsets() {
+        let offsets: [i32; 4] = [0, 8, 3, 10];
+        let finder = FinderBuilder::new().build_forward(",");
+        finder.find_in_batch(b"a,bcdef,,g", &offsets).for_each(drop);
+    }
+}
@@ -0,0 +1,279 @@
+use alloc::{vec, vec::Vec};
+
+use crate::arch::all::is_equal;
+
+/// The number of distinct pairs of bytes.
+const BIGRAMS: usize = 1 << 16;
+
+/// An index of a haystack for answering many substring queries against it.
+///
+/// Searching a haystack with [`Finder`](crate::memmem::Finder) always
+/// requires scanning the haystack. When many different needles are searched
+/// for in the same large haystack, this can be avoided by building an index
+/// of the haystack once. This index records the positions of every pair of
+/// adjacent bytes (bigram) in the haystack. A query for a needle with at least
+/// two bytes then only needs to look at the positions of the needle's rarest
+/// bigram, and verifies each one by comparing the needle with the haystack.
+///
+/// Queries for needles with fewer than two bytes don't benefit from the
+/// index, and are answered by scanning the haystack instead.
+///
+/// The index uses one `usize` for every byte in the haystack, plus a fixed
+/// table of `2^16` `usize` values. It is only worth building when the
+/// haystack is searched many times.
+///
+/// This is only available when the `alloc` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::HaystackIndex;
+///
+/// let index = HaystackIndex::new(b"the quick brown fox jumps over the dog");
+/// assert_eq!(Some(4), index.find(b"quick"));
+/// assert_eq!(Some(16), index.find(b"fox"));
+/// assert_eq!(2, index.count(b"the"));
+/// assert_eq!(None, index.find(b"cat"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct HaystackIndex<'h> {
+    haystack: &'h [u8],
+    /// The positions of each bigram are `positions[starts[b]..starts[b+1]]`,
+    /// in ascending order.
+    starts: Vec<usize>,
+    positions: Vec<usize>,
+}
+
+impl<'h> HaystackIndex<'h> {
+    /// Build an index of the given haystack.
+    ///
+    /// This runs in `O(haystack.len())` time.
+    pub fn new(haystack: &'h [u8]) -> HaystackIndex<'h> {
+        let mut starts = vec![0; BIGRAMS + 1];
+        for pair in haystack.windows(2) {
+            starts[bigram(pair) + 1] += 1;
+        }
+        for i in 1..starts.len() {
+            starts[i] += starts[i - 1];
+        }
+        let mut next = starts[..BIGRAMS].to_vec();
+        let mut positions = vec![0; haystack.len().saturating_sub(1)];
+        for (i, pair) in haystack.windows(2).enumerate() {
+            let slot = &mut next[bigram(pair)];
+            positions[*slot] = i;
+            *slot += 1;
+        }
+        HaystackIndex { haystack, starts, positions }
+    }
+
+    /// Returns the haystack that this index was built for.
+    #[inline]
+    pub fn haystack(&self) -> &'h [u8] {
+        self.haystack
+    }
+
+    /// Returns the index of the first occurrence of the given needle in the
+    /// indexed haystack. If no such occurrence exists, then `None` is
+    /// returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::HaystackIndex;
+    ///
+    /// let index = HaystackIndex::new(b"foo bar baz");
+    /// assert_eq!(Some(4), index.find(b"bar"));
+    /// assert_eq!(None, index.find(b"quux"));
+    /// ```
+    #[inline]
+    pub fn find(&self, needle: &[u8]) -> Option<usize> {
+        self.find_iter(needle).next()
+    }
+
+    /// Returns an iterator over all non-overlapping occurrences of the given
+    /// needle in the indexed haystack. This reports the same matches as
+    /// [`find_iter`](crate::memmem::find_iter).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::HaystackIndex;
+    ///
+    /// let index = HaystackIndex::new(b"aaaaa");
+    /// let matches: Vec<usize> = index.find_iter(b"aa").collect();
+    /// assert_eq!(matches, vec![0, 2]);
+    /// ```
+    #[inline]
+    pub fn find_iter<'a, 'n>(
+        &'a self,
+        needle: &'n [u8],
+    ) -> IndexFindIter<'a, 'h, 'n> {
+        let kind = match *needle {
+            [] => Kind::Empty(0..=self.haystack.len()),
+            [b] => Kind::Byte(crate::memchr_iter(b, self.haystack)),
+            _ => {
+                // Pick the bigram in the needle with the fewest occurrences
+                // in the haystack. Every match must have this bigram at the
+                // same offset.
+                let (shift, candidates) = needle
+                    .windows(2)
+                    .map(|pair| self.positions(bigram(pair)))
+                    .enumerate()
+                    .min_by_key(|(_, positions)| positions.len())
+                    .unwrap();
+                Kind::Candidates { candidates, shift, at: 0 }
+            }
+        };
+        IndexFindIter { haystack: self.haystack, needle, kind }
+    }
+
+    /// Returns the number of non-overlapping occurrences of the given needle
+    /// in the indexed haystack.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::HaystackIndex;
+    ///
+    /// let index = HaystackIndex::new(b"foo bar foo baz foo");
+    /// assert_eq!(3, index.count(b"foo"));
+    /// assert_eq!(2, index.count(b"ba"));
+    /// ```
+    #[inline]
+    pub fn count(&self, needle: &[u8]) -> usize {
+        self.find_iter(needle).count()
+    }
+
+    /// Returns the heap memory, in bytes, used by this index.
+    #[inline]
+    pub fn memory_usage(&self) -> usize {
+        (self.starts.len() + self.positions.len())
+            * core::mem::size_of::<usize>()
+    }
+
+    /// Returns the positions in the haystack of the given bigram.
+    #[inline]
+    fn positions(&self, bigram: usize) -> &[usize] {
+        &self.positions[self.starts[bigram]..self.starts[bigram + 1]]
+    }
+}
+
+/// An iterator over non-overlapping substring matches in an indexed
+/// haystack.
+///
+/// Matches are reported by the byte offset at which they begin.
+///
+/// This iterator is created by [`HaystackIndex::find_iter`].
+///
+/// `'a` is the lifetime of the index, `'h` is the lifetime of the haystack
+/// and `'n` is the lifetime of the needle.
+#[derive(Clone, Debug)]
+pub struct IndexFindIter<'a, 'h, 'n> {
+    haystack: &'h [u8],
+    needle: &'n [u8],
+    kind: Kind<'a, 'h>,
+}
+
+#[derive(Clone, Debug)]
+enum Kind<'a, 'h> {
+    /// An empty needle matches at every position.
+    Empty(core::ops::RangeInclusive<usize>),
+    /// A single byte needle is searched for directly.
+    Byte(crate::Memchr<'h>),
+    /// Otherwise, every candidate is the position of a bigram that occurs at
+    /// the offset `shift` in the needle. Only candidates that begin at or
+    /// after `at` can be reported, which guarantees non-overlapping matches.
+    Candidates { candidates: &'a [usize], shift: usize, at: usize },
+}
+
+impl<'a, 'h, 'n> Iterator for IndexFindIter<'a, 'h, 'n> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let (candidates, shift, at) = match self.kind {
+            Kind::Empty(ref mut it) => return it.next(),
+            Kind::Byte(ref mut it) => return it.next(),
+            Kind::Candidates { ref mut candidates, shift, ref mut at } => {
+                (candidates, shift, at)
+            }
+        };
+        loop {
+            let remaining: &'a [usize] = candidates;
+            let (&pos, rest) = remaining.split_first()?;
+            *candidates = rest;
+            let start = match pos.checked_sub(shift) {
+                Some(start) if start >= *at => start,
+                _ => continue,
+            };
+            let end = start + self.needle.len();
+            let matched = self
+                .haystack
+                .get(start..end)
+                .map_or(false, |window| is_equal(window, self.needle));
+            if matched {
+                *at = end;
+                return Some(start);
+            }
+        }
+    }
+}
+
+impl<'a, 'h, 'n> core::iter::FusedIterator for IndexFindIter<'a, 'h, 'n> {}
+
+/// Returns the bigram formed by the first two bytes of the given slice.
+#[inline(always)]
+fn bigram(pair: &[u8]) -> usize {
+    (usize::from(pair[0]) << 8) | usize::from(pair[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn same_as_find_iter() {
+        let haystacks: &[&[u8]] = &[
+            b"",
+            b"a",
+            b"ab",
+            b"aaaaaaaaa",
+            b"abababababa",
+            b"abcabcabcabc",
+            b"the quick brown fox jumps over the lazy dog",
+        ];
+        let needles: &[&[u8]] = &[
+            b"",
+            b"a",
+            b"z",
+            b"aa",
+            b"aaa",
+            b"ab",
+            b"aba",
+            b"abab",
+            b"cab",
+            b"the",
+            b"o",
+            b"fox jumps",
+            b"dog",
+            b"dogs",
+            b"zz",
+        ];
+        for &haystack in haystacks {
+            let index = HaystackIndex::new(haystack);
+            for &needle in needles {
+                let expected: Vec<usize> =
+                    crate::memmem::find_iter(haystack, needle).collect();
+                let got: Vec<usize> = index.find_iter(needle).collect();
+                assert_eq!(
+                    expected, got,
+                    "haystack: {:?}, needle: {:?}",
+                    haystack, needle,
+                );
+                assert_eq!(expected.len(), index.count(needle));
+                assert_eq!(expected.first().copied(), index.find(needle));
+            }
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use crate::memmem::FindIter;
+
+/// An iterator over the lines of a haystack that contain at least one match.
+///
+/// Each line containing a match is reported exactly once, regardless of how
+/// many matches it contains. A line is a sequence of bytes terminated by
+/// `\n` or by the end of the haystack. The range of each line reported
+/// includes its line terminator, if one is present. If a match spans more
+/// than one line, then only the line on which it begins is reported.
+///
+/// Optionally, lines surrounding each matching line can be reported as
+/// context via [`FindLinesIter::before_context`] and
+/// [`FindLinesIter::after_context`], and line numbers can be computed via
+/// [`FindLinesIter::line_numbers`]. Context lines are never reported more than
+/// once, and lines are always reported in the order in which they appear in
+/// the haystack.
+///
+/// This iterator is created by
+/// [`Finder::find_lines_iter`](crate::memmem::Finder::find_lines_iter).
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+#[derive(Clone, Debug)]
+pub struct FindLinesIter<'h, 'n> {
+    it: FindIter<'h, 'n>,
+    /// The start of the next line that has not yet been reported or skipped.
+    /// This is always at the beginning of a line.
+    pos: usize,
+    /// The line number of the line starting at `pos`, if line numbers were
+    /// requested.
+    line_number: Option<usize>,
+    /// The start of the next line containing a match at or after `pos`. The
+    /// outer `None` indicates that it hasn't been computed yet, while the
+    /// inner `None` indicates that there are no more matching lines.
+    next_match_line: Option<Option<usize>>,
+    before: usize,
+    after: usize,
+    /// The number of after-context lines left to report for the most
+    /// recently reported matching line.
+    after_left: usize,
+}
+
+impl<'h, 'n> FindLinesIter<'h, 'n> {
+    #[inline]
+    pub(crate) fn new(it: FindIter<'h, 'n>) -> FindLinesIter<'h, 'n> {
+        FindLinesIter {
+            it,
+            pos: 0,
+            line_number: None,
+            next_match_line: None,
+            before: 0,
+            after: 0,
+            after_left: 0,
+        }
+    }
+
+    /// When enabled, every line reported includes its 1-based line number.
+    /// This is disabled by default.
+    ///
+    /// Computing line numbers requires counting the line terminators in
+    /// every part of the haystack that is skipped. This is done with a
+    /// vectorized routine, but it is still more work than not counting them.
+    ///
+    /// This should be called before iteration begins. If it is called after,
+    /// then line numbers are counted from the position at which the iterator
+    /// currently is.
+    #[inline]
+    pub fn line_numbers(mut self, yes: bool) -> FindLinesIter<'h, 'n> {
+        self.line_number = if yes { Some(1) } else { None };
+        self
+    }
+
+    /// Set the number of lines to report before each matching line. This is
+    /// `0` by default.
+    ///
+    /// Context lines never precede the end of the previously reported line.
+    #[inline]
+    pub fn before_context(mut self, lines: usize) -> FindLinesIter<'h, 'n> {
+        self.before = lines;
+        self
+    }
+
+    /// Set the number of lines to report after each matching line. This is
+    /// `0` by default.
+    ///
+    /// If a matching line occurs within the after-context of a previous
+    /// matching line, then it is reported as a match and the after-context
+    /// restarts from it.
+    #[inline]
+    pub fn after_context(mut self, lines: usize) -> FindLinesIter<'h, 'n> {
+        self.after = lines;
+        self
+    }
+
+    /// Returns the start of the next line containing a match, using the
+    /// cached value if present.
+    #[inline]
+    fn next_match_line(&mut self) -> Option<usize> {
+        if let Some(start) = self.next_match_line {
+            return start;
+        }
+        self.it.pos = self.pos;
+        let start = self.it.next().and_then(|m| {
+            let haystack = self.it.haystack;
+            let start =
+                crate::memrchr(b'\n', &haystack[..m]).map_or(0, |i| i + 1);
+            // A match at the very end of a haystack that is empty or that
+            // ends with a line terminator doesn't belong to any line.
+            if start == haystack.len() {
+                None
+            } else {
+                Some(start)
+            }
+        });
+        self.next_match_line = Some(start);
+        start
+    }
+
+    /// Report the line starting at `self.pos` and advance past it.
+    #[inline]
+    fn report(&mut self, is_match: bool) -> Line<'h> {
+        let haystack = self.it.haystack;
+        let start = self.pos;
+        let end = crate::memchr(b'\n', &haystack[start..])
+            .map_or(haystack.len(), |i| start + i + 1);
+        let line_number = self.line_number;
+        self.pos = end;
+        self.line_number = self.line_number.map(|n| n + 1);
+        Line { bytes: &haystack[start..end], start, line_number, is_match }
+    }
+
+    /// Skip to the line starting at `start`, which must be at or after
+    /// `self.pos`.
+    #[inline]
+    fn skip_to(&mut self, start: usize) {
+        debug_assert!(self.pos <= start);
+        if let Some(n) = self.line_number {
+            let skipped = &self.it.haystack[self.pos..start];
+            self.line_number =
+                Some(n + crate::memchr_iter(b'\n', skipped).count());
+        }
+        self.pos = start;
+    }
+}
+
+impl<'h, 'n> Iterator for FindLinesIter<'h, 'n> {
+    type Item = Line<'h>;
+
+    fn next(&mut self) -> Option<Line<'h>> {
+        let haystack = self.it.haystack;
+        if self.pos >= haystack.len() {
+            return None;
+        }
+        let next_match = self.next_match_line();
+        if next_match == Some(self.pos) {
+            self.next_match_line = None;
+            self.after_left = self.after;
+            return Some(self.report(true));
+        }
+        if self.after_left > 0 {
+            self.after_left -= 1;
+            return Some(self.report(false));
+        }
+        let match_start = next_match?;
+        // Walk backwards from the matching line to find where its
+        // before-context begins, without going past the last line reported.
+        let mut start = match_start;
+        for _ in 0..self.before {
+            if start <= self.pos {
+                break;
+            }
+            start = crate::memrchr(b'\n', &haystack[self.pos..start - 1])
+                .map_or(self.pos, |i| self.pos + i + 1);
+        }
+        self.skip_to(start);
+        let is_match = start == match_start;
+        if is_match {
+            self.next_match_line = None;
+            self.after_left = self.after;
+        }
+        Some(self.report(is_match))
+    }
+}
+
+/// A single line reported by a [`FindLinesIter`].
+///
+/// `'h` is the lifetime of the haystack.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Line<'h> {
+    bytes: &'h [u8],
+    start: usize,
+    line_number: Option<usize>,
+    is_match: bool,
+}
+
+impl<'h> Line<'h> {
+    /// Returns the bytes of this line, including its line terminator if
+    /// present.
+    #[inline]
+    pub fn as_bytes(&self) -> &'h [u8] {
+        self.bytes
+    }
+
+    /// Returns the offset in the haystack at which this line begins.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the offset in the haystack at which this line ends. This is
+    /// the offset immediately following its line terminator, if present.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.start + self.bytes.len()
+    }
+
+    /// Returns the range of this line in the haystack.
+    #[inline]
+    pub fn range(&self) -> core::ops::Range<usize> {
+        self.start()..self.end()
+    }
+
+    /// Returns the 1-based line number of this line, if line numbers were
+    /// requested via [`FindLinesIter::line_numbers`].
+    #[inline]
+    pub fn line_number(&self) -> Option<usize> {
+        self.line_number
+    }
+
+    /// Returns true if this line contains a match, and false if it was only
+    /// reported as context.
+    #[inline]
+    pub fn is_match(&self) -> bool {
+        self.is_match
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+
+    use crate::memmem::Finder;
+
+    /// Renders the lines reported in a grep-like format, where matching
+    /// lines use a `:` separator and context lines use a `-` separator.
+    fn grep(
+        haystack: &str,
+        needle: &str,
+        before: usize,
+        after: usize,
+    ) -> String {
+        let finder = Finder::new(needle);
+        let mut out = String::new();
+        let it = finder
+            .find_lines_iter(haystack.as_bytes())
+            .line_numbers(true)
+            .before_context(before)
+            .after_context(after);
+        for line in it {
+            let n = line.line_number().unwrap();
+            let sep = if line.is_match() { ':' } else { '-' };
+            out.push_str(&alloc::format!("{}{}", n, sep));
+            out.push_str(&haystack[line.range()]);
+        }
+        out
+    }
+
+    #[test]
+    fn no_context() {
+        let haystack = "foo\nbar\nfoo foo\nbaz\nfoo";
+        assert_eq!("1:foo\n3:foo foo\n5:foo", grep(haystack, "foo", 0, 0));
+        assert_eq!("2:bar\n4:baz\n", grep(haystack, "ba", 0, 0));
+        assert_eq!("", grep(haystack, "quux", 0, 0));
+        assert_eq!("", grep("", "foo", 0, 0));
+        assert_eq!("", grep("", "", 0, 0));
+        assert_eq!("1:a\n2:\n", grep("a\n\n", "", 0, 0));
+        // A match spanning lines is reported on the line where it begins.
+        assert_eq!("2:bar\n", grep(haystack, "bar\nfoo", 0, 0));
+    }
+
+    #[test]
+    fn context() {
+        let haystack = "a\nb\nc\nX\nd\ne\nf\ng\nX\nX\nh\n";
+        assert_eq!(
+            "3-c\n4:X\n5-d\n8-g\n9:X\n10:X\n11-h\n",
+            grep(haystack, "X", 1, 1),
+        );
+        assert_eq!(
+            "1-a\n2-b\n3-c\n4:X\n5-d\n6-e\n7-f\n8-g\n9:X\n10:X\n11-h\n",
+            grep(haystack, "X", 3, 2),
+        );
+        assert_eq!(
+            "4:X\n5-d\n6-e\n9:X\n10:X\n11-h\n",
+            grep(haystack, "X", 0, 2)
+        );
+        assert_eq!(
+            "2-b\n3-c\n4:X\n7-f\n8-g\n9:X\n10:X\n",
+            grep(haystack, "X", 2, 0)
+        );
+    }
+
+    #[test]
+    fn without_line_numbers() {
+        let finder = Finder::new("b");
+        let lines: Vec<_> = finder
+            .find_lines_iter(b"a\nb\nc")
+            .after_context(5)
+            .map(|line| (line.range(), line.line_number(), line.is_match()))
+            .collect();
+        assert_eq!(lines, [(2..4, None, true), (4..5, None, false)]);
+    }
+}
@@ -66,7 +66,19 @@ assert_eq!(None, finder.find(b"quux baz bar"));
 ```
 */
 
-pub use crate::memmem::searcher::PrefilterConfig as Prefilter;
+pub use crate::memmem::{
+    batch::FindBatchIter,
+    lines::{FindLinesIter, Line},
+    prefilter::{PrefilterFinder, PrefilterIter},
+    proximity::{ProximityFinder, ProximityIter, ProximityMatch},
+    searcher::{PrefilterConfig as Prefilter, Strategy},
+    segmented::FindSegmentedIter,
+    serialize::DeserializeError,
+    stats::SearchStats,
+};
+
+#[cfg(feature = "alloc")]
+pub use crate::memmem::index::{HaystackIndex, IndexFindIter};
 
 // This is exported here for use in the crate::arch::all::twoway
 // implementation. This is essentially an abstraction breaker. Namely, the
@@ -78,13 +90,28 @@ pub(crate) use crate::memmem::searcher::Pre;
 use crate::{
     arch::all::{
         packedpair::{DefaultFrequencyRank, HeuristicFrequencyRank},
-        rabinkarp,
+        rabinkarp, twoway,
     },
     cow::CowBytes,
     memmem::searcher::{PrefilterState, Searcher, SearcherRev},
 };
 
-mod searcher;
+mod batch;
+#[cfg(feature = "alloc")]
+mod index;
+mod lines;
+mod prefilter;
+mod proximity;
+pub(crate) mod searcher;
+mod segmented;
+mod serialize;
+#[cfg(any(
+    all(target_arch = "x86_64", target_feature = "sse2"),
+    all(target_arch = "wasm32", target_feature = "simd128"),
+    target_arch = "aarch64"
+))]
+mod shortneedle;
+mod stats;
 
 /// Returns an iterator over all non-overlapping occurrences of a substring in
 /// a haystack.
@@ -227,6 +254,90 @@ pub fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
     }
 }
 
+/// Returns the index of the first occurrence of the given needle in a
+/// `const` context.
+///
+/// This returns the same result as [`find`], but it can be called in a
+/// `const` context, for example, to validate data included with
+/// `include_bytes!` at compile time. It always uses the scalar Two-Way
+/// algorithm, and so retains the linear time guarantee of [`find`]. Outside
+/// of a `const` context, [`find`] should be used instead.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem;
+///
+/// const DATA: &[u8] = b"HEADER\r\n\r\nBODY";
+/// const BODY: usize = match memmem::find_const(DATA, b"\r\n\r\n") {
+///     Some(i) => i + 4,
+///     None => panic!("missing end of header"),
+/// };
+/// assert_eq!(b"BODY", &DATA[BODY..]);
+/// ```
+#[inline]
+pub const fn find_const(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    twoway::Finder::new(needle).find_const(haystack, needle)
+}
+
+/// Returns the index of the last occurrence of the given needle in a `const`
+/// context.
+///
+/// This returns the same result as [`rfind`], but it can be called in a
+/// `const` context. It always uses the scalar Two-Way algorithm, and so
+/// retains the linear time guarantee of [`rfind`]. Outside of a `const`
+/// context, [`rfind`] should be used instead.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem;
+///
+/// const LAST: Option<usize> = memmem::rfind_const(b"foo bar foo", b"foo");
+/// assert_eq!(LAST, Some(8));
+/// ```
+#[inline]
+pub const fn rfind_const(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    twoway::FinderRev::new(needle).rfind(haystack, needle)
+}
+
+/// Returns the position of the first occurrence of the given needle in a
+/// sequence of segments that are treated as one logical haystack.
+///
+/// This is useful when a haystack isn't stored contiguously in memory, for
+/// example, a ring buffer like `VecDeque<u8>` or a chain of `IoSlice`s. It
+/// avoids the need to copy all of the segments into a single slice before
+/// searching. Each segment may be any type that dereferences to `[u8]`.
+///
+/// Matches may cross segment boundaries. See [`Finder::find_segmented`] for
+/// more details.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use std::io::IoSlice;
+///
+/// use memchr::memmem;
+///
+/// let chain = [
+///     IoSlice::new(b"HTTP/1.1 200 OK\r"),
+///     IoSlice::new(b"\nContent-Length: 0\r\n\r"),
+///     IoSlice::new(b"\n"),
+/// ];
+/// let pos = memmem::find_segmented(&chain, b"\r\n\r\n").unwrap();
+/// assert_eq!(34, pos.offset());
+/// assert_eq!((1, 18), (pos.segment(), pos.segment_offset()));
+/// ```
+#[inline]
+pub fn find_segmented<S: core::ops::Deref<Target = [u8]>>(
+    segments: &[S],
+    needle: &[u8],
+) -> Option<crate::SegmentPos> {
+    Finder::new(needle).find_segmented(segments)
+}
+
 /// An iterator over non-overlapping substring matches.
 ///
 /// Matches are reported by the byte offset at which they begin.
@@ -239,6 +350,7 @@ pub struct FindIter<'h, 'n> {
     prestate: PrefilterState,
     finder: Finder<'n>,
     pos: usize,
+    stats: Option<SearchStats>,
 }
 
 impl<'h, 'n> FindIter<'h, 'n> {
@@ -248,7 +360,39 @@ impl<'h, 'n> FindIter<'h, 'n> {
         finder: Finder<'n>,
     ) -> FindIter<'h, 'n> {
         let prestate = PrefilterState::new();
-        FindIter { haystack, prestate, finder, pos: 0 }
+        FindIter { haystack, prestate, finder, pos: 0, stats: None }
+    }
+
+    /// Enable the collection of statistics for the searches executed by this
+    /// iterator.
+    ///
+    /// The statistics collected so far can be retrieved with
+    /// [`FindIter::stats`]. See [`SearchStats`] for more details.
+    ///
+    /// If statistics are already being collected, then this resets them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new("foo");
+    /// let mut it = finder.find_iter(b"foo bar foo").with_stats();
+    /// assert_eq!(2, it.by_ref().count());
+    /// let stats = it.stats().unwrap();
+    /// // One search for each match, and one that finds nothing.
+    /// assert_eq!(3, stats.searches());
+    /// ```
+    #[inline]
+    pub fn with_stats(self) -> FindIter<'h, 'n> {
+        FindIter { stats: Some(SearchStats::new()), ..self }
+    }
+
+    /// Returns the statistics collected by this iterator, if they were
+    /// enabled with [`FindIter::with_stats`].
+    #[inline]
+    pub fn stats(&self) -> Option<&SearchStats> {
+        self.stats.as_ref()
     }
 
     /// Convert this iterator into its owned variant, such that it no longer
@@ -266,6 +410,7 @@ impl<'h, 'n> FindIter<'h, 'n> {
             prestate: self.prestate,
             finder: self.finder.into_owned(),
             pos: self.pos,
+            stats: self.stats,
         }
     }
 }
@@ -274,14 +419,22 @@ impl<'h, 'n> Iterator for FindIter<'h, 'n> {
     type Item = usize;
 
     fn next(&mut self) -> Option<usize> {
-        let needle = self.finder.needle();
-        let haystack = self.haystack.get(self.pos..)?;
-        let idx =
-            self.finder.searcher.find(&mut self.prestate, haystack, needle)?;
-
-        let pos = self.pos + idx;
-        self.pos = pos + needle.len().max(1);
-
+        let pos = match self.stats {
+            None => self.finder.find_in(
+                &mut self.prestate,
+                self.haystack,
+                self.pos,
+                self.haystack.len(),
+            )?,
+            Some(ref mut stats) => self.finder.find_in_with_stats(
+                &mut self.prestate,
+                self.haystack,
+                self.pos,
+                self.haystack.len(),
+                stats,
+            )?,
+        };
+        self.pos = pos + self.finder.needle().len().max(1);
         Some(pos)
     }
 
@@ -351,19 +504,124 @@ impl<'h, 'n> Iterator for FindRevIter<'h, 'n> {
     type Item = usize;
 
     fn next(&mut self) -> Option<usize> {
-        let pos = self.pos?;
-        let result = self.finder.rfind(&self.haystack[..pos]);
-        match result {
-            None => None,
-            Some(i) => {
-                if pos == i {
-                    self.pos = pos.checked_sub(1);
-                } else {
-                    self.pos = Some(i);
-                }
-                Some(i)
-            }
-        }
+        rfind_next(&self.finder, self.haystack, &mut self.pos)
+    }
+}
+
+/// Returns the last match ending at or before `pos` and moves `pos` before
+/// it, so that matches don't overlap. `pos` is set to `None` once an empty
+/// needle has matched at `0`.
+///
+/// This is shared by [`FindRevIter`] and [`OwnedFindRevIter`].
+#[inline(always)]
+fn rfind_next(
+    finder: &FinderRev<'_>,
+    haystack: &[u8],
+    pos: &mut Option<usize>,
+) -> Option<usize> {
+    let i = finder.rfind_to(haystack, (*pos)?)?;
+    // An empty needle can match before `pos` when only whole words are
+    // reported, so we check the needle instead of whether `pos == i`.
+    if finder.needle().is_empty() {
+        *pos = i.checked_sub(1);
+    } else {
+        *pos = Some(i);
+    }
+    Some(i)
+}
+
+/// An iterator over non-overlapping substring matches in a haystack that it
+/// owns.
+///
+/// This is like [`FindIter`], except the haystack is owned by the iterator
+/// instead of borrowed. The haystack may be any type that can be cheaply
+/// converted into a `&[u8]`, such as `Vec<u8>`, `Box<[u8]>` or `Arc<[u8]>`.
+/// When both the haystack type and the needle are `'static`, then so is this
+/// iterator. This makes it possible to return this iterator from a function
+/// or store it in a long-lived data structure without borrowing the haystack.
+///
+/// This iterator is created by [`Finder::into_find_iter`].
+///
+/// Matches are reported by the byte offset at which they begin.
+///
+/// `'n` is the lifetime of the needle while `H` is the type of the haystack.
+#[derive(Clone, Debug)]
+pub struct OwnedFindIter<'n, H> {
+    haystack: H,
+    prestate: PrefilterState,
+    finder: Finder<'n>,
+    pos: usize,
+}
+
+impl<'n, H: AsRef<[u8]>> OwnedFindIter<'n, H> {
+    /// Returns a reference to the haystack owned by this iterator.
+    #[inline]
+    pub fn haystack(&self) -> &H {
+        &self.haystack
+    }
+
+    /// Consumes this iterator and returns the haystack it owns.
+    #[inline]
+    pub fn into_haystack(self) -> H {
+        self.haystack
+    }
+}
+
+impl<'n, H: AsRef<[u8]>> Iterator for OwnedFindIter<'n, H> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let haystack = self.haystack.as_ref();
+        let pos = self.finder.find_in(
+            &mut self.prestate,
+            haystack,
+            self.pos,
+            haystack.len(),
+        )?;
+        self.pos = pos + self.finder.needle().len().max(1);
+        Some(pos)
+    }
+}
+
+/// An iterator over non-overlapping substring matches in reverse in a
+/// haystack that it owns.
+///
+/// This is like [`FindRevIter`], except the haystack is owned by the
+/// iterator instead of borrowed. See [`OwnedFindIter`] for more details.
+///
+/// This iterator is created by [`FinderRev::into_rfind_iter`].
+///
+/// Matches are reported by the byte offset at which they begin.
+///
+/// `'n` is the lifetime of the needle while `H` is the type of the haystack.
+#[derive(Clone, Debug)]
+pub struct OwnedFindRevIter<'n, H> {
+    haystack: H,
+    finder: FinderRev<'n>,
+    /// When searching with an empty needle, this gets set to `None` after
+    /// we've yielded the last element at `0`.
+    pos: Option<usize>,
+}
+
+impl<'n, H: AsRef<[u8]>> OwnedFindRevIter<'n, H> {
+    /// Returns a reference to the haystack owned by this iterator.
+    #[inline]
+    pub fn haystack(&self) -> &H {
+        &self.haystack
+    }
+
+    /// Consumes this iterator and returns the haystack it owns.
+    #[inline]
+    pub fn into_haystack(self) -> H {
+        self.haystack
+    }
+}
+
+impl<'n, H: AsRef<[u8]>> Iterator for OwnedFindRevIter<'n, H> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        rfind_next(&self.finder, self.haystack.as_ref(), &mut self.pos)
     }
 }
 
@@ -384,6 +642,7 @@ impl<'h, 'n> Iterator for FindRevIter<'h, 'n> {
 pub struct Finder<'n> {
     needle: CowBytes<'n>,
     searcher: Searcher,
+    pub(crate) whole_word: bool,
 }
 
 impl<'n> Finder<'n> {
@@ -393,6 +652,41 @@ impl<'n> Finder<'n> {
         FinderBuilder::new().build_forward(needle)
     }
 
+    /// Create a new finder for the given needle in a `const` context.
+    ///
+    /// This permits building a finder for a needle that is known at compile
+    /// time, such that no construction cost is paid at runtime. The needle
+    /// is analyzed in the same way as [`Finder::new`] analyzes it: the same
+    /// pair of rare bytes is chosen using the default frequency ranking and
+    /// the same Two-Way factorization is computed.
+    ///
+    /// Since vector algorithms can't be set up at compile time (and some of
+    /// them depend on CPU features that are only known at runtime), a finder
+    /// built by this routine never uses them. Instead, it always uses the
+    /// scalar Two-Way algorithm, accelerated by a prefilter based on the rare
+    /// bytes in the needle. This prefilter still uses a vectorized `memchr`
+    /// when it is available. For short needles searched in long haystacks,
+    /// a finder built at runtime with [`Finder::new`] may be faster.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// static FINDER: Finder<'static> = Finder::new_const(b"</script>");
+    ///
+    /// let haystack = b"<script>alert(1)</script>";
+    /// assert_eq!(Some(16), FINDER.find(haystack));
+    /// ```
+    #[inline]
+    pub const fn new_const(needle: &'n [u8]) -> Finder<'n> {
+        Finder {
+            needle: CowBytes::from_slice(needle),
+            searcher: Searcher::new_const(needle),
+            whole_word: false,
+        }
+    }
+
     /// Returns the index of the first occurrence of this needle in the given
     /// haystack.
     ///
@@ -421,7 +715,107 @@ impl<'n> Finder<'n> {
     pub fn find(&self, haystack: &[u8]) -> Option<usize> {
         let mut prestate = PrefilterState::new();
         let needle = self.needle.as_slice();
-        self.searcher.find(&mut prestate, haystack, needle)
+        if self.whole_word {
+            self.find_in(&mut prestate, haystack, 0, haystack.len())
+        } else {
+            self.searcher.find(&mut prestate, haystack, needle)
+        }
+    }
+
+    /// Returns the index of the first occurrence of this needle in
+    /// `haystack[start..end]`, relative to the beginning of `haystack`.
+    ///
+    /// Unlike slicing the haystack before searching, this permits the bytes
+    /// outside of `start..end` to be consulted when this finder only reports
+    /// whole word matches. If `start..end` is not a valid range in the
+    /// haystack, then this returns `None`.
+    #[inline(always)]
+    pub(crate) fn find_in(
+        &self,
+        prestate: &mut PrefilterState,
+        haystack: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Option<usize> {
+        let needle = self.needle.as_slice();
+        let mut at = start;
+        loop {
+            let idx = self.searcher.find(
+                prestate,
+                haystack.get(at..end)?,
+                needle,
+            )?;
+            let pos = at + idx;
+            if !self.whole_word
+                || is_word_match(haystack, pos, pos + needle.len())
+            {
+                return Some(pos);
+            }
+            at = pos + 1;
+        }
+    }
+
+    /// Like `find_in`, but also updates the given statistics.
+    fn find_in_with_stats(
+        &self,
+        prestate: &mut PrefilterState,
+        haystack: &[u8],
+        start: usize,
+        end: usize,
+        stats: &mut SearchStats,
+    ) -> Option<usize> {
+        let needle = self.needle.as_slice();
+        let mut at = start;
+        loop {
+            let idx = self.searcher.find_with_stats(
+                prestate,
+                haystack.get(at..end)?,
+                needle,
+                stats,
+            )?;
+            let pos = at + idx;
+            if !self.whole_word
+                || is_word_match(haystack, pos, pos + needle.len())
+            {
+                return Some(pos);
+            }
+            at = pos + 1;
+        }
+    }
+
+    /// Returns the index of the first occurrence of this needle in the given
+    /// haystack, and adds statistics about the search to `stats`.
+    ///
+    /// This reports the same match as [`Finder::find`], but is usually
+    /// slower. It is meant for profiling. See [`SearchStats`] for the
+    /// statistics that are collected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::{Finder, SearchStats};
+    ///
+    /// let finder = Finder::new("foo");
+    /// let mut stats = SearchStats::new();
+    /// for haystack in [&b"foo bar"[..], b"bar baz", b"quux foo"] {
+    ///     finder.find_with_stats(haystack, &mut stats);
+    /// }
+    /// assert_eq!(3, stats.searches());
+    /// ```
+    #[inline]
+    pub fn find_with_stats(
+        &self,
+        haystack: &[u8],
+        stats: &mut SearchStats,
+    ) -> Option<usize> {
+        let mut prestate = PrefilterState::new();
+        self.find_in_with_stats(
+            &mut prestate,
+            haystack,
+            0,
+            haystack.len(),
+            stats,
+        )
     }
 
     /// Returns an iterator over all occurrences of a substring in a haystack.
@@ -458,117 +852,685 @@ impl<'n> Finder<'n> {
         FindIter::new(haystack, self.as_ref())
     }
 
-    /// Convert this finder into its owned variant, such that it no longer
-    /// borrows the needle.
+    /// Returns an iterator over all lines in a haystack that contain at least
+    /// one occurrence of this needle.
     ///
-    /// If this is already an owned finder, then this is a no-op. Otherwise,
-    /// this copies the needle.
+    /// Each such line is reported exactly once, along with its range in the
+    /// haystack. The iterator returned can also be configured to report line
+    /// numbers and lines of context surrounding each matching line. See
+    /// [`FindLinesIter`] for more details.
     ///
-    /// This is only available when the `alloc` feature is enabled.
-    #[cfg(feature = "alloc")]
-    #[inline]
-    pub fn into_owned(self) -> Finder<'static> {
-        Finder { needle: self.needle.into_owned(), searcher: self.searcher }
-    }
-
-    /// Convert this finder into its borrowed variant.
+    /// Lines are found by searching for the needle and then expanding each
+    /// match to its line boundaries with [`memrchr`](crate::memrchr) and
+    /// [`memchr`](crate::memchr). Searching then resumes at the beginning of
+    /// the following line.
     ///
-    /// This is primarily useful if your finder is owned and you'd like to
-    /// store its borrowed variant in some intermediate data structure.
+    /// # Examples
     ///
-    /// Note that the lifetime parameter of the returned finder is tied to the
-    /// lifetime of `self`, and may be shorter than the `'n` lifetime of the
-    /// needle itself. Namely, a finder's needle can be either borrowed or
-    /// owned, so the lifetime of the needle returned must necessarily be the
-    /// shorter of the two.
-    #[inline]
-    pub fn as_ref(&self) -> Finder<'_> {
-        Finder {
-            needle: CowBytes::new(self.needle()),
-            searcher: self.searcher.clone(),
-        }
-    }
-
-    /// Returns the needle that this finder searches for.
+    /// Basic usage:
     ///
-    /// Note that the lifetime of the needle returned is tied to the lifetime
-    /// of the finder, and may be shorter than the `'n` lifetime. Namely, a
-    /// finder's needle can be either borrowed or owned, so the lifetime of the
-    /// needle returned must necessarily be the shorter of the two.
-    #[inline]
-    pub fn needle(&self) -> &[u8] {
-        self.needle.as_slice()
-    }
-}
-
-/// A single substring reverse searcher fixed to a particular needle.
-///
-/// The purpose of this type is to permit callers to construct a substring
-/// searcher that can be used to search haystacks without the overhead of
-/// constructing the searcher in the first place. This is a somewhat niche
-/// concern when it's necessary to re-use the same needle to search multiple
-/// different haystacks with as little overhead as possible. In general,
-/// using [`rfind`] is good enough, but `FinderRev` is useful when you can
-/// meaningfully observe searcher construction time in a profile.
-///
-/// When the `std` feature is enabled, then this type has an `into_owned`
-/// version which permits building a `FinderRev` that is not connected to
-/// the lifetime of its needle.
-#[derive(Clone, Debug)]
-pub struct FinderRev<'n> {
-    needle: CowBytes<'n>,
-    searcher: SearcherRev,
-}
-
-impl<'n> FinderRev<'n> {
-    /// Create a new reverse finder for the given needle.
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"INFO: ok\nERROR: disk full\nINFO: ok\nERROR: oops\n";
+    /// let finder = Finder::new("ERROR");
+    /// let lines: Vec<(usize, &[u8])> = finder
+    ///     .find_lines_iter(haystack)
+    ///     .line_numbers(true)
+    ///     .map(|line| (line.line_number().unwrap(), line.as_bytes()))
+    ///     .collect();
+    /// assert_eq!(lines, vec![
+    ///     (2, &b"ERROR: disk full\n"[..]),
+    ///     (4, &b"ERROR: oops\n"[..]),
+    /// ]);
+    /// ```
+    ///
+    /// This shows how to report one line of context before and after each
+    /// matching line:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"a\nb\nc\nd\ne\n";
+    /// let finder = Finder::new("c");
+    /// let lines: Vec<(&[u8], bool)> = finder
+    ///     .find_lines_iter(haystack)
+    ///     .before_context(1)
+    ///     .after_context(1)
+    ///     .map(|line| (line.as_bytes(), line.is_match()))
+    ///     .collect();
+    /// assert_eq!(lines, vec![
+    ///     (&b"b\n"[..], false),
+    ///     (&b"c\n"[..], true),
+    ///     (&b"d\n"[..], false),
+    /// ]);
+    /// ```
     #[inline]
-    pub fn new<B: ?Sized + AsRef<[u8]>>(needle: &'n B) -> FinderRev<'n> {
-        FinderBuilder::new().build_reverse(needle)
+    pub fn find_lines_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> FindLinesIter<'h, 'a> {
+        FindLinesIter::new(self.find_iter(haystack))
     }
 
-    /// Returns the index of the last occurrence of this needle in the given
-    /// haystack.
+    /// Returns the position of the first occurrence of this needle in a
+    /// sequence of segments that are treated as one logical haystack.
     ///
-    /// The haystack may be any type that can be cheaply converted into a
-    /// `&[u8]`. This includes, but is not limited to, `&str` and `&[u8]`.
+    /// This is useful when a haystack isn't stored contiguously in memory,
+    /// for example, a ring buffer like `VecDeque<u8>` or a chain of
+    /// `IoSlice`s. It avoids the need to copy all of the segments into a
+    /// single slice before searching. Each segment may be any type that
+    /// dereferences to `[u8]`.
+    ///
+    /// Matches may cross segment boundaries. The position returned includes
+    /// both the offset into the logical haystack and the segment (along with
+    /// the offset into that segment) at which the match begins.
     ///
     /// # Complexity
     ///
-    /// This routine is guaranteed to have worst case linear time complexity
-    /// with respect to both the needle and the haystack. That is, this runs
-    /// in `O(needle.len() + haystack.len())` time.
+    /// Matches contained entirely within a single segment are found with
+    /// the same substring search algorithm used by [`Finder::find`].
+    /// Matches crossing a segment boundary are found by comparing the needle
+    /// at each position in the last `needle.len() - 1` bytes of a segment
+    /// that contains the first byte of the needle. Thus, when segments are
+    /// long relative to the needle, this runs in approximately linear time,
+    /// but in the worst case (for example, many single byte segments), this
+    /// runs in `O(needle.len() * haystack.len())` time.
     ///
-    /// This routine is also guaranteed to have worst case constant space
+    /// This routine is guaranteed to have worst case constant space
     /// complexity.
     ///
     /// # Examples
     ///
-    /// Basic usage:
+    /// This shows how to search a `VecDeque<u8>` without copying it, where a
+    /// match crosses the boundary of the ring buffer:
     ///
     /// ```
-    /// use memchr::memmem::FinderRev;
+    /// use std::collections::VecDeque;
     ///
-    /// let haystack = b"foo bar baz";
-    /// assert_eq!(Some(0), FinderRev::new("foo").rfind(haystack));
-    /// assert_eq!(Some(4), FinderRev::new("bar").rfind(haystack));
-    /// assert_eq!(None, FinderRev::new("quux").rfind(haystack));
+    /// use memchr::memmem::Finder;
+    ///
+    /// let mut deque = VecDeque::with_capacity(8);
+    /// deque.extend(b"xxxxxx");
+    /// deque.drain(..4);
+    /// deque.extend(b"foobar");
+    ///
+    /// let (a, b) = deque.as_slices();
+    /// let finder = Finder::new("oba");
+    /// let pos = finder.find_segmented(&[a, b]).unwrap();
+    /// assert_eq!(4, pos.offset());
     /// ```
-    pub fn rfind<B: AsRef<[u8]>>(&self, haystack: B) -> Option<usize> {
-        self.searcher.rfind(haystack.as_ref(), self.needle.as_slice())
+    #[inline]
+    pub fn find_segmented<S: core::ops::Deref<Target = [u8]>>(
+        &self,
+        segments: &[S],
+    ) -> Option<crate::SegmentPos> {
+        self.find_segmented_iter(segments).next()
     }
 
-    /// Returns a reverse iterator over all occurrences of a substring in a
+    /// Returns an iterator over all non-overlapping occurrences of this
+    /// needle in a sequence of segments that are treated as one logical
     /// haystack.
     ///
-    /// # Complexity
+    /// See [`Finder::find_segmented`] for more details.
     ///
-    /// This routine is guaranteed to have worst case linear time complexity
-    /// with respect to both the needle and the haystack. That is, this runs
-    /// in `O(needle.len() + haystack.len())` time.
+    /// # Examples
     ///
-    /// This routine is also guaranteed to have worst case constant space
-    /// complexity.
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let segments: [&[u8]; 3] = [b"foo f", b"o", b"o foo"];
+    /// let finder = Finder::new("foo");
+    /// let offsets: Vec<(usize, usize)> = finder
+    ///     .find_segmented_iter(&segments)
+    ///     .map(|pos| (pos.offset(), pos.segment()))
+    ///     .collect();
+    /// assert_eq!(offsets, vec![(0, 0), (4, 0), (8, 2)]);
+    /// ```
+    #[inline]
+    pub fn find_segmented_iter<'a, 's, S: core::ops::Deref<Target = [u8]>>(
+        &'a self,
+        segments: &'s [S],
+    ) -> FindSegmentedIter<'s, 'a, S> {
+        FindSegmentedIter::new(segments, self.as_ref())
+    }
+
+    /// Returns an iterator over the rows of a columnar batch of byte strings
+    /// that contain this needle, along with the position of the first match
+    /// in each such row.
+    ///
+    /// The batch is stored as in the Apache Arrow columnar format: `values`
+    /// is the contiguous concatenation of every row, and `offsets` contains
+    /// one more element than there are rows, such that row `i` is
+    /// `values[offsets[i]..offsets[i + 1]]`. Offsets may be any integer type
+    /// that converts to `usize`, such as the `i32` and `i64` offsets used by
+    /// Arrow.
+    ///
+    /// Instead of searching each row separately, this searches the values
+    /// buffer in one pass and maps each match back to the row containing it.
+    /// This avoids the overhead of starting a new search for every row, which
+    /// can dominate when rows are short. Candidates that cross a row boundary
+    /// are never reported, and whole word matching (when enabled) treats the
+    /// beginning and end of each row as word boundaries.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the offsets are not non-decreasing, or if any offset is
+    /// negative or greater than the length of `values`.
+    ///
+    /// # Examples
+    ///
+    /// This shows how to collect the rows that contain a match:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let values = b"foobarbazfoo quuxfo";
+    /// let offsets: [i32; 6] = [0, 3, 6, 9, 17, 19];
+    /// let finder = Finder::new("foo");
+    /// let rows: Vec<usize> = finder
+    ///     .find_in_batch(values, &offsets)
+    ///     .map(|m| m.row())
+    ///     .collect();
+    /// assert_eq!(rows, vec![0, 3]);
+    /// ```
+    #[inline]
+    pub fn find_in_batch<'a, 'h, 'o, O: Copy>(
+        &'a self,
+        values: &'h [u8],
+        offsets: &'o [O],
+    ) -> FindBatchIter<'h, 'o, 'a, O>
+    where
+        usize: TryFrom<O>,
+    {
+        FindBatchIter::new(values, offsets, self.as_ref())
+    }
+
+    /// Consumes this finder and returns an iterator over all occurrences of
+    /// its needle in a haystack that is owned by the iterator.
+    ///
+    /// The haystack may be any type that can be cheaply converted into a
+    /// `&[u8]`, such as `Vec<u8>`, `Box<[u8]>` or `Arc<[u8]>`. Unlike
+    /// [`Finder::find_iter`], the iterator returned does not borrow the
+    /// haystack. When combined with [`Finder::into_owned`], this makes it
+    /// possible to build an iterator that is `'static`.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time.
+    ///
+    /// This routine is also guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// This shows how to return a match iterator from a function that owns
+    /// its haystack:
+    ///
+    /// ```
+    /// use memchr::memmem::{Finder, OwnedFindIter};
+    ///
+    /// fn matches(haystack: Vec<u8>) -> OwnedFindIter<'static, Vec<u8>> {
+    ///     Finder::new("foo").into_find_iter(haystack)
+    /// }
+    ///
+    /// let it = matches(b"foo bar foo".to_vec());
+    /// assert_eq!(it.collect::<Vec<usize>>(), vec![0, 8]);
+    /// ```
+    #[inline]
+    pub fn into_find_iter<H: AsRef<[u8]>>(
+        self,
+        haystack: H,
+    ) -> OwnedFindIter<'n, H> {
+        let prestate = PrefilterState::new();
+        OwnedFindIter { haystack, prestate, finder: self, pos: 0 }
+    }
+
+    /// Returns the number of non-overlapping occurrences of this needle in a
+    /// haystack, searching it in parallel.
+    ///
+    /// The haystack is split into partitions that are searched on the
+    /// `rayon` global thread pool. Matches straddling partition boundaries
+    /// are found, and the result is always the same as
+    /// `self.find_iter(haystack).count()`. See the [`par`](crate::par)
+    /// module for more details.
+    ///
+    /// This is only available when the `rayon` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"foo bar foo baz".repeat(1_000);
+    /// let finder = Finder::new("foo");
+    /// assert_eq!(2_000, finder.par_count(&haystack));
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_count(&self, haystack: &[u8]) -> usize {
+        crate::par::count(
+            self,
+            haystack,
+            crate::par::partition_len(haystack.len()),
+        )
+    }
+
+    /// Returns the offsets of all non-overlapping occurrences of this needle
+    /// in a haystack, in ascending order, searching it in parallel.
+    ///
+    /// The haystack is split into partitions that are searched on the
+    /// `rayon` global thread pool. Matches straddling partition boundaries
+    /// are found, and the result is always the same as
+    /// `self.find_iter(haystack).collect::<Vec<_>>()`. See the
+    /// [`par`](crate::par) module for more details.
+    ///
+    /// This is only available when the `rayon` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new("foo");
+    /// assert_eq!(vec![0, 8], finder.par_find_all(b"foo bar foo"));
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_find_all(&self, haystack: &[u8]) -> alloc::vec::Vec<usize> {
+        crate::par::find_all(
+            self,
+            haystack,
+            crate::par::partition_len(haystack.len()),
+        )
+    }
+
+    /// Returns true if and only if this finder's needle is a prefix of the
+    /// given haystack.
+    ///
+    /// This does not run a search. It only compares the needle with the
+    /// beginning of the haystack, and is therefore useful for checking
+    /// whether a precompiled needle occurs at a known position.
+    ///
+    /// An empty needle is a prefix of every haystack.
+    ///
+    /// # Complexity
+    ///
+    /// This runs in `O(needle.len())` time and constant space.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new("foo");
+    /// assert!(finder.is_prefix_of(b"foobar"));
+    /// assert!(!finder.is_prefix_of(b"barfoo"));
+    /// assert!(!finder.is_prefix_of(b"fo"));
+    /// ```
+    #[inline]
+    pub fn is_prefix_of(&self, haystack: &[u8]) -> bool {
+        crate::arch::all::is_prefix(haystack, self.needle())
+    }
+
+    /// Returns true if and only if this finder's needle is a suffix of the
+    /// given haystack.
+    ///
+    /// This does not run a search. It only compares the needle with the end
+    /// of the haystack.
+    ///
+    /// An empty needle is a suffix of every haystack.
+    ///
+    /// # Complexity
+    ///
+    /// This runs in `O(needle.len())` time and constant space.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new("foo");
+    /// assert!(finder.is_suffix_of(b"barfoo"));
+    /// assert!(!finder.is_suffix_of(b"foobar"));
+    /// assert!(!finder.is_suffix_of(b"oo"));
+    /// ```
+    #[inline]
+    pub fn is_suffix_of(&self, haystack: &[u8]) -> bool {
+        crate::arch::all::is_suffix(haystack, self.needle())
+    }
+
+    /// Returns true if and only if this finder's needle occurs in the given
+    /// haystack starting at the byte offset `pos`.
+    ///
+    /// This does not run a search. It only compares the needle with the
+    /// bytes of the haystack beginning at `pos`. When `pos` is greater than
+    /// the length of the haystack, or when the needle does not fit in the
+    /// remainder of the haystack, then this returns false.
+    ///
+    /// An empty needle matches at every position in `0..=haystack.len()`.
+    ///
+    /// # Complexity
+    ///
+    /// This runs in `O(needle.len())` time and constant space.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"let foo = bar;";
+    /// let finder = Finder::new("foo");
+    /// assert!(finder.is_match_at(haystack, 4));
+    /// assert!(!finder.is_match_at(haystack, 5));
+    /// assert!(!finder.is_match_at(haystack, 100));
+    /// ```
+    #[inline]
+    pub fn is_match_at(&self, haystack: &[u8], pos: usize) -> bool {
+        match haystack.get(pos..) {
+            None => false,
+            Some(haystack) => self.is_prefix_of(haystack),
+        }
+    }
+
+    /// Convert this finder into its owned variant, such that it no longer
+    /// borrows the needle.
+    ///
+    /// If this is already an owned finder, then this is a no-op. Otherwise,
+    /// this copies the needle.
+    ///
+    /// This is only available when the `alloc` feature is enabled.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn into_owned(self) -> Finder<'static> {
+        Finder {
+            needle: self.needle.into_owned(),
+            searcher: self.searcher,
+            whole_word: self.whole_word,
+        }
+    }
+
+    /// Convert this finder into its borrowed variant.
+    ///
+    /// This is primarily useful if your finder is owned and you'd like to
+    /// store its borrowed variant in some intermediate data structure.
+    ///
+    /// Note that the lifetime parameter of the returned finder is tied to the
+    /// lifetime of `self`, and may be shorter than the `'n` lifetime of the
+    /// needle itself. Namely, a finder's needle can be either borrowed or
+    /// owned, so the lifetime of the needle returned must necessarily be the
+    /// shorter of the two.
+    #[inline]
+    pub fn as_ref(&self) -> Finder<'_> {
+        Finder {
+            needle: CowBytes::new(self.needle()),
+            searcher: self.searcher.clone(),
+            whole_word: self.whole_word,
+        }
+    }
+
+    /// Returns the needle that this finder searches for.
+    ///
+    /// Note that the lifetime of the needle returned is tied to the lifetime
+    /// of the finder, and may be shorter than the `'n` lifetime. Namely, a
+    /// finder's needle can be either borrowed or owned, so the lifetime of the
+    /// needle returned must necessarily be the shorter of the two.
+    #[inline]
+    pub fn needle(&self) -> &[u8] {
+        self.needle.as_slice()
+    }
+
+    /// Returns a description of the strategy used by this finder.
+    ///
+    /// The strategy is chosen when the finder is built, based on the needle,
+    /// heuristics and what the current target supports. This is useful for
+    /// understanding the performance of a search without needing to enable
+    /// logging.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::{Finder, Strategy};
+    ///
+    /// assert_eq!(Strategy::OneByte, Finder::new("a").strategy());
+    /// // Long needles never use a vector algorithm on their own.
+    /// let finder = Finder::new(&[b'a'; 100][..]);
+    /// assert!(!matches!(finder.strategy(), Strategy::PackedPair { .. }));
+    /// ```
+    #[inline]
+    pub fn strategy(&self) -> Strategy {
+        self.searcher.strategy()
+    }
+
+    /// Serializes this finder to bytes.
+    ///
+    /// The bytes returned contain the needle along with the state computed
+    /// when building this finder, such as its search strategy, the pair of
+    /// rare bytes in the needle and the critical factorization used by the
+    /// Two-Way algorithm. They can be turned back into a finder with
+    /// [`Finder::from_bytes`], which is faster than building a new finder
+    /// since none of that state is computed again. This is useful when a
+    /// large set of needles is known ahead of time.
+    ///
+    /// The format is the same on all targets. It is only guaranteed to be
+    /// readable by the same version of this crate.
+    ///
+    /// This is only available when the `alloc` feature is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let bytes = Finder::new("foo").to_bytes();
+    /// let (finder, nread) = Finder::from_bytes(&bytes).unwrap();
+    /// assert_eq!(bytes.len(), nread);
+    /// assert_eq!(Some(4), finder.find(b"bar foo"));
+    /// ```
+    ///
+    /// # Example: many finders
+    ///
+    /// This shows how to serialize many finders into one buffer and read
+    /// them back.
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let mut bytes = vec![];
+    /// for needle in ["foo", "bar", "quux"] {
+    ///     bytes.extend(Finder::new(needle).to_bytes());
+    /// }
+    ///
+    /// let mut finders = vec![];
+    /// let mut rest = &bytes[..];
+    /// while !rest.is_empty() {
+    ///     let (finder, nread) = Finder::from_bytes(rest).unwrap();
+    ///     finders.push(finder);
+    ///     rest = &rest[nread..];
+    /// }
+    /// assert_eq!(b"quux", finders[2].needle());
+    /// assert_eq!(Some(2), finders[1].find(b"a bar"));
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn to_bytes(&self) -> alloc::vec::Vec<u8> {
+        let mut bytes = alloc::vec::Vec::new();
+        serialize::write_finder(self, &mut bytes);
+        bytes
+    }
+
+    /// Deserializes a finder from bytes produced by `Finder::to_bytes`.
+    ///
+    /// The finder is read from the beginning of `bytes` and returned along
+    /// with the number of bytes read. This permits reading many finders
+    /// that were serialized one after the other. The finder returned borrows
+    /// its needle from `bytes`, so no allocation is performed.
+    ///
+    /// The state read is used without computing it again. A checksum is used
+    /// to reject bytes that were corrupted after serialization, and the
+    /// state is checked to be consistent with the needle so that searching
+    /// never panics. However, bytes that were crafted to pass these checks
+    /// may produce a finder that reports incorrect matches. So bytes from an
+    /// untrusted source should not be deserialized.
+    ///
+    /// If the finder was serialized on a target where it used a vector
+    /// algorithm that isn't available on the current target, then the next
+    /// best strategy is used instead.
+    ///
+    /// This assumes that the finder was built with the default frequency
+    /// ranking. For finders built with
+    /// [`FinderBuilder::build_forward_with_ranker`], use
+    /// [`Finder::from_bytes_with_ranker`] instead.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if the bytes aren't a valid serialization of a
+    /// forward finder, for example, when they are truncated, corrupt or were
+    /// produced by an incompatible version of this crate.
+    ///
+    /// # Example
+    ///
+    /// See `Finder::to_bytes` for examples.
+    pub fn from_bytes(
+        bytes: &'n [u8],
+    ) -> Result<(Finder<'n>, usize), DeserializeError> {
+        serialize::read_finder(DefaultFrequencyRank, bytes)
+    }
+
+    /// Deserializes a finder from bytes produced by `Finder::to_bytes`,
+    /// where the finder was built with the given heuristic for determining
+    /// the frequency of a given byte in the dataset.
+    ///
+    /// This is like [`Finder::from_bytes`], except the given ranker is used
+    /// instead of the default one when a strategy has to be chosen again.
+    /// For example, when the finder used a vector algorithm for short
+    /// needles that isn't available on the current target. See
+    /// [`HeuristicFrequencyRank`] for more details.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if the bytes aren't a valid serialization of a
+    /// forward finder.
+    pub fn from_bytes_with_ranker<R: HeuristicFrequencyRank>(
+        ranker: R,
+        bytes: &'n [u8],
+    ) -> Result<(Finder<'n>, usize), DeserializeError> {
+        serialize::read_finder(ranker, bytes)
+    }
+}
+
+/// A single substring reverse searcher fixed to a particular needle.
+///
+/// The purpose of this type is to permit callers to construct a substring
+/// searcher that can be used to search haystacks without the overhead of
+/// constructing the searcher in the first place. This is a somewhat niche
+/// concern when it's necessary to re-use the same needle to search multiple
+/// different haystacks with as little overhead as possible. In general,
+/// using [`rfind`] is good enough, but `FinderRev` is useful when you can
+/// meaningfully observe searcher construction time in a profile.
+///
+/// When the `std` feature is enabled, then this type has an `into_owned`
+/// version which permits building a `FinderRev` that is not connected to
+/// the lifetime of its needle.
+#[derive(Clone, Debug)]
+pub struct FinderRev<'n> {
+    needle: CowBytes<'n>,
+    searcher: SearcherRev,
+    pub(crate) whole_word: bool,
+}
+
+impl<'n> FinderRev<'n> {
+    /// Create a new reverse finder for the given needle.
+    #[inline]
+    pub fn new<B: ?Sized + AsRef<[u8]>>(needle: &'n B) -> FinderRev<'n> {
+        FinderBuilder::new().build_reverse(needle)
+    }
+
+    /// Returns the index of the last occurrence of this needle in the given
+    /// haystack.
+    ///
+    /// The haystack may be any type that can be cheaply converted into a
+    /// `&[u8]`. This includes, but is not limited to, `&str` and `&[u8]`.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time.
+    ///
+    /// This routine is also guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::FinderRev;
+    ///
+    /// let haystack = b"foo bar baz";
+    /// assert_eq!(Some(0), FinderRev::new("foo").rfind(haystack));
+    /// assert_eq!(Some(4), FinderRev::new("bar").rfind(haystack));
+    /// assert_eq!(None, FinderRev::new("quux").rfind(haystack));
+    /// ```
+    pub fn rfind<B: AsRef<[u8]>>(&self, haystack: B) -> Option<usize> {
+        let haystack = haystack.as_ref();
+        if self.whole_word {
+            self.rfind_to(haystack, haystack.len())
+        } else {
+            self.searcher.rfind(haystack, self.needle.as_slice())
+        }
+    }
+
+    /// Returns the index of the last occurrence of this needle in
+    /// `haystack[..end]`.
+    ///
+    /// Unlike slicing the haystack before searching, this permits the bytes
+    /// at or after `end` to be consulted when this finder only reports whole
+    /// word matches.
+    #[inline(always)]
+    pub(crate) fn rfind_to(
+        &self,
+        haystack: &[u8],
+        end: usize,
+    ) -> Option<usize> {
+        let needle = self.needle.as_slice();
+        let mut end = end;
+        loop {
+            let pos = self.searcher.rfind(&haystack[..end], needle)?;
+            if !self.whole_word
+                || is_word_match(haystack, pos, pos + needle.len())
+            {
+                return Some(pos);
+            }
+            // The next candidate must start before `pos`, which means it
+            // must end before `pos + needle.len()`.
+            end = (pos + needle.len()).checked_sub(1)?;
+        }
+    }
+
+    /// Returns a reverse iterator over all occurrences of a substring in a
+    /// haystack.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time.
+    ///
+    /// This routine is also guaranteed to have worst case constant space
+    /// complexity.
     ///
     /// # Examples
     ///
@@ -593,6 +1555,46 @@ impl<'n> FinderRev<'n> {
         FindRevIter::new(haystack, self.as_ref())
     }
 
+    /// Consumes this finder and returns a reverse iterator over all
+    /// occurrences of its needle in a haystack that is owned by the iterator.
+    ///
+    /// The haystack may be any type that can be cheaply converted into a
+    /// `&[u8]`, such as `Vec<u8>`, `Box<[u8]>` or `Arc<[u8]>`. Unlike
+    /// [`FinderRev::rfind_iter`], the iterator returned does not borrow the
+    /// haystack. When combined with [`FinderRev::into_owned`], this makes it
+    /// possible to build an iterator that is `'static`.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time.
+    ///
+    /// This routine is also guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use memchr::memmem::FinderRev;
+    ///
+    /// let haystack: Arc<[u8]> = Arc::from(&b"foo bar foo"[..]);
+    /// let it = FinderRev::new("foo").into_rfind_iter(haystack);
+    /// assert_eq!(it.collect::<Vec<usize>>(), vec![8, 0]);
+    /// ```
+    #[inline]
+    pub fn into_rfind_iter<H: AsRef<[u8]>>(
+        self,
+        haystack: H,
+    ) -> OwnedFindRevIter<'n, H> {
+        let pos = Some(haystack.as_ref().len());
+        OwnedFindRevIter { haystack, finder: self, pos }
+    }
+
     /// Convert this finder into its owned variant, such that it no longer
     /// borrows the needle.
     ///
@@ -603,7 +1605,11 @@ impl<'n> FinderRev<'n> {
     #[cfg(feature = "alloc")]
     #[inline]
     pub fn into_owned(self) -> FinderRev<'static> {
-        FinderRev { needle: self.needle.into_owned(), searcher: self.searcher }
+        FinderRev {
+            needle: self.needle.into_owned(),
+            searcher: self.searcher,
+            whole_word: self.whole_word,
+        }
     }
 
     /// Convert this finder into its borrowed variant.
@@ -621,6 +1627,7 @@ impl<'n> FinderRev<'n> {
         FinderRev {
             needle: CowBytes::new(self.needle()),
             searcher: self.searcher.clone(),
+            whole_word: self.whole_word,
         }
     }
 
@@ -634,6 +1641,57 @@ impl<'n> FinderRev<'n> {
     pub fn needle(&self) -> &[u8] {
         self.needle.as_slice()
     }
+
+    /// Returns a description of the strategy used by this finder.
+    ///
+    /// Reverse searching never uses a prefilter, so this is always one of
+    /// [`Strategy::Empty`], [`Strategy::OneByte`], [`Strategy::ShortNeedle`]
+    /// or [`Strategy::TwoWay`].
+    #[inline]
+    pub fn strategy(&self) -> Strategy {
+        self.searcher.strategy()
+    }
+
+    /// Serializes this finder to bytes.
+    ///
+    /// This is the reverse analog of [`Finder::to_bytes`]. The bytes
+    /// returned can be turned back into a finder with
+    /// [`FinderRev::from_bytes`].
+    ///
+    /// This is only available when the `alloc` feature is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::FinderRev;
+    ///
+    /// let bytes = FinderRev::new("foo").to_bytes();
+    /// let (finder, _) = FinderRev::from_bytes(&bytes).unwrap();
+    /// assert_eq!(Some(8), finder.rfind(b"foo bar foo"));
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn to_bytes(&self) -> alloc::vec::Vec<u8> {
+        let mut bytes = alloc::vec::Vec::new();
+        serialize::write_finder_rev(self, &mut bytes);
+        bytes
+    }
+
+    /// Deserializes a finder from bytes produced by `FinderRev::to_bytes`.
+    ///
+    /// The finder is read from the beginning of `bytes` and returned along
+    /// with the number of bytes read. The finder returned borrows its needle
+    /// from `bytes`. This performs the same validation as
+    /// [`Finder::from_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if the bytes aren't a valid serialization of a
+    /// reverse finder.
+    pub fn from_bytes(
+        bytes: &'n [u8],
+    ) -> Result<(FinderRev<'n>, usize), DeserializeError> {
+        serialize::read_finder_rev(bytes)
+    }
 }
 
 /// A builder for constructing non-default forward or reverse memmem finders.
@@ -644,6 +1702,7 @@ impl<'n> FinderRev<'n> {
 #[derive(Clone, Debug, Default)]
 pub struct FinderBuilder {
     prefilter: Prefilter,
+    whole_word: bool,
 }
 
 impl FinderBuilder {
@@ -687,6 +1746,7 @@ impl FinderBuilder {
         Finder {
             needle: CowBytes::new(needle),
             searcher: Searcher::new(self.prefilter, ranker, needle),
+            whole_word: self.whole_word,
         }
     }
 
@@ -704,7 +1764,11 @@ impl FinderBuilder {
     ) -> Finder<'static> {
         let needle = needle.into();
         let searcher = Searcher::new(self.prefilter, ranker, &needle);
-        Finder { needle: CowBytes::new_owned(needle), searcher }
+        Finder {
+            needle: CowBytes::new_owned(needle),
+            searcher,
+            whole_word: self.whole_word,
+        }
     }
 
     /// Build a reverse finder using the given needle from the current
@@ -717,6 +1781,7 @@ impl FinderBuilder {
         FinderRev {
             needle: CowBytes::new(needle),
             searcher: SearcherRev::new(needle),
+            whole_word: self.whole_word,
         }
     }
 
@@ -729,7 +1794,11 @@ impl FinderBuilder {
     ) -> FinderRev<'static> {
         let needle = needle.into();
         let searcher = SearcherRev::new(&needle);
-        FinderRev { needle: CowBytes::new_owned(needle), searcher }
+        FinderRev {
+            needle: CowBytes::new_owned(needle),
+            searcher,
+            whole_word: self.whole_word,
+        }
     }
 
     /// Configure the prefilter setting for the finder.
@@ -740,10 +1809,78 @@ impl FinderBuilder {
         self.prefilter = prefilter;
         self
     }
+
+    /// When enabled, finders only report matches that begin and end on an
+    /// ASCII word boundary. This is disabled by default.
+    ///
+    /// This is like searching for the regex `\bneedle\b`, where a word byte
+    /// is any of `[0-9A-Za-z_]`. That is, a match is only reported if the
+    /// wordness of the byte immediately before the match differs from the
+    /// wordness of the first byte of the match, and similarly for the byte
+    /// immediately after the match and the last byte of the match. The
+    /// beginning and end of the haystack are treated as non-word bytes.
+    ///
+    /// Candidates are rejected by looking at their neighboring bytes only,
+    /// so enabling this does not change the underlying search algorithm.
+    /// Iterators returned by finders built with this option consult the
+    /// entire haystack, so a match is never reported merely because it is
+    /// adjacent to the position at which the previous search stopped.
+    ///
+    /// This applies to both forward and reverse finders.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::FinderBuilder;
+    ///
+    /// let haystack = b"foo_bar foo foobar (foo)";
+    /// let mut builder = FinderBuilder::new();
+    /// builder.whole_word(true);
+    ///
+    /// let finder = builder.build_forward("foo");
+    /// let matches: Vec<usize> = finder.find_iter(haystack).collect();
+    /// assert_eq!(matches, vec![8, 20]);
+    ///
+    /// let finder = builder.build_reverse("foo");
+    /// assert_eq!(Some(20), finder.rfind(haystack));
+    /// ```
+    pub fn whole_word(&mut self, yes: bool) -> &mut FinderBuilder {
+        self.whole_word = yes;
+        self
+    }
+}
+
+/// Returns true if and only if the match at `haystack[start..end]` begins and
+/// ends on an ASCII word boundary.
+///
+/// This only looks at the bytes on either side of both ends of the match.
+#[inline(always)]
+fn is_word_match(haystack: &[u8], start: usize, end: usize) -> bool {
+    is_word_boundary(haystack, start) && is_word_boundary(haystack, end)
+}
+
+/// Returns true if and only if the position `at` in `haystack` is an ASCII
+/// word boundary. Positions outside of the haystack are treated as non-word
+/// bytes.
+#[inline(always)]
+fn is_word_boundary(haystack: &[u8], at: usize) -> bool {
+    let before = at.checked_sub(1).and_then(|i| haystack.get(i)).copied();
+    let after = haystack.get(at).copied();
+    is_word_byte(before) != is_word_byte(after)
+}
+
+/// Returns true if and only if the given byte is an ASCII word byte. A
+/// missing byte, i.e., one that is outside the haystack, is never a word
+/// byte.
+#[inline(always)]
+fn is_word_byte(byte: Option<u8>) -> bool {
+    byte.map_or(false, |b| b.is_ascii_alphanumeric() || b == b'_')
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::{arch::all::packedpair::Pair, Implementation};
+
     use super::*;
 
     define_substring_forward_quickcheck!(|h, n| Some(Finder::new(n).find(h)));
@@ -751,6 +1888,43 @@ mod tests {
         FinderRev::new(n).rfind(h)
     ));
 
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn owned_iters() {
+        use alloc::{boxed::Box, sync::Arc, vec::Vec};
+
+        let haystacks: &[&[u8]] =
+            &[b"", b"a", b"aaa", b"abcabc", b"xabcabcx", b"ab\x00ab"];
+        let needles: &[&[u8]] = &[b"", b"a", b"ab", b"abc", b"aa", b"xyz"];
+        for &haystack in haystacks {
+            for &needle in needles {
+                let expected: Vec<usize> =
+                    find_iter(haystack, needle).collect();
+                let got: Vec<usize> = Finder::new(needle)
+                    .into_owned()
+                    .into_find_iter(Vec::from(haystack))
+                    .collect();
+                assert_eq!(expected, got);
+                let got: Vec<usize> = Finder::new(needle)
+                    .into_find_iter(Box::<[u8]>::from(haystack))
+                    .collect();
+                assert_eq!(expected, got);
+
+                let expected: Vec<usize> =
+                    rfind_iter(haystack, needle).collect();
+                let got: Vec<usize> = FinderRev::new(needle)
+                    .into_owned()
+                    .into_rfind_iter(Arc::<[u8]>::from(haystack))
+                    .collect();
+                assert_eq!(expected, got);
+            }
+        }
+
+        fn assert_static<T: 'static>(_: &T) {}
+        let it = Finder::new("a").into_owned().into_find_iter(Vec::new());
+        assert_static(&it);
+    }
+
     #[test]
     fn forward() {
         crate::tests::substring::Runner::new()
@@ -758,10 +1932,216 @@ mod tests {
             .run();
     }
 
+    #[test]
+    fn forward_const() {
+        crate::tests::substring::Runner::new()
+            .fwd(|h, n| Some(Finder::new_const(n).find(h)))
+            .run();
+
+        static FINDER: Finder<'static> = Finder::new_const(b"Sherlock");
+        assert_eq!(Some(4), FINDER.find(b"Mr. Sherlock Holmes"));
+        assert_eq!(None, FINDER.find(b"Mr. Holmes"));
+    }
+
+    #[test]
+    fn forward_const_fn() {
+        crate::tests::substring::Runner::new()
+            .fwd(|h, n| Some(find_const(h, n)))
+            .run();
+    }
+
+    #[test]
+    fn reverse_const_fn() {
+        crate::tests::substring::Runner::new()
+            .rev(|h, n| Some(rfind_const(h, n)))
+            .run();
+    }
+
     #[test]
     fn reverse() {
         crate::tests::substring::Runner::new()
             .rev(|h, n| Some(FinderRev::new(n).rfind(h)))
             .run();
     }
+
+    #[test]
+    fn anchored() {
+        let finder = Finder::new("ab");
+        assert!(finder.is_prefix_of(b"ab"));
+        assert!(finder.is_prefix_of(b"abc"));
+        assert!(!finder.is_prefix_of(b"a"));
+        assert!(!finder.is_prefix_of(b"cab"));
+        assert!(finder.is_suffix_of(b"cab"));
+        assert!(!finder.is_suffix_of(b"abc"));
+        assert!(!finder.is_suffix_of(b""));
+
+        let haystack = b"xabyab";
+        let positions: alloc::vec::Vec<usize> = (0..=haystack.len() + 1)
+            .filter(|&i| finder.is_match_at(haystack, i))
+            .collect();
+        assert_eq!(positions, [1, 4]);
+
+        let empty = Finder::new("");
+        assert!(empty.is_prefix_of(b""));
+        assert!(empty.is_suffix_of(b"abc"));
+        assert!(empty.is_match_at(b"abc", 3));
+        assert!(!empty.is_match_at(b"abc", 4));
+    }
+
+    #[test]
+    fn strategy() {
+        assert_eq!(Strategy::Empty, Finder::new("").strategy());
+        assert_eq!(Strategy::OneByte, Finder::new("a").strategy());
+        assert_eq!(Strategy::TwoWay, FinderRev::new("foobar").strategy());
+
+        let long = [b'z'; 100];
+        let finder = FinderBuilder::new()
+            .prefilter(Prefilter::None)
+            .build_forward(&long);
+        assert_eq!(Strategy::TwoWay, finder.strategy());
+        match Finder::new(&long).strategy() {
+            Strategy::TwoWayWithPrefilter { pair, .. } => {
+                assert_eq!(Some(pair), Pair::with_indices(&long, 0, 1));
+            }
+            strategy => panic!("unexpected strategy: {:?}", strategy),
+        }
+
+        let vector = crate::dispatch_info().memmem();
+        match Finder::new("foobar").strategy() {
+            Strategy::PackedPair { implementation, .. } => {
+                assert_eq!(vector, implementation);
+            }
+            Strategy::TwoWayWithPrefilter { implementation, .. } => {
+                assert!(!vector.is_vector());
+                assert_eq!(Implementation::Fallback, implementation);
+            }
+            strategy => panic!("unexpected strategy: {:?}", strategy),
+        }
+
+        // Even the rarest pair of bytes in a DNA sequence is common, so the
+        // vector prefilter looks for a third byte too.
+        let dna = b"GATTACACCGGTAGCTTAGCATGCATCGATCGTAGCTAGCTAGGCTA";
+        let finder = Finder::new(dna);
+        match finder.strategy() {
+            Strategy::TwoWayWithPrefilter { pair, triple, .. } => {
+                assert_eq!(vector.is_vector(), triple.is_some());
+                if let Some(triple) = triple {
+                    assert_eq!(pair, triple.pair());
+                }
+            }
+            strategy => panic!("unexpected strategy: {:?}", strategy),
+        }
+        let haystack = [&b"GATTACA"[..], dna].concat();
+        assert_eq!(Some(7), finder.find(&haystack));
+        // But a short needle still uses the packed pair algorithm, even
+        // though its pair is just as common.
+        match Finder::new(&dna[..8]).strategy() {
+            Strategy::PackedPair { implementation, .. } => {
+                assert_eq!(vector, implementation);
+            }
+            Strategy::TwoWayWithPrefilter { triple, .. } => {
+                assert!(!vector.is_vector());
+                assert_eq!(None, triple);
+            }
+            strategy => panic!("unexpected strategy: {:?}", strategy),
+        }
+
+        // Short needles only use the short needle algorithm for forward
+        // search when their bytes are very common. Reverse search always
+        // uses it.
+        for needle in ["\r\n", "::", "=>", "::=", "impl", "the", "e "] {
+            let common = ["the", "e "].contains(&needle);
+            match Finder::new(needle).strategy() {
+                Strategy::ShortNeedle { implementation } => {
+                    assert!(common, "{:?}", needle);
+                    assert_eq!(vector, implementation);
+                }
+                Strategy::PackedPair { implementation, .. } => {
+                    assert!(!common, "{:?}", needle);
+                    assert_eq!(vector, implementation);
+                }
+                strategy => assert!(!vector.is_vector(), "{:?}", strategy),
+            }
+            match FinderRev::new(needle).strategy() {
+                Strategy::ShortNeedle { implementation } => {
+                    assert_eq!(vector, implementation);
+                }
+                strategy => assert!(!vector.is_vector(), "{:?}", strategy),
+            }
+        }
+    }
+
+    #[test]
+    fn whole_word() {
+        use alloc::vec::Vec;
+
+        fn naive(haystack: &[u8], needle: &[u8]) -> Vec<usize> {
+            let mut matches = Vec::new();
+            let mut at = 0;
+            while at + needle.len() <= haystack.len() {
+                if &haystack[at..at + needle.len()] == needle
+                    && is_word_match(haystack, at, at + needle.len())
+                {
+                    matches.push(at);
+                    at += needle.len().max(1);
+                } else {
+                    at += 1;
+                }
+            }
+            matches
+        }
+
+        let haystacks: &[&[u8]] = &[
+            b"",
+            b"foo",
+            b"foofoo",
+            b"foo foo",
+            b"_foo foo_ (foo) foo.foo",
+            b"a-b a_b ab-ab--",
+            b"x xx x  xxx x",
+            b"::foo::bar::",
+            b" \t\n ",
+            b"ab cd",
+        ];
+        let needles: &[&[u8]] =
+            &[b"", b"foo", b"x", b"xx", b"-", b"--", b" ", b"::", b"a-b"];
+        for &haystack in haystacks {
+            for &needle in needles {
+                let fwd = FinderBuilder::new()
+                    .whole_word(true)
+                    .build_forward(needle);
+                let rev = FinderBuilder::new()
+                    .whole_word(true)
+                    .build_reverse(needle);
+                let expected = naive(haystack, needle);
+                let got: Vec<usize> = fwd.find_iter(haystack).collect();
+                assert_eq!(
+                    expected, got,
+                    "forward, haystack: {:?}, needle: {:?}",
+                    haystack, needle,
+                );
+                assert_eq!(expected.first().copied(), fwd.find(haystack));
+                assert_eq!(
+                    expected.last().copied(),
+                    rev.rfind(haystack),
+                    "reverse, haystack: {:?}, needle: {:?}",
+                    haystack,
+                    needle,
+                );
+                // The reverse iterator may find different non-overlapping
+                // matches than the forward iterator, but every match it
+                // reports must be a whole word match, and each one must be
+                // reported only once.
+                let got: Vec<usize> = rev.rfind_iter(haystack).collect();
+                for &i in got.iter() {
+                    assert!(is_word_match(haystack, i, i + needle.len()));
+                }
+                assert!(got.windows(2).all(|w| w[0] > w[1]), "{:?}", got);
+                // The owned iterator must report the same matches.
+                let owned: Vec<usize> =
+                    rev.clone().into_rfind_iter(haystack).collect();
+                assert_eq!(got, owned);
+            }
+        }
+    }
 }
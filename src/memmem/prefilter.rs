@@ -0,0 +1,293 @@
+use crate::{
+    arch::all::packedpair::{
+        DefaultFrequencyRank, HeuristicFrequencyRank, Pair,
+    },
+    memmem::searcher::{Prefilter, PrefilterState},
+};
+
+/// A prefilter that reports candidate positions for a needle.
+///
+/// This exposes the prefilter used internally by
+/// [`Finder`](crate::memmem::Finder) to accelerate substring search. It looks
+/// for occurrences of a pair of bytes from the needle that are believed to be
+/// rare, and reports the positions at which the needle could begin.
+/// Confirming whether the needle actually occurs at a candidate position is
+/// left to the caller. This is useful when the caller has its own
+/// verification step, for example, when the needle is a literal prefix of a
+/// regex.
+///
+/// Candidates may be false positives, but there are never false negatives.
+/// That is, every position at which the needle occurs is reported as a
+/// candidate. Every candidate reported is such that the needle would fit
+/// in the haystack if it began at that position.
+///
+/// Like [`Finder`](crate::memmem::Finder), this uses a vector algorithm when
+/// one is available for the current target, and otherwise falls back to a
+/// portable implementation.
+///
+/// A prefilter can produce many false positives when the bytes it looks for
+/// aren't as rare as believed. The iterator returned by
+/// [`PrefilterFinder::find_iter`] tracks how many bytes each candidate
+/// skips, and [`PrefilterIter::is_effective`] reports when the prefilter no
+/// longer appears to be worth its cost. This is the same heuristic used to
+/// disable the prefilter inside of [`Finder`](crate::memmem::Finder).
+///
+/// # Example
+///
+/// This shows how to verify candidates with a custom matching routine. Here,
+/// only occurrences of the needle that are followed by a digit are reported.
+///
+/// ```
+/// use memchr::memmem::PrefilterFinder;
+///
+/// let haystack = b"foo1 foobar foo2";
+/// let pre = PrefilterFinder::new(b"foo").unwrap();
+/// let matches: Vec<usize> = pre
+///     .find_iter(haystack)
+///     .filter(|&i| {
+///         haystack[i..].starts_with(b"foo")
+///             && haystack.get(i + 3).map_or(false, |b| b.is_ascii_digit())
+///     })
+///     .collect();
+/// assert_eq!(matches, vec![0, 12]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct PrefilterFinder {
+    prefilter: Prefilter,
+    needle_len: usize,
+}
+
+impl PrefilterFinder {
+    /// Create a new prefilter for the given needle.
+    ///
+    /// The default byte frequency heuristic is used to choose the pair of
+    /// bytes to look for.
+    ///
+    /// This returns `None` when the needle is shorter than 2 bytes. Callers
+    /// should use [`memchr`](crate::memchr()) directly for needles of length
+    /// `1`.
+    #[inline]
+    pub fn new(needle: &[u8]) -> Option<PrefilterFinder> {
+        PrefilterFinder::with_ranker(DefaultFrequencyRank, needle)
+    }
+
+    /// Create a new prefilter for the given needle.
+    ///
+    /// The given ranker is used to choose the pair of bytes to look for. See
+    /// [`HeuristicFrequencyRank`] for more details.
+    ///
+    /// This returns `None` when the needle is shorter than 2 bytes.
+    #[inline]
+    pub fn with_ranker<R: HeuristicFrequencyRank>(
+        ranker: R,
+        needle: &[u8],
+    ) -> Option<PrefilterFinder> {
+        let pair = Pair::with_ranker(needle, ranker)?;
+        PrefilterFinder::with_pair(needle, pair)
+    }
+
+    /// Create a new prefilter for the given needle that looks for the given
+    /// pair of bytes.
+    ///
+    /// This returns `None` when the pair isn't valid for the needle given.
+    /// That is, when either of its indices is out of bounds for the needle.
+    #[inline]
+    pub fn with_pair(needle: &[u8], pair: Pair) -> Option<PrefilterFinder> {
+        let pair = Pair::with_indices(needle, pair.index1(), pair.index2())?;
+        let prefilter = Prefilter::with_pair(needle, pair, true)?;
+        trace!("building prefilter finder with {:?}", prefilter);
+        Some(PrefilterFinder { prefilter, needle_len: needle.len() })
+    }
+
+    /// Returns the first candidate position in the given haystack.
+    ///
+    /// When `None` is returned, it is guaranteed that the needle does not
+    /// occur in the haystack.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::PrefilterFinder;
+    ///
+    /// let pre = PrefilterFinder::new(b"foobar").unwrap();
+    /// let i = pre.find(b"quux foobar").unwrap();
+    /// assert!(i <= 5);
+    /// assert_eq!(None, pre.find(b"quux"));
+    /// ```
+    #[inline]
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        self.find_iter(haystack).next()
+    }
+
+    /// Returns an iterator over all candidate positions in the given
+    /// haystack, in ascending order.
+    ///
+    /// A fresh effectiveness state is created for each iterator.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::PrefilterFinder;
+    ///
+    /// let haystack = b"foo bar foo bar";
+    /// let pre = PrefilterFinder::new(b"bar").unwrap();
+    /// let found: Vec<usize> = pre
+    ///     .find_iter(haystack)
+    ///     .filter(|&i| haystack[i..].starts_with(b"bar"))
+    ///     .collect();
+    /// assert_eq!(found, vec![4, 12]);
+    /// ```
+    #[inline]
+    pub fn find_iter<'f, 'h>(
+        &'f self,
+        haystack: &'h [u8],
+    ) -> PrefilterIter<'f, 'h> {
+        PrefilterIter {
+            finder: self,
+            haystack,
+            prestate: PrefilterState::new(),
+            pos: 0,
+        }
+    }
+
+    /// Returns the pair of bytes from the needle that this prefilter looks
+    /// for.
+    #[inline]
+    pub fn pair(&self) -> &Pair {
+        self.prefilter.pair()
+    }
+
+    /// Returns the length of the needle that this prefilter was built for.
+    #[inline]
+    pub fn needle_len(&self) -> usize {
+        self.needle_len
+    }
+
+    /// Returns true if this prefilter uses a vector algorithm.
+    ///
+    /// When this is false, a portable implementation is used instead.
+    #[inline]
+    pub fn is_vector(&self) -> bool {
+        self.prefilter.implementation().is_vector()
+    }
+}
+
+/// An iterator over candidate positions reported by a prefilter.
+///
+/// This is created by [`PrefilterFinder::find_iter`].
+///
+/// `'f` is the lifetime of the prefilter while `'h` is the lifetime of the
+/// haystack.
+#[derive(Clone, Debug)]
+pub struct PrefilterIter<'f, 'h> {
+    finder: &'f PrefilterFinder,
+    haystack: &'h [u8],
+    prestate: PrefilterState,
+    pos: usize,
+}
+
+impl<'f, 'h> PrefilterIter<'f, 'h> {
+    /// Returns true if and only if the prefilter is still believed to be
+    /// effective.
+    ///
+    /// This is based on the number of bytes skipped by each candidate
+    /// reported so far. Once a prefilter is no longer effective, this always
+    /// returns false for this iterator. At that point, callers are
+    /// encouraged to stop using the prefilter and run their verification
+    /// step on the rest of the haystack directly, starting at the offset
+    /// immediately following the last candidate reported.
+    ///
+    /// Whether a prefilter is effective is only a heuristic. Continuing to
+    /// use this iterator after it is no longer effective still reports all
+    /// candidates correctly.
+    #[inline]
+    pub fn is_effective(&mut self) -> bool {
+        self.prestate.is_effective()
+    }
+}
+
+impl<'f, 'h> Iterator for PrefilterIter<'f, 'h> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let haystack = self.haystack.get(self.pos..)?;
+        let result = self.finder.prefilter.find(haystack);
+        self.prestate.update(result.unwrap_or(haystack.len()));
+        let i = match result {
+            Some(i) => self.pos + i,
+            None => {
+                self.pos = self.haystack.len() + 1;
+                return None;
+            }
+        };
+        // Candidates are reported in ascending order, so once the needle no
+        // longer fits, it never will.
+        if self.haystack.len() - i < self.finder.needle_len {
+            self.pos = self.haystack.len() + 1;
+            return None;
+        }
+        self.pos = i + 1;
+        Some(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn forward() {
+        crate::tests::substring::Runner::new()
+            .fwd(|h, n| {
+                let pre = PrefilterFinder::new(n)?;
+                Some(pre.find_iter(h).find(|&i| h[i..].starts_with(n)))
+            })
+            .run();
+    }
+
+    #[test]
+    fn no_false_negatives() {
+        let haystack = b"abcabcabXabcaaaabcabcbcabcabcab";
+        for needle in [&b"abc"[..], b"ab", b"bca", b"cabca", b"aaab"] {
+            let pre = PrefilterFinder::new(needle).unwrap();
+            let candidates: Vec<usize> = pre.find_iter(haystack).collect();
+            for i in 0..=haystack.len() - needle.len() {
+                if haystack[i..].starts_with(needle) {
+                    assert!(
+                        candidates.contains(&i),
+                        "needle {:?} at {} not reported",
+                        needle,
+                        i
+                    );
+                }
+            }
+            for &i in candidates.iter() {
+                assert!(i + needle.len() <= haystack.len());
+            }
+            assert!(candidates.windows(2).all(|w| w[0] < w[1]));
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(PrefilterFinder::new(b"").is_none());
+        assert!(PrefilterFinder::new(b"a").is_none());
+        let pair = Pair::with_indices(b"abcd", 1, 3).unwrap();
+        assert!(PrefilterFinder::with_pair(b"abcd", pair).is_some());
+        assert!(PrefilterFinder::with_pair(b"abc", pair).is_none());
+    }
+
+    #[test]
+    fn ineffective() {
+        // Every position is a candidate for this needle, so the prefilter
+        // should eventually be deemed ineffective.
+        let haystack = [b'a'; 1000];
+        let pre = PrefilterFinder::new(b"aaaaa").unwrap();
+        let mut it = pre.find_iter(&haystack);
+        assert!(it.is_effective());
+        while it.next().is_some() {}
+        assert!(!it.is_effective());
+    }
+}
@@ -0,0 +1,453 @@
+use crate::{
+    arch::all::packedpair::{DefaultFrequencyRank, HeuristicFrequencyRank},
+    memmem::{searcher::PrefilterState, Finder},
+};
+
+/// A searcher for one needle followed by another needle within a bounded
+/// distance.
+///
+/// A proximity match consists of an occurrence of the first needle followed
+/// by an occurrence of the second needle, such that the second needle begins
+/// at or after the end of the first needle and the number of bytes between
+/// them is at most `max_gap`. For example, with a `max_gap` of `0`, the second
+/// needle must immediately follow the first.
+///
+/// When more than one proximity match is possible, the one whose first needle
+/// begins earliest is reported. For that occurrence of the first needle, the
+/// earliest occurrence of the second needle within the permitted window is
+/// reported. Iteration reports non-overlapping proximity matches, where each
+/// search resumes at the end of the previous proximity match.
+///
+/// The search is driven by whichever needle is believed to be rarer, as
+/// determined by a [`HeuristicFrequencyRank`]. Candidates for the driving
+/// needle are found using its substring searcher, and then the other needle
+/// is only searched for inside the window implied by `max_gap`. Regardless of
+/// which needle drives the search, the matches reported are the same.
+///
+/// Any configuration on the finders given, such as
+/// [`FinderBuilder::whole_word`](crate::memmem::FinderBuilder::whole_word),
+/// is respected.
+///
+/// # Complexity
+///
+/// Unlike [`Finder`], this searcher does not guarantee linear time. In the
+/// worst case, each occurrence of the driving needle leads to a search of a
+/// window of approximately `max_gap` bytes for the other needle.
+///
+/// # Example
+///
+/// This shows how to find a key followed by its value in a log line.
+///
+/// ```
+/// use memchr::memmem::{Finder, ProximityFinder};
+///
+/// let haystack = b"user=bob status=ok; user=alice id=7 status=error";
+/// let finder = ProximityFinder::new(
+///     Finder::new("user="),
+///     Finder::new("status=error"),
+///     12,
+/// );
+/// let m = finder.find(haystack).unwrap();
+/// assert_eq!(20, m.first());
+/// assert_eq!(36, m.second());
+/// assert_eq!(48, m.end());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ProximityFinder<'a, 'b> {
+    first: Finder<'a>,
+    second: Finder<'b>,
+    max_gap: usize,
+    drive: Drive,
+}
+
+/// Which needle is used to drive the search.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Drive {
+    First,
+    Second,
+}
+
+impl<'a, 'b> ProximityFinder<'a, 'b> {
+    /// Create a new proximity searcher for `first` followed by `second`, with
+    /// at most `max_gap` bytes between them.
+    ///
+    /// The default byte frequency heuristic is used to determine which needle
+    /// drives the search.
+    #[inline]
+    pub fn new(
+        first: Finder<'a>,
+        second: Finder<'b>,
+        max_gap: usize,
+    ) -> ProximityFinder<'a, 'b> {
+        ProximityFinder::with_ranker(
+            DefaultFrequencyRank,
+            first,
+            second,
+            max_gap,
+        )
+    }
+
+    /// Create a new proximity searcher for `first` followed by `second`, with
+    /// at most `max_gap` bytes between them.
+    ///
+    /// The given ranker is used to determine which needle is believed to be
+    /// rarer. That needle is then used to drive the search. See
+    /// [`HeuristicFrequencyRank`] for more details.
+    #[inline]
+    pub fn with_ranker<R: HeuristicFrequencyRank>(
+        ranker: R,
+        first: Finder<'a>,
+        second: Finder<'b>,
+        max_gap: usize,
+    ) -> ProximityFinder<'a, 'b> {
+        let rank1 = rarity(&ranker, first.needle());
+        let rank2 = rarity(&ranker, second.needle());
+        let drive = if rank2 < rank1 { Drive::Second } else { Drive::First };
+        trace!("building proximity searcher driven by {:?} needle", drive);
+        ProximityFinder { first, second, max_gap, drive }
+    }
+
+    /// Returns the first proximity match in the given haystack.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::{Finder, ProximityFinder};
+    ///
+    /// let finder =
+    ///     ProximityFinder::new(Finder::new("foo"), Finder::new("bar"), 3);
+    /// let m = finder.find(b"foo    bar foo..bar").unwrap();
+    /// assert_eq!((11, 16), (m.first(), m.second()));
+    /// assert_eq!(None, finder.find(b"bar foo    bar"));
+    /// ```
+    #[inline]
+    pub fn find(&self, haystack: &[u8]) -> Option<ProximityMatch> {
+        let mut prestate = PrefilterState::new();
+        self.find_from(&mut prestate, haystack, 0)
+    }
+
+    /// Returns an iterator over all non-overlapping proximity matches in the
+    /// given haystack.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::{Finder, ProximityFinder};
+    ///
+    /// let haystack = b"k=1 v=a k=2 k=3 v=b";
+    /// let finder =
+    ///     ProximityFinder::new(Finder::new("k="), Finder::new("v="), 2);
+    /// let firsts: Vec<usize> =
+    ///     finder.find_iter(haystack).map(|m| m.first()).collect();
+    /// assert_eq!(firsts, vec![0, 12]);
+    /// ```
+    #[inline]
+    pub fn find_iter<'f, 'h>(
+        &'f self,
+        haystack: &'h [u8],
+    ) -> ProximityIter<'f, 'h> {
+        ProximityIter {
+            finder: self,
+            haystack,
+            prestate: PrefilterState::new(),
+            pos: 0,
+        }
+    }
+
+    /// Returns the finder for the needle that must occur first.
+    #[inline]
+    pub fn first(&self) -> &Finder<'a> {
+        &self.first
+    }
+
+    /// Returns the finder for the needle that must occur second.
+    #[inline]
+    pub fn second(&self) -> &Finder<'b> {
+        &self.second
+    }
+
+    /// Returns the maximum number of bytes permitted between the end of the
+    /// first needle and the start of the second needle.
+    #[inline]
+    pub fn max_gap(&self) -> usize {
+        self.max_gap
+    }
+
+    /// Returns the first proximity match whose first needle begins at or
+    /// after `start`.
+    ///
+    /// `prestate` is the prefilter state of the driving needle. The needle
+    /// that is searched for within a window always uses a fresh state, since
+    /// each window is treated as an independent search.
+    fn find_from(
+        &self,
+        prestate: &mut PrefilterState,
+        haystack: &[u8],
+        start: usize,
+    ) -> Option<ProximityMatch> {
+        let len1 = self.first.needle().len();
+        let len2 = self.second.needle().len();
+        match self.drive {
+            Drive::First => {
+                let mut at = start;
+                loop {
+                    let i = self.first.find_in(
+                        prestate,
+                        haystack,
+                        at,
+                        haystack.len(),
+                    )?;
+                    let lo = i + len1;
+                    let hi = lo
+                        .saturating_add(self.max_gap)
+                        .saturating_add(len2)
+                        .min(haystack.len());
+                    let mut winstate = PrefilterState::new();
+                    if let Some(j) =
+                        self.second.find_in(&mut winstate, haystack, lo, hi)
+                    {
+                        return Some(ProximityMatch::new(i, j, j + len2));
+                    }
+                    at = i + 1;
+                }
+            }
+            Drive::Second => {
+                let mut at = start.checked_add(len1)?;
+                loop {
+                    let j = self.second.find_in(
+                        prestate,
+                        haystack,
+                        at,
+                        haystack.len(),
+                    )?;
+                    // The first needle must end somewhere in the range
+                    // `j - max_gap..=j`. We look for the leftmost occurrence
+                    // of it in that window. If a proximity match whose first
+                    // needle begins earlier existed, then it would have been
+                    // found in the window of an earlier occurrence of the
+                    // second needle.
+                    let lo =
+                        j.saturating_sub(len1.saturating_add(self.max_gap));
+                    let lo = lo.max(start);
+                    let mut winstate = PrefilterState::new();
+                    if let Some(i) =
+                        self.first.find_in(&mut winstate, haystack, lo, j)
+                    {
+                        return Some(ProximityMatch::new(i, j, j + len2));
+                    }
+                    at = j + 1;
+                }
+            }
+        }
+    }
+}
+
+/// Returns a rank approximating how rare the given needle is believed to be.
+/// Lower is rarer.
+///
+/// This uses the rank of the rarest byte in the needle, with ties broken in
+/// favor of longer needles. The empty needle is treated as the least rare.
+fn rarity<R: HeuristicFrequencyRank>(
+    ranker: &R,
+    needle: &[u8],
+) -> (u16, usize) {
+    let rarest = needle.iter().map(|&b| u16::from(ranker.rank(b))).min();
+    (rarest.unwrap_or(u16::MAX), usize::MAX - needle.len())
+}
+
+/// A single proximity match reported by a [`ProximityFinder`].
+///
+/// This records the start offsets of both needles, along with the end offset
+/// of the second needle.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ProximityMatch {
+    first: usize,
+    second: usize,
+    end: usize,
+}
+
+impl ProximityMatch {
+    #[inline]
+    fn new(first: usize, second: usize, end: usize) -> ProximityMatch {
+        ProximityMatch { first, second, end }
+    }
+
+    /// Returns the offset at which the first needle begins. This is also the
+    /// start of the entire proximity match.
+    #[inline]
+    pub fn first(&self) -> usize {
+        self.first
+    }
+
+    /// Returns the offset at which the second needle begins.
+    #[inline]
+    pub fn second(&self) -> usize {
+        self.second
+    }
+
+    /// Returns the offset at which the entire proximity match begins. This is
+    /// always equivalent to [`ProximityMatch::first`].
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.first
+    }
+
+    /// Returns the offset at which the entire proximity match ends. That is,
+    /// the offset immediately following the end of the second needle.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.end
+    }
+}
+
+/// An iterator over non-overlapping proximity matches.
+///
+/// This is created by [`ProximityFinder::find_iter`].
+///
+/// `'f` is the lifetime of the proximity finder while `'h` is the lifetime of
+/// the haystack.
+#[derive(Clone, Debug)]
+pub struct ProximityIter<'f, 'h> {
+    finder: &'f ProximityFinder<'f, 'f>,
+    haystack: &'h [u8],
+    prestate: PrefilterState,
+    pos: usize,
+}
+
+impl<'f, 'h> Iterator for ProximityIter<'f, 'h> {
+    type Item = ProximityMatch;
+
+    fn next(&mut self) -> Option<ProximityMatch> {
+        let m = self.finder.find_from(
+            &mut self.prestate,
+            self.haystack,
+            self.pos,
+        )?;
+        // When both needles are empty, the match is empty too, so we need to
+        // make sure we make progress.
+        self.pos = m.end().max(m.start() + 1);
+        Some(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::memmem::FinderBuilder;
+
+    use super::*;
+
+    fn naive(
+        haystack: &[u8],
+        needle1: &[u8],
+        needle2: &[u8],
+        max_gap: usize,
+    ) -> Vec<ProximityMatch> {
+        let is_at = |needle: &[u8], i: usize| {
+            haystack.get(i..).map_or(false, |h| h.starts_with(needle))
+        };
+        let find_at = |at: usize| {
+            (at..=haystack.len()).filter(|&i| is_at(needle1, i)).find_map(
+                |i| {
+                    let lo = i + needle1.len();
+                    let j =
+                        (lo..=lo + max_gap).find(|&j| is_at(needle2, j))?;
+                    Some(ProximityMatch::new(i, j, j + needle2.len()))
+                },
+            )
+        };
+        let mut matches = Vec::new();
+        let mut at = 0;
+        while let Some(m) = find_at(at) {
+            matches.push(m);
+            at = m.end().max(m.start() + 1);
+        }
+        matches
+    }
+
+    #[test]
+    fn both_drives() {
+        let haystacks: &[&[u8]] = &[
+            b"",
+            b"ab",
+            b"a b",
+            b"aab bba",
+            b"a  b a b ab",
+            b"xaxxxbxxabxxxxab",
+            b"abababab",
+            b"zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzab",
+        ];
+        let needles: &[(&[u8], &[u8])] = &[
+            (b"a", b"b"),
+            (b"ab", b"ab"),
+            (b"b", b"a"),
+            (b"", b"b"),
+            (b"a", b""),
+            (b"", b""),
+            (b"aa", b"bb"),
+            (b"zab", b"z"),
+        ];
+        for &haystack in haystacks {
+            for &(n1, n2) in needles {
+                for max_gap in [0, 1, 2, 5, usize::MAX / 2] {
+                    let expected = naive(haystack, n1, n2, max_gap.min(100));
+                    for drive in [Drive::First, Drive::Second] {
+                        let finder = ProximityFinder {
+                            first: Finder::new(n1),
+                            second: Finder::new(n2),
+                            max_gap,
+                            drive,
+                        };
+                        let got: Vec<ProximityMatch> =
+                            finder.find_iter(haystack).collect();
+                        assert_eq!(
+                            expected,
+                            got,
+                            "haystack: {:?}, needles: {:?}, gap: {}, \
+                             drive: {:?}",
+                            haystack,
+                            (n1, n2),
+                            max_gap,
+                            drive,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn whole_word() {
+        let mut builder = FinderBuilder::new();
+        builder.whole_word(true);
+        let haystack = b"key keys=1 key = 2";
+        for drive in [Drive::First, Drive::Second] {
+            let finder = ProximityFinder {
+                first: builder.build_forward("key"),
+                second: builder.build_forward("2"),
+                max_gap: 3,
+                drive,
+            };
+            let m = finder.find(haystack).unwrap();
+            assert_eq!((11, 17, 18), (m.first(), m.second(), m.end()));
+        }
+    }
+
+    #[test]
+    fn drive_selection() {
+        let finder =
+            ProximityFinder::new(Finder::new("e"), Finder::new("\x00"), 1);
+        assert_eq!(Drive::Second, finder.drive);
+        let finder =
+            ProximityFinder::new(Finder::new("\x00"), Finder::new("e"), 1);
+        assert_eq!(Drive::First, finder.drive);
+        let finder =
+            ProximityFinder::new(Finder::new(""), Finder::new("e"), 1);
+        assert_eq!(Drive::Second, finder.drive);
+    }
+}
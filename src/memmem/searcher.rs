@@ -1,6 +1,18 @@
-use crate::arch::all::{
-    packedpair::{HeuristicFrequencyRank, Pair},
-    rabinkarp, twoway,
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::{
+    arch::all::{
+        packedpair::{
+            default_rank::RANK, HeuristicFrequencyRank, Pair, Triple,
+        },
+        rabinkarp, twoway,
+    },
+    memmem::{
+        serialize::{DeserializeError, Reader, NO_FACTORIZATION},
+        stats::SearchStats,
+    },
+    Implementation,
 };
 
 #[cfg(target_arch = "aarch64")]
@@ -11,6 +23,12 @@ use crate::arch::wasm32::simd128::packedpair as simd128;
 use crate::arch::x86_64::{
     avx2::packedpair as avx2, sse2::packedpair as sse2,
 };
+#[cfg(any(
+    all(target_arch = "x86_64", target_feature = "sse2"),
+    all(target_arch = "wasm32", target_feature = "simd128"),
+    target_arch = "aarch64"
+))]
+use crate::memmem::shortneedle::ShortNeedle;
 
 /// A "meta" substring searcher.
 ///
@@ -34,6 +52,7 @@ pub(crate) struct Searcher {
     call: SearcherKindFn,
     kind: SearcherKind,
     rabinkarp: rabinkarp::Finder,
+    strategy: Strategy,
 }
 
 impl Searcher {
@@ -54,6 +73,7 @@ impl Searcher {
                     call: searcher_kind_empty,
                     kind: SearcherKind { empty: () },
                     rabinkarp,
+                    strategy: Strategy::Empty,
                 }
             } else {
                 trace!("building one-byte substring searcher");
@@ -62,6 +82,7 @@ impl Searcher {
                     call: searcher_kind_one_byte,
                     kind: SearcherKind { one_byte: needle[0] },
                     rabinkarp,
+                    strategy: Strategy::OneByte,
                 }
             };
         }
@@ -69,6 +90,19 @@ impl Searcher {
             Some(pair) => pair,
             None => return Searcher::twoway(needle, rabinkarp, None),
         };
+        #[cfg(any(
+            all(target_arch = "x86_64", target_feature = "sse2"),
+            all(target_arch = "wasm32", target_feature = "simd128"),
+            target_arch = "aarch64"
+        ))]
+        {
+            if is_very_common_pair(&ranker, pair, needle) {
+                if let Some(short) = ShortNeedle::new(needle) {
+                    trace!("building short needle substring searcher");
+                    return Searcher::short_needle(short, rabinkarp);
+                }
+            }
+        }
         debug_assert_ne!(
             pair.index1(),
             pair.index2(),
@@ -76,26 +110,40 @@ impl Searcher {
         );
         #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
         {
-            if let Some(pp) = avx2::Finder::with_pair(needle, pair) {
+            if let Some(pp) = avx2_with_pair(needle, pair) {
                 if do_packed_search(needle) {
                     trace!("building x86_64 AVX2 substring searcher");
                     let kind = SearcherKind { avx2: pp };
-                    Searcher { call: searcher_kind_avx2, kind, rabinkarp }
+                    let strategy = Strategy::PackedPair {
+                        pair,
+                        implementation: Implementation::Avx2,
+                    };
+                    let call = searcher_kind_avx2;
+                    Searcher { call, kind, rabinkarp, strategy }
                 } else if prefilter.is_none() {
                     Searcher::twoway(needle, rabinkarp, None)
                 } else {
-                    let prestrat = Prefilter::avx2(pp, needle);
+                    let prestrat =
+                        Prefilter::triple_if_common(&ranker, pair, needle)
+                            .unwrap_or_else(|| Prefilter::avx2(pp, needle));
                     Searcher::twoway(needle, rabinkarp, Some(prestrat))
                 }
-            } else if let Some(pp) = sse2::Finder::with_pair(needle, pair) {
+            } else if let Some(pp) = sse2_with_pair(needle, pair) {
                 if do_packed_search(needle) {
                     trace!("building x86_64 SSE2 substring searcher");
                     let kind = SearcherKind { sse2: pp };
-                    Searcher { call: searcher_kind_sse2, kind, rabinkarp }
+                    let strategy = Strategy::PackedPair {
+                        pair,
+                        implementation: Implementation::Sse2,
+                    };
+                    let call = searcher_kind_sse2;
+                    Searcher { call, kind, rabinkarp, strategy }
                 } else if prefilter.is_none() {
                     Searcher::twoway(needle, rabinkarp, None)
                 } else {
-                    let prestrat = Prefilter::sse2(pp, needle);
+                    let prestrat =
+                        Prefilter::triple_if_common(&ranker, pair, needle)
+                            .unwrap_or_else(|| Prefilter::sse2(pp, needle));
                     Searcher::twoway(needle, rabinkarp, Some(prestrat))
                 }
             } else if prefilter.is_none() {
@@ -111,15 +159,22 @@ impl Searcher {
         }
         #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
         {
-            if let Some(pp) = simd128::Finder::with_pair(needle, pair) {
+            if let Some(pp) = simd128_with_pair(needle, pair) {
                 if do_packed_search(needle) {
                     trace!("building wasm32 simd128 substring searcher");
                     let kind = SearcherKind { simd128: pp };
-                    Searcher { call: searcher_kind_simd128, kind, rabinkarp }
+                    let strategy = Strategy::PackedPair {
+                        pair,
+                        implementation: Implementation::Simd128,
+                    };
+                    let call = searcher_kind_simd128;
+                    Searcher { call, kind, rabinkarp, strategy }
                 } else if prefilter.is_none() {
                     Searcher::twoway(needle, rabinkarp, None)
                 } else {
-                    let prestrat = Prefilter::simd128(pp, needle);
+                    let prestrat =
+                        Prefilter::triple_if_common(&ranker, pair, needle)
+                            .unwrap_or_else(|| Prefilter::simd128(pp, needle));
                     Searcher::twoway(needle, rabinkarp, Some(prestrat))
                 }
             } else if prefilter.is_none() {
@@ -131,15 +186,22 @@ impl Searcher {
         }
         #[cfg(target_arch = "aarch64")]
         {
-            if let Some(pp) = neon::Finder::with_pair(needle, pair) {
+            if let Some(pp) = neon_with_pair(needle, pair) {
                 if do_packed_search(needle) {
                     trace!("building aarch64 neon substring searcher");
                     let kind = SearcherKind { neon: pp };
-                    Searcher { call: searcher_kind_neon, kind, rabinkarp }
+                    let strategy = Strategy::PackedPair {
+                        pair,
+                        implementation: Implementation::Neon,
+                    };
+                    let call = searcher_kind_neon;
+                    Searcher { call, kind, rabinkarp, strategy }
                 } else if prefilter.is_none() {
                     Searcher::twoway(needle, rabinkarp, None)
                 } else {
-                    let prestrat = Prefilter::neon(pp, needle);
+                    let prestrat =
+                        Prefilter::triple_if_common(&ranker, pair, needle)
+                            .unwrap_or_else(|| Prefilter::neon(pp, needle));
                     Searcher::twoway(needle, rabinkarp, Some(prestrat))
                 }
             } else if prefilter.is_none() {
@@ -164,6 +226,55 @@ impl Searcher {
         }
     }
 
+    /// Creates a new "meta" substring searcher in a `const` context.
+    ///
+    /// This analyzes the needle exactly as `Searcher::new` does with the
+    /// default prefilter configuration and frequency ranking. That is, the
+    /// same pair of rare bytes is chosen and the same Two-Way factorization
+    /// is computed. However, the vector algorithms can't be set up at compile
+    /// time (and some of them depend on CPU features that are only known at
+    /// runtime), so this always uses the scalar Two-Way searcher. It is
+    /// accelerated by the fallback prefilter when the needle has a rare
+    /// enough byte.
+    #[inline]
+    pub(crate) const fn new_const(needle: &[u8]) -> Searcher {
+        let rabinkarp = rabinkarp::Finder::new(needle);
+        if needle.is_empty() {
+            let kind = SearcherKind { empty: () };
+            let (call, strategy) = (searcher_kind_empty, Strategy::Empty);
+            return Searcher { call, kind, rabinkarp, strategy };
+        } else if needle.len() == 1 {
+            let kind = SearcherKind { one_byte: needle[0] };
+            let (call, strategy) = (searcher_kind_one_byte, Strategy::OneByte);
+            return Searcher { call, kind, rabinkarp, strategy };
+        }
+        let finder = twoway::Finder::new(needle);
+        let prestrat = match Pair::new(needle) {
+            None => None,
+            Some(pair) => Prefilter::fallback_const(pair, needle),
+        };
+        match prestrat {
+            None => {
+                let kind = SearcherKind { two_way: finder };
+                let (call, strategy) =
+                    (searcher_kind_two_way, Strategy::TwoWay);
+                Searcher { call, kind, rabinkarp, strategy }
+            }
+            Some(prestrat) => {
+                let strategy = prestrat.strategy();
+                let two_way_with_prefilter =
+                    TwoWayWithPrefilter { finder, prestrat };
+                let kind = SearcherKind { two_way_with_prefilter };
+                Searcher {
+                    call: searcher_kind_two_way_with_prefilter,
+                    kind,
+                    rabinkarp,
+                    strategy,
+                }
+            }
+        }
+    }
+
     /// Creates a new searcher that always uses the Two-Way algorithm. This is
     /// typically used when vector algorithms are unavailable or inappropriate.
     /// (For example, when the needle is "too long.")
@@ -177,17 +288,31 @@ impl Searcher {
         prestrat: Option<Prefilter>,
     ) -> Searcher {
         let finder = twoway::Finder::new(needle);
+        Searcher::twoway_with_finder(finder, rabinkarp, prestrat)
+    }
+
+    /// Like `twoway`, but uses a Two-Way searcher that has already been
+    /// built.
+    #[inline]
+    fn twoway_with_finder(
+        finder: twoway::Finder,
+        rabinkarp: rabinkarp::Finder,
+        prestrat: Option<Prefilter>,
+    ) -> Searcher {
         match prestrat {
             None => {
                 trace!("building scalar two-way substring searcher");
                 let kind = SearcherKind { two_way: finder };
-                Searcher { call: searcher_kind_two_way, kind, rabinkarp }
+                let (call, strategy) =
+                    (searcher_kind_two_way, Strategy::TwoWay);
+                Searcher { call, kind, rabinkarp, strategy }
             }
             Some(prestrat) => {
                 trace!(
                     "building scalar two-way \
                      substring searcher with a prefilter"
                 );
+                let strategy = prestrat.strategy();
                 let two_way_with_prefilter =
                     TwoWayWithPrefilter { finder, prestrat };
                 let kind = SearcherKind { two_way_with_prefilter };
@@ -195,11 +320,413 @@ impl Searcher {
                     call: searcher_kind_two_way_with_prefilter,
                     kind,
                     rabinkarp,
+                    strategy,
                 }
             }
         }
     }
 
+    /// Creates a new searcher that uses a vector implementation of the short
+    /// needle algorithm.
+    #[cfg(any(
+        all(target_arch = "x86_64", target_feature = "sse2"),
+        all(target_arch = "wasm32", target_feature = "simd128"),
+        target_arch = "aarch64"
+    ))]
+    #[inline]
+    fn short_needle(
+        short: ShortNeedle,
+        rabinkarp: rabinkarp::Finder,
+    ) -> Searcher {
+        let implementation = short.implementation();
+        let strategy = Strategy::ShortNeedle { implementation };
+        let kind = SearcherKind { short_needle: short };
+        let call = searcher_kind_short_needle;
+        Searcher { call, kind, rabinkarp, strategy }
+    }
+
+    /// Creates a new searcher that uses a vector implementation of the packed
+    /// pair algorithm, if one is available.
+    #[inline]
+    fn packed_pair(
+        needle: &[u8],
+        pair: Pair,
+        rabinkarp: rabinkarp::Finder,
+    ) -> Option<Searcher> {
+        let strategy =
+            |implementation| Strategy::PackedPair { pair, implementation };
+        #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+        {
+            if let Some(pp) = avx2_with_pair(needle, pair) {
+                let kind = SearcherKind { avx2: pp };
+                let call = searcher_kind_avx2;
+                let strategy = strategy(Implementation::Avx2);
+                Some(Searcher { call, kind, rabinkarp, strategy })
+            } else if let Some(pp) = sse2_with_pair(needle, pair) {
+                let kind = SearcherKind { sse2: pp };
+                let call = searcher_kind_sse2;
+                let strategy = strategy(Implementation::Sse2);
+                Some(Searcher { call, kind, rabinkarp, strategy })
+            } else {
+                None
+            }
+        }
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        {
+            let pp = simd128_with_pair(needle, pair)?;
+            let kind = SearcherKind { simd128: pp };
+            let call = searcher_kind_simd128;
+            let strategy = strategy(Implementation::Simd128);
+            Some(Searcher { call, kind, rabinkarp, strategy })
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            let pp = neon_with_pair(needle, pair)?;
+            let kind = SearcherKind { neon: pp };
+            let call = searcher_kind_neon;
+            let strategy = strategy(Implementation::Neon);
+            Some(Searcher { call, kind, rabinkarp, strategy })
+        }
+        #[cfg(not(any(
+            all(target_arch = "x86_64", target_feature = "sse2"),
+            all(target_arch = "wasm32", target_feature = "simd128"),
+            target_arch = "aarch64"
+        )))]
+        {
+            let _ = (needle, rabinkarp, strategy);
+            None
+        }
+    }
+
+    /// Writes the state of this searcher to `dst`. The needle given must be
+    /// the same as the needle that this searcher was initialized with.
+    ///
+    /// The format written here is read by `Searcher::read_from`.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn write_to(&self, needle: &[u8], dst: &mut Vec<u8>) {
+        use crate::memmem::serialize::{write_factorization, write_u32};
+
+        let (tag, pair) = match self.strategy {
+            Strategy::Empty => (0, None),
+            Strategy::OneByte => (1, None),
+            Strategy::TwoWay => (2, None),
+            Strategy::TwoWayWithPrefilter { pair, implementation, triple } => {
+                if triple.is_some() {
+                    (7, Some(pair))
+                } else if implementation.is_vector() {
+                    (4, Some(pair))
+                } else {
+                    (3, Some(pair))
+                }
+            }
+            Strategy::PackedPair { pair, .. } => (5, Some(pair)),
+            Strategy::ShortNeedle { .. } => (6, None),
+        };
+        // SAFETY: The strategy always corresponds to the field of the
+        // `SearcherKind` union that is set.
+        let factorization = unsafe {
+            match self.strategy {
+                // A short needle searcher is cheap to build from scratch, so
+                // nothing is written for it beyond its tag.
+                Strategy::Empty
+                | Strategy::OneByte
+                | Strategy::ShortNeedle { .. } => None,
+                Strategy::TwoWay => Some(self.kind.two_way.factorization()),
+                Strategy::TwoWayWithPrefilter { .. } => Some(
+                    self.kind.two_way_with_prefilter.finder.factorization(),
+                ),
+                // A vector searcher doesn't need Two-Way, but it might not be
+                // available where the searcher is read. So we always write
+                // what's needed to fall back to Two-Way.
+                Strategy::PackedPair { .. } => {
+                    Some(twoway::Finder::new(needle).factorization())
+                }
+            }
+        };
+        dst.push(tag);
+        dst.push(pair.map_or(0, |p| p.index1()));
+        dst.push(pair.map_or(0, |p| p.index2()));
+        if let Strategy::TwoWayWithPrefilter { triple: Some(t), .. } =
+            self.strategy
+        {
+            dst.push(t.index3());
+        }
+        let (hash, hash_2pow) = self.rabinkarp.hash_parts();
+        write_u32(hash, dst);
+        write_u32(hash_2pow, dst);
+        write_factorization(factorization, dst);
+    }
+
+    /// Reads a searcher for the given needle that was written by
+    /// `Searcher::write_to`. The ranker given must be the one that the
+    /// searcher was built with.
+    ///
+    /// The state that was written is used as is, since computing it again
+    /// would take as long as building a new searcher. Instead, it is only
+    /// checked to be consistent with the needle: the rare byte offsets must
+    /// be in bounds and the critical factorization must permit a search to
+    /// terminate without panicking. The Rabin-Karp hash is cheap enough to
+    /// recompute, so it is compared in full.
+    ///
+    /// If the chosen vector algorithm isn't available on the current target,
+    /// then the next best strategy is used instead.
+    pub(crate) fn read_from<R: HeuristicFrequencyRank>(
+        ranker: R,
+        needle: &[u8],
+        r: &mut Reader<'_>,
+    ) -> Result<Searcher, DeserializeError> {
+        let tag = r.u8()?;
+        let (index1, index2) = (r.u8()?, r.u8()?);
+        let index3 = if tag == 7 { r.u8()? } else { 0 };
+        let hash = (r.u32()?, r.u32()?);
+        let factorization = r.factorization()?;
+
+        let rabinkarp = rabinkarp::Finder::new(needle);
+        if rabinkarp.hash_parts() != hash {
+            return Err(DeserializeError::new("Rabin-Karp hash mismatch"));
+        }
+        match tag {
+            0 if needle.is_empty() => {}
+            1 if needle.len() == 1 => {}
+            2..=5 if needle.len() >= 2 => {}
+            6 if (2..=4).contains(&needle.len()) => {}
+            7 if needle.len() >= 3 => {}
+            0..=7 => {
+                return Err(DeserializeError::new(
+                    "strategy is invalid for needle length",
+                ))
+            }
+            _ => return Err(DeserializeError::new("unrecognized strategy")),
+        }
+        let pair = if matches!(tag, 3..=5 | 7) {
+            let pair = Pair::with_indices(needle, index1, index2)
+                .ok_or(DeserializeError::new("invalid rare byte pair"))?;
+            Some(pair)
+        } else if (index1, index2) != (0, 0) {
+            return Err(DeserializeError::new("unexpected rare byte pair"));
+        } else {
+            None
+        };
+        let finder = if matches!(tag, 2..=5 | 7) {
+            twoway::Finder::from_factorization(needle, factorization).ok_or(
+                DeserializeError::new("invalid Two-Way factorization"),
+            )?
+        } else if factorization != NO_FACTORIZATION {
+            return Err(DeserializeError::new(
+                "unexpected Two-Way factorization",
+            ));
+        } else {
+            match tag {
+                0 => {
+                    let kind = SearcherKind { empty: () };
+                    let (call, strategy) =
+                        (searcher_kind_empty, Strategy::Empty);
+                    return Ok(Searcher { call, kind, rabinkarp, strategy });
+                }
+                1 => {
+                    let kind = SearcherKind { one_byte: needle[0] };
+                    let (call, strategy) =
+                        (searcher_kind_one_byte, Strategy::OneByte);
+                    return Ok(Searcher { call, kind, rabinkarp, strategy });
+                }
+                // This picks the short needle algorithm again when it's
+                // available, and the next best strategy otherwise. Since
+                // the needle is at most 4 bytes, this is cheap.
+                _ => {
+                    let config = PrefilterConfig::default();
+                    return Ok(Searcher::new(config, ranker, needle));
+                }
+            }
+        };
+        let pair = match pair {
+            None => {
+                return Ok(Searcher::twoway_with_finder(
+                    finder, rabinkarp, None,
+                ))
+            }
+            Some(pair) => pair,
+        };
+        if tag == 5 {
+            if let Some(searcher) =
+                Searcher::packed_pair(needle, pair, rabinkarp.clone())
+            {
+                return Ok(searcher);
+            }
+        }
+        if tag == 7 {
+            let triple = Triple::with_indices(needle, index1, index2, index3)
+                .ok_or(DeserializeError::new("invalid rare byte triple"))?;
+            if let Some(prestrat) = Prefilter::with_triple(needle, triple) {
+                let prestrat = Some(prestrat);
+                return Ok(Searcher::twoway_with_finder(
+                    finder, rabinkarp, prestrat,
+                ));
+            }
+        }
+        let prestrat = Prefilter::with_pair(needle, pair, tag != 3);
+        Ok(Searcher::twoway_with_finder(finder, rabinkarp, prestrat))
+    }
+
+    /// Returns the strategy used by this searcher.
+    #[inline]
+    pub(crate) fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    /// Like `find`, but also updates the given statistics.
+    ///
+    /// This follows the same decisions as `find`, but a vector algorithm
+    /// that confirms candidates on its own is replaced by its prefilter
+    /// followed by a scalar confirmation step, so that the candidates can be
+    /// counted.
+    pub(crate) fn find_with_stats(
+        &self,
+        prestate: &mut PrefilterState,
+        haystack: &[u8],
+        needle: &[u8],
+        stats: &mut SearchStats,
+    ) -> Option<usize> {
+        stats.searches += 1;
+        let result = if haystack.len() < needle.len() {
+            None
+        } else {
+            self.find_with_stats_imp(prestate, haystack, needle, stats)
+        };
+        let scanned = result.map_or(haystack.len(), |i| i + needle.len());
+        stats.bytes_scanned += scanned as u64;
+        result
+    }
+
+    /// The implementation of `find_with_stats`. Callers must ensure that the
+    /// haystack is at least as long as the needle.
+    fn find_with_stats_imp(
+        &self,
+        prestate: &mut PrefilterState,
+        haystack: &[u8],
+        needle: &[u8],
+        stats: &mut SearchStats,
+    ) -> Option<usize> {
+        match self.strategy {
+            // The short needle algorithm has no candidates to count, since
+            // every position it reports is a match.
+            Strategy::Empty
+            | Strategy::OneByte
+            | Strategy::ShortNeedle { .. } => {
+                self.find(prestate, haystack, needle)
+            }
+            Strategy::TwoWay => {
+                if rabinkarp::is_fast(haystack, needle) {
+                    stats.rabinkarp_searches += 1;
+                    return self.rabinkarp.find(haystack, needle);
+                }
+                stats.twoway_searches += 1;
+                // SAFETY: The strategy always corresponds to the field of
+                // the `SearcherKind` union that is set.
+                unsafe { self.kind.two_way.find(haystack, needle) }
+            }
+            Strategy::TwoWayWithPrefilter { .. } => {
+                if rabinkarp::is_fast(haystack, needle) {
+                    stats.rabinkarp_searches += 1;
+                    return self.rabinkarp.find(haystack, needle);
+                }
+                stats.twoway_searches += 1;
+                // SAFETY: The strategy always corresponds to the field of
+                // the `SearcherKind` union that is set.
+                let TwoWayWithPrefilter { ref finder, ref prestrat } =
+                    unsafe { &self.kind.two_way_with_prefilter };
+                let candidates = stats.candidates;
+                let pre = Pre {
+                    prestate: &mut *prestate,
+                    prestrat,
+                    stats: Some(&mut *stats),
+                };
+                let result =
+                    finder.find_with_prefilter(Some(pre), haystack, needle);
+                let mut false_positives = stats.candidates - candidates;
+                // While the prefilter is effective, it is consulted before
+                // every attempt at confirming a match. So if it is still
+                // effective, the match was its last candidate.
+                if result.is_some() && !prestate.is_inert() {
+                    false_positives = false_positives.saturating_sub(1);
+                }
+                stats.false_positives += false_positives;
+                result
+            }
+            Strategy::PackedPair { pair, .. } => {
+                if haystack.len() < self.packed_pair_min_haystack_len() {
+                    stats.rabinkarp_searches += 1;
+                    return self.rabinkarp.find(haystack, needle);
+                }
+                // The vector algorithms report the same candidates as the
+                // portable prefilter for the same pair. They then confirm
+                // each candidate by comparing it with the needle.
+                let finder = crate::arch::all::packedpair::Finder::with_pair(
+                    needle, pair,
+                )?;
+                let mut pos = 0;
+                loop {
+                    let i = pos + finder.find_prefilter(&haystack[pos..])?;
+                    if haystack.len() - i < needle.len() {
+                        return None;
+                    }
+                    stats.candidates += 1;
+                    let matched = haystack[i..]
+                        .iter()
+                        .zip(needle)
+                        .take_while(|&(h, n)| h == n)
+                        .count();
+                    let compared =
+                        matched + usize::from(matched < needle.len());
+                    stats.verified_bytes += compared as u64;
+                    if matched == needle.len() {
+                        return Some(i);
+                    }
+                    stats.false_positives += 1;
+                    pos = i + 1;
+                }
+            }
+        }
+    }
+
+    /// Returns the minimum haystack length required by the vector packed
+    /// pair algorithm used by this searcher. Shorter haystacks are searched
+    /// with Rabin-Karp instead.
+    ///
+    /// This returns `0` when this searcher doesn't use a vector packed pair
+    /// algorithm.
+    fn packed_pair_min_haystack_len(&self) -> usize {
+        // SAFETY: The strategy always corresponds to the field of the
+        // `SearcherKind` union that is set.
+        unsafe {
+            match self.strategy {
+                #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+                Strategy::PackedPair {
+                    implementation: Implementation::Avx2,
+                    ..
+                } => self.kind.avx2.min_haystack_len(),
+                #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+                Strategy::PackedPair {
+                    implementation: Implementation::Sse2,
+                    ..
+                } => self.kind.sse2.min_haystack_len(),
+                #[cfg(all(
+                    target_arch = "wasm32",
+                    target_feature = "simd128"
+                ))]
+                Strategy::PackedPair {
+                    implementation: Implementation::Simd128,
+                    ..
+                } => self.kind.simd128.min_haystack_len(),
+                #[cfg(target_arch = "aarch64")]
+                Strategy::PackedPair {
+                    implementation: Implementation::Neon,
+                    ..
+                } => self.kind.neon.min_haystack_len(),
+                _ => 0,
+            }
+        }
+    }
+
     /// Searches the given haystack for the given needle. The needle given
     /// should be the same as the needle that this finder was initialized
     /// with.
@@ -230,6 +757,7 @@ impl core::fmt::Debug for Searcher {
             .field("call", &"<searcher function>")
             .field("kind", &"<searcher kind union>")
             .field("rabinkarp", &self.rabinkarp)
+            .field("strategy", &self.strategy)
             .finish()
     }
 }
@@ -255,6 +783,90 @@ union SearcherKind {
     simd128: crate::arch::wasm32::simd128::packedpair::Finder,
     #[cfg(target_arch = "aarch64")]
     neon: crate::arch::aarch64::neon::packedpair::Finder,
+    #[cfg(any(
+        all(target_arch = "x86_64", target_feature = "sse2"),
+        all(target_arch = "wasm32", target_feature = "simd128"),
+        target_arch = "aarch64"
+    ))]
+    short_needle: ShortNeedle,
+}
+
+/// A description of the strategy used by a substring searcher.
+///
+/// This is returned by [`Finder::strategy`](crate::memmem::Finder::strategy)
+/// and [`FinderRev::strategy`](crate::memmem::FinderRev::strategy). It is
+/// principally useful for debugging performance problems, since the strategy
+/// chosen depends on the needle, on heuristics and on what the current target
+/// supports.
+///
+/// Note that even when a vector algorithm is chosen, a searcher may still use
+/// a different algorithm for haystacks that are too short for the vector
+/// algorithm to handle.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::{Finder, Strategy};
+///
+/// assert_eq!(Strategy::Empty, Finder::new("").strategy());
+/// assert_eq!(Strategy::OneByte, Finder::new("a").strategy());
+/// match Finder::new("foobar").strategy() {
+///     Strategy::PackedPair { pair, implementation, .. } => {
+///         println!("using {:?} with {:?}", implementation, pair);
+///     }
+///     strategy => println!("using {:?}", strategy),
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Strategy {
+    /// The needle is empty, so it matches at every position.
+    Empty,
+    /// The needle is a single byte, so `memchr` is used.
+    OneByte,
+    /// The Two-Way algorithm is used without a prefilter.
+    TwoWay,
+    /// The Two-Way algorithm is used with a prefilter that looks for
+    /// occurrences of a pair of bytes from the needle that are believed to be
+    /// rare.
+    #[non_exhaustive]
+    TwoWayWithPrefilter {
+        /// The pair of bytes that the prefilter looks for.
+        pair: Pair,
+        /// The implementation of the prefilter.
+        implementation: Implementation,
+        /// When set, the prefilter also looks for a third byte, and `pair`
+        /// is made up of the first two bytes of this triple. This is only
+        /// done when the pair on its own is believed to be too common for
+        /// the prefilter to be effective.
+        ///
+        /// Since a prefilter is only used for needles that are too long for
+        /// `PackedPair`, short needles never use a triple. So a short needle
+        /// made up only of common bytes, like one from a DNA sequence, still
+        /// uses `PackedPair` with its common pair.
+        triple: Option<Triple>,
+    },
+    /// A vector algorithm looks for occurrences of a pair of bytes from the
+    /// needle that are believed to be rare, and confirms each candidate by
+    /// comparing it with the needle directly. This is only used for short
+    /// needles.
+    #[non_exhaustive]
+    PackedPair {
+        /// The pair of bytes that the algorithm looks for.
+        pair: Pair,
+        /// The vector implementation of the algorithm.
+        implementation: Implementation,
+    },
+    /// A vector algorithm compares every position in the haystack with the
+    /// entire needle at once, so there are no candidates to confirm. This is
+    /// only used for needles of `2`, `3` or `4` bytes. For forward search, it
+    /// is only used when the needle is made up of very common bytes, since
+    /// `PackedPair` is faster otherwise.
+    #[non_exhaustive]
+    ShortNeedle {
+        /// The vector implementation of the algorithm.
+        implementation: Implementation,
+    },
 }
 
 /// A two-way substring searcher with a prefilter.
@@ -347,7 +959,7 @@ unsafe fn searcher_kind_two_way_with_prefilter(
     } else {
         let TwoWayWithPrefilter { ref finder, ref prestrat } =
             searcher.kind.two_way_with_prefilter;
-        let pre = Pre { prestate, prestrat };
+        let pre = Pre { prestate, prestrat, stats: None };
         finder.find_with_prefilter(Some(pre), haystack, needle)
     }
 }
@@ -436,6 +1048,28 @@ unsafe fn searcher_kind_neon(
     }
 }
 
+/// Reads from the `short_needle` field of `SearcherKind` to execute a
+/// vectorized substring search implementation specialized to needles of `2`,
+/// `3` or `4` bytes.
+///
+/// # Safety
+///
+/// Callers must ensure that the `searcher.kind.short_needle` union field is
+/// set.
+#[cfg(any(
+    all(target_arch = "x86_64", target_feature = "sse2"),
+    all(target_arch = "wasm32", target_feature = "simd128"),
+    target_arch = "aarch64"
+))]
+unsafe fn searcher_kind_short_needle(
+    searcher: &Searcher,
+    _prestate: &mut PrefilterState,
+    haystack: &[u8],
+    _needle: &[u8],
+) -> Option<usize> {
+    searcher.kind.short_needle.find(haystack)
+}
+
 /// A reverse substring searcher.
 #[derive(Clone, Debug)]
 pub(crate) struct SearcherRev {
@@ -445,10 +1079,11 @@ pub(crate) struct SearcherRev {
 
 /// The kind of the reverse searcher.
 ///
-/// For the reverse case, we don't do any SIMD acceleration or prefilters.
-/// There is no specific technical reason why we don't, but rather don't do it
-/// because it's not clear it's worth the extra code to do so. If you have a
-/// use case for it, please file an issue.
+/// For the reverse case, we don't do any prefilters, and the only SIMD
+/// acceleration is for needles of `2`, `3` or `4` bytes. There is no specific
+/// technical reason why we don't do more, but rather don't do it because it's
+/// not clear it's worth the extra code to do so. If you have a use case for
+/// it, please file an issue.
 ///
 /// We also don't do the union trick as we do with the forward case and
 /// prefilters. Basically for the same reason we don't have prefilters or
@@ -458,8 +1093,20 @@ pub(crate) struct SearcherRev {
 #[derive(Clone, Debug)]
 enum SearcherRevKind {
     Empty,
-    OneByte { needle: u8 },
-    TwoWay { finder: twoway::FinderRev },
+    OneByte {
+        needle: u8,
+    },
+    TwoWay {
+        finder: twoway::FinderRev,
+    },
+    #[cfg(any(
+        all(target_arch = "x86_64", target_feature = "sse2"),
+        all(target_arch = "wasm32", target_feature = "simd128"),
+        target_arch = "aarch64"
+    ))]
+    ShortNeedle {
+        finder: ShortNeedle,
+    },
 }
 
 impl SearcherRev {
@@ -468,6 +1115,23 @@ impl SearcherRev {
     /// of a needle in a haystack.
     #[inline]
     pub(crate) fn new(needle: &[u8]) -> SearcherRev {
+        let rabinkarp = rabinkarp::FinderRev::new(needle);
+        #[cfg(any(
+            all(target_arch = "x86_64", target_feature = "sse2"),
+            all(target_arch = "wasm32", target_feature = "simd128"),
+            target_arch = "aarch64"
+        ))]
+        {
+            // Unlike the forward case, this doesn't depend on how common the
+            // needle's bytes are. There is no vector algorithm for reverse
+            // search to fall back to, and the short needle algorithm is much
+            // faster than Two-Way even when the needle is rare.
+            if let Some(finder) = ShortNeedle::new(needle) {
+                trace!("building short needle reverse substring searcher");
+                let kind = SearcherRevKind::ShortNeedle { finder };
+                return SearcherRev { kind, rabinkarp };
+            }
+        }
         let kind = if needle.len() <= 1 {
             if needle.is_empty() {
                 trace!("building empty reverse substring searcher");
@@ -482,10 +1146,109 @@ impl SearcherRev {
             let finder = twoway::FinderRev::new(needle);
             SearcherRevKind::TwoWay { finder }
         };
-        let rabinkarp = rabinkarp::FinderRev::new(needle);
         SearcherRev { kind, rabinkarp }
     }
 
+    /// Returns the strategy used by this searcher.
+    #[inline]
+    pub(crate) fn strategy(&self) -> Strategy {
+        match self.kind {
+            SearcherRevKind::Empty => Strategy::Empty,
+            SearcherRevKind::OneByte { .. } => Strategy::OneByte,
+            SearcherRevKind::TwoWay { .. } => Strategy::TwoWay,
+            #[cfg(any(
+                all(target_arch = "x86_64", target_feature = "sse2"),
+                all(target_arch = "wasm32", target_feature = "simd128"),
+                target_arch = "aarch64"
+            ))]
+            SearcherRevKind::ShortNeedle { ref finder } => {
+                Strategy::ShortNeedle {
+                    implementation: finder.implementation(),
+                }
+            }
+        }
+    }
+
+    /// Writes the state of this searcher to `dst`. The needle given must be
+    /// the same as the needle that this searcher was initialized with.
+    ///
+    /// The format written here is read by `SearcherRev::read_from`.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn write_to(&self, _needle: &[u8], dst: &mut Vec<u8>) {
+        use crate::memmem::serialize::{write_factorization, write_u32};
+
+        let (tag, factorization) = match self.kind {
+            SearcherRevKind::Empty => (0, None),
+            SearcherRevKind::OneByte { .. } => (1, None),
+            SearcherRevKind::TwoWay { ref finder } => {
+                (2, Some(finder.factorization()))
+            }
+            #[cfg(any(
+                all(target_arch = "x86_64", target_feature = "sse2"),
+                all(target_arch = "wasm32", target_feature = "simd128"),
+                target_arch = "aarch64"
+            ))]
+            SearcherRevKind::ShortNeedle { .. } => (3, None),
+        };
+        dst.push(tag);
+        let (hash, hash_2pow) = self.rabinkarp.hash_parts();
+        write_u32(hash, dst);
+        write_u32(hash_2pow, dst);
+        write_factorization(factorization, dst);
+    }
+
+    /// Reads a searcher for the given needle that was written by
+    /// `SearcherRev::write_to`.
+    ///
+    /// Like `Searcher::read_from`, this uses the critical factorization that
+    /// was read after checking that it is consistent with the needle.
+    pub(crate) fn read_from(
+        needle: &[u8],
+        r: &mut Reader<'_>,
+    ) -> Result<SearcherRev, DeserializeError> {
+        let tag = r.u8()?;
+        let hash = (r.u32()?, r.u32()?);
+        let factorization = r.factorization()?;
+
+        let rabinkarp = rabinkarp::FinderRev::new(needle);
+        if rabinkarp.hash_parts() != hash {
+            return Err(DeserializeError::new("Rabin-Karp hash mismatch"));
+        }
+        let kind = match tag {
+            0 if needle.is_empty() => SearcherRevKind::Empty,
+            1 if needle.len() == 1 => {
+                SearcherRevKind::OneByte { needle: needle[0] }
+            }
+            2 if needle.len() >= 2 => {
+                let finder = twoway::FinderRev::from_factorization(
+                    needle,
+                    factorization,
+                )
+                .ok_or(DeserializeError::new(
+                    "invalid Two-Way factorization",
+                ))?;
+                SearcherRevKind::TwoWay { finder }
+            }
+            // Like the forward case, this picks the short needle algorithm
+            // again when it's available.
+            3 if (2..=4).contains(&needle.len()) => {
+                SearcherRev::new(needle).kind
+            }
+            0..=3 => {
+                return Err(DeserializeError::new(
+                    "strategy is invalid for needle length",
+                ))
+            }
+            _ => return Err(DeserializeError::new("unrecognized strategy")),
+        };
+        if tag != 2 && factorization != NO_FACTORIZATION {
+            return Err(DeserializeError::new(
+                "unexpected Two-Way factorization",
+            ));
+        }
+        Ok(SearcherRev { kind, rabinkarp })
+    }
+
     /// Searches the given haystack for the last occurrence of the given
     /// needle. The needle given should be the same as the needle that this
     /// finder was initialized with.
@@ -510,6 +1273,14 @@ impl SearcherRev {
                     finder.rfind(haystack, needle)
                 }
             }
+            #[cfg(any(
+                all(target_arch = "x86_64", target_feature = "sse2"),
+                all(target_arch = "wasm32", target_feature = "simd128"),
+                target_arch = "aarch64"
+            ))]
+            SearcherRevKind::ShortNeedle { ref finder } => {
+                finder.rfind(haystack)
+            }
         }
     }
 }
@@ -601,9 +1372,12 @@ impl PrefilterConfig {
 /// byte-at-a-time loop because it will read in `usize`-sized chunks at a
 /// time.)
 #[derive(Clone, Copy)]
-struct Prefilter {
+pub(crate) struct Prefilter {
     call: PrefilterKindFn,
     kind: PrefilterKind,
+    pair: Pair,
+    triple: Option<Triple>,
+    implementation: Implementation,
     rarest_byte: u8,
     rarest_offset: u8,
 }
@@ -617,12 +1391,6 @@ impl Prefilter {
         pair: Pair,
         needle: &[u8],
     ) -> Option<Prefilter> {
-        /// The maximum frequency rank permitted for the fallback prefilter.
-        /// If the rarest byte in the needle has a frequency rank above this
-        /// value, then no prefilter is used if the fallback prefilter would
-        /// otherwise be selected.
-        const MAX_FALLBACK_RANK: u8 = 250;
-
         trace!("building fallback prefilter");
         let rarest_offset = pair.index1();
         let rarest_byte = needle[usize::from(rarest_offset)];
@@ -634,7 +1402,39 @@ impl Prefilter {
                 crate::arch::all::packedpair::Finder::with_pair(needle, pair)?;
             let call = prefilter_kind_fallback;
             let kind = PrefilterKind { fallback: finder };
-            Some(Prefilter { call, kind, rarest_byte, rarest_offset })
+            let implementation = Implementation::Fallback;
+            Some(Prefilter {
+                call,
+                kind,
+                pair,
+                triple: None,
+                implementation,
+                rarest_byte,
+                rarest_offset,
+            })
+        }
+    }
+
+    /// Like `fallback`, but uses the default frequency ranking and can be
+    /// called in a `const` context.
+    #[inline]
+    const fn fallback_const(pair: Pair, needle: &[u8]) -> Option<Prefilter> {
+        let rarest_offset = pair.index1();
+        let rarest_byte = needle[rarest_offset as usize];
+        if RANK[rarest_byte as usize] > MAX_FALLBACK_RANK {
+            return None;
+        }
+        match crate::arch::all::packedpair::Finder::with_pair(needle, pair) {
+            None => None,
+            Some(finder) => Some(Prefilter {
+                call: prefilter_kind_fallback,
+                kind: PrefilterKind { fallback: finder },
+                pair,
+                triple: None,
+                implementation: Implementation::Fallback,
+                rarest_byte,
+                rarest_offset,
+            }),
         }
     }
 
@@ -648,6 +1448,9 @@ impl Prefilter {
         Prefilter {
             call: prefilter_kind_sse2,
             kind: PrefilterKind { sse2: finder },
+            pair: *finder.pair(),
+            triple: None,
+            implementation: Implementation::Sse2,
             rarest_byte,
             rarest_offset,
         }
@@ -663,6 +1466,9 @@ impl Prefilter {
         Prefilter {
             call: prefilter_kind_avx2,
             kind: PrefilterKind { avx2: finder },
+            pair: *finder.pair(),
+            triple: None,
+            implementation: Implementation::Avx2,
             rarest_byte,
             rarest_offset,
         }
@@ -678,6 +1484,9 @@ impl Prefilter {
         Prefilter {
             call: prefilter_kind_simd128,
             kind: PrefilterKind { simd128: finder },
+            pair: *finder.pair(),
+            triple: None,
+            implementation: Implementation::Simd128,
             rarest_byte,
             rarest_offset,
         }
@@ -693,11 +1502,209 @@ impl Prefilter {
         Prefilter {
             call: prefilter_kind_neon,
             kind: PrefilterKind { neon: finder },
+            pair: *finder.pair(),
+            triple: None,
+            implementation: Implementation::Neon,
             rarest_byte,
             rarest_offset,
         }
     }
 
+    /// Return a prefilter for the given pair, without regard for whether it is
+    /// believed to be effective.
+    ///
+    /// When `vector` is true, a vector algorithm is used if one is available.
+    #[inline]
+    pub(crate) fn with_pair(
+        needle: &[u8],
+        pair: Pair,
+        vector: bool,
+    ) -> Option<Prefilter> {
+        if vector {
+            #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+            {
+                if let Some(pp) = avx2_with_pair(needle, pair) {
+                    return Some(Prefilter::avx2(pp, needle));
+                } else if let Some(pp) = sse2_with_pair(needle, pair) {
+                    return Some(Prefilter::sse2(pp, needle));
+                }
+            }
+            #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+            {
+                if let Some(pp) = simd128_with_pair(needle, pair) {
+                    return Some(Prefilter::simd128(pp, needle));
+                }
+            }
+            #[cfg(target_arch = "aarch64")]
+            {
+                if let Some(pp) = neon_with_pair(needle, pair) {
+                    return Some(Prefilter::neon(pp, needle));
+                }
+            }
+        }
+        let finder =
+            crate::arch::all::packedpair::Finder::with_pair(needle, pair)?;
+        let rarest_offset = pair.index1();
+        Some(Prefilter {
+            call: prefilter_kind_fallback,
+            kind: PrefilterKind { fallback: finder },
+            pair,
+            triple: None,
+            implementation: Implementation::Fallback,
+            rarest_byte: needle[usize::from(rarest_offset)],
+            rarest_offset,
+        })
+    }
+
+    /// Return a prefilter that looks for the given triple of bytes with a
+    /// vector algorithm, without regard for whether it is believed to be
+    /// effective.
+    ///
+    /// If no vector algorithm is available, then `None` is returned.
+    #[inline]
+    pub(crate) fn with_triple(
+        needle: &[u8],
+        triple: Triple,
+    ) -> Option<Prefilter> {
+        #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+        {
+            if let Some(finder) = avx2_with_triple(needle, triple) {
+                let kind = PrefilterKind { avx2_triple: finder };
+                let call = prefilter_kind_avx2_triple;
+                let implementation = Implementation::Avx2;
+                return Some(Prefilter::vector_triple(
+                    call,
+                    kind,
+                    implementation,
+                    triple,
+                    needle,
+                ));
+            } else if let Some(finder) = sse2_with_triple(needle, triple) {
+                let kind = PrefilterKind { sse2_triple: finder };
+                let call = prefilter_kind_sse2_triple;
+                let implementation = Implementation::Sse2;
+                return Some(Prefilter::vector_triple(
+                    call,
+                    kind,
+                    implementation,
+                    triple,
+                    needle,
+                ));
+            }
+        }
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        {
+            if let Some(finder) = simd128_with_triple(needle, triple) {
+                let kind = PrefilterKind { simd128_triple: finder };
+                let call = prefilter_kind_simd128_triple;
+                let implementation = Implementation::Simd128;
+                return Some(Prefilter::vector_triple(
+                    call,
+                    kind,
+                    implementation,
+                    triple,
+                    needle,
+                ));
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            if let Some(finder) = neon_with_triple(needle, triple) {
+                let kind = PrefilterKind { neon_triple: finder };
+                let call = prefilter_kind_neon_triple;
+                let implementation = Implementation::Neon;
+                return Some(Prefilter::vector_triple(
+                    call,
+                    kind,
+                    implementation,
+                    triple,
+                    needle,
+                ));
+            }
+        }
+        #[cfg(not(any(
+            all(target_arch = "x86_64", target_feature = "sse2"),
+            all(target_arch = "wasm32", target_feature = "simd128"),
+            target_arch = "aarch64"
+        )))]
+        {
+            let _ = (needle, triple);
+        }
+        None
+    }
+
+    /// Return a prefilter that looks for a triple of bytes from the needle
+    /// with a vector algorithm, but only when the pair given is believed to
+    /// be too common for a prefilter that looks for it to be effective.
+    #[cfg(any(
+        all(target_arch = "x86_64", target_feature = "sse2"),
+        all(target_arch = "wasm32", target_feature = "simd128"),
+        target_arch = "aarch64"
+    ))]
+    #[inline]
+    fn triple_if_common<R: HeuristicFrequencyRank>(
+        ranker: R,
+        pair: Pair,
+        needle: &[u8],
+    ) -> Option<Prefilter> {
+        if !is_common_pair(&ranker, pair, needle) {
+            return None;
+        }
+        let triple = Triple::with_ranker(needle, &ranker)?;
+        Prefilter::with_triple(needle, triple)
+    }
+
+    /// Return a prefilter around a vector "packed triple" algorithm. The
+    /// function given must be paired with the field set in the union given.
+    #[cfg(any(
+        all(target_arch = "x86_64", target_feature = "sse2"),
+        all(target_arch = "wasm32", target_feature = "simd128"),
+        target_arch = "aarch64"
+    ))]
+    #[inline]
+    fn vector_triple(
+        call: PrefilterKindFn,
+        kind: PrefilterKind,
+        implementation: Implementation,
+        triple: Triple,
+        needle: &[u8],
+    ) -> Prefilter {
+        trace!("building {:?} triple prefilter", implementation);
+        let rarest_offset = triple.index1();
+        Prefilter {
+            call,
+            kind,
+            pair: triple.pair(),
+            triple: Some(triple),
+            implementation,
+            rarest_byte: needle[usize::from(rarest_offset)],
+            rarest_offset,
+        }
+    }
+
+    /// Returns the pair of bytes used by this prefilter.
+    #[inline]
+    pub(crate) fn pair(&self) -> &Pair {
+        &self.pair
+    }
+
+    /// Returns the implementation used by this prefilter.
+    #[inline]
+    pub(crate) fn implementation(&self) -> Implementation {
+        self.implementation
+    }
+
+    /// Returns the strategy of a Two-Way searcher accelerated by this
+    /// prefilter.
+    #[inline]
+    const fn strategy(&self) -> Strategy {
+        Strategy::TwoWayWithPrefilter {
+            pair: self.pair,
+            implementation: self.implementation,
+            triple: self.triple,
+        }
+    }
+
     /// Return a *candidate* position for a match.
     ///
     /// When this returns an offset, it implies that a match could begin at
@@ -712,7 +1719,7 @@ impl Prefilter {
     /// as quickly as possible before running a (likely) slower confirmation
     /// step.
     #[inline]
-    fn find(&self, haystack: &[u8]) -> Option<usize> {
+    pub(crate) fn find(&self, haystack: &[u8]) -> Option<usize> {
         // SAFETY: By construction, we've ensured that the function in
         // `self.call` is properly paired with the union used in `self.kind`.
         unsafe { (self.call)(self, haystack) }
@@ -739,12 +1746,20 @@ impl core::fmt::Debug for Prefilter {
         f.debug_struct("Prefilter")
             .field("call", &"<prefilter function>")
             .field("kind", &"<prefilter kind union>")
+            .field("pair", &self.pair)
+            .field("triple", &self.triple)
+            .field("implementation", &self.implementation)
             .field("rarest_byte", &self.rarest_byte)
             .field("rarest_offset", &self.rarest_offset)
             .finish()
     }
 }
 
+/// The maximum frequency rank permitted for the fallback prefilter. If the
+/// rarest byte in the needle has a frequency rank above this value, then no
+/// prefilter is used if the fallback prefilter would otherwise be selected.
+const MAX_FALLBACK_RANK: u8 = 250;
+
 /// A union indicating one of several possible prefilters that are in active
 /// use.
 ///
@@ -763,6 +1778,14 @@ union PrefilterKind {
     simd128: crate::arch::wasm32::simd128::packedpair::Finder,
     #[cfg(target_arch = "aarch64")]
     neon: crate::arch::aarch64::neon::packedpair::Finder,
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    sse2_triple: crate::arch::x86_64::sse2::packedpair::TripleFinder,
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    avx2_triple: crate::arch::x86_64::avx2::packedpair::TripleFinder,
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    simd128_triple: crate::arch::wasm32::simd128::packedpair::TripleFinder,
+    #[cfg(target_arch = "aarch64")]
+    neon_triple: crate::arch::aarch64::neon::packedpair::TripleFinder,
 }
 
 /// The type of a prefilter function.
@@ -863,6 +1886,86 @@ unsafe fn prefilter_kind_neon(
     }
 }
 
+/// Reads from the `sse2_triple` field of `PrefilterKind` to execute the
+/// x86_64 SSE2 "packed triple" prefilter.
+///
+/// # Safety
+///
+/// Callers must ensure that the `strat.kind.sse2_triple` union field is
+/// set.
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+unsafe fn prefilter_kind_sse2_triple(
+    strat: &Prefilter,
+    haystack: &[u8],
+) -> Option<usize> {
+    let finder = &strat.kind.sse2_triple;
+    if haystack.len() < finder.min_haystack_len() {
+        strat.find_simple(haystack)
+    } else {
+        finder.find_prefilter(haystack)
+    }
+}
+
+/// Reads from the `avx2_triple` field of `PrefilterKind` to execute the
+/// x86_64 AVX2 "packed triple" prefilter.
+///
+/// # Safety
+///
+/// Callers must ensure that the `strat.kind.avx2_triple` union field is
+/// set.
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+unsafe fn prefilter_kind_avx2_triple(
+    strat: &Prefilter,
+    haystack: &[u8],
+) -> Option<usize> {
+    let finder = &strat.kind.avx2_triple;
+    if haystack.len() < finder.min_haystack_len() {
+        strat.find_simple(haystack)
+    } else {
+        finder.find_prefilter(haystack)
+    }
+}
+
+/// Reads from the `simd128_triple` field of `PrefilterKind` to execute the
+/// wasm32 simd128 "packed triple" prefilter.
+///
+/// # Safety
+///
+/// Callers must ensure that the `strat.kind.simd128_triple` union field is
+/// set.
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+unsafe fn prefilter_kind_simd128_triple(
+    strat: &Prefilter,
+    haystack: &[u8],
+) -> Option<usize> {
+    let finder = &strat.kind.simd128_triple;
+    if haystack.len() < finder.min_haystack_len() {
+        strat.find_simple(haystack)
+    } else {
+        finder.find_prefilter(haystack)
+    }
+}
+
+/// Reads from the `neon_triple` field of `PrefilterKind` to execute the
+/// aarch64 neon "packed triple" prefilter.
+///
+/// # Safety
+///
+/// Callers must ensure that the `strat.kind.neon_triple` union field is
+/// set.
+#[cfg(target_arch = "aarch64")]
+unsafe fn prefilter_kind_neon_triple(
+    strat: &Prefilter,
+    haystack: &[u8],
+) -> Option<usize> {
+    let finder = &strat.kind.neon_triple;
+    if haystack.len() < finder.min_haystack_len() {
+        strat.find_simple(haystack)
+    } else {
+        finder.find_prefilter(haystack)
+    }
+}
+
 /// PrefilterState tracks state associated with the effectiveness of a
 /// prefilter. It is used to track how many bytes, on average, are skipped by
 /// the prefilter. If this average dips below a certain threshold over time,
@@ -907,7 +2010,7 @@ impl PrefilterState {
     /// Update this state with the number of bytes skipped on the last
     /// invocation of the prefilter.
     #[inline]
-    fn update(&mut self, skipped: usize) {
+    pub(crate) fn update(&mut self, skipped: usize) {
         self.skips = self.skips.saturating_add(1);
         // We need to do this dance since it's technically possible for
         // `skipped` to overflow a `u32`. (And we use a `u32` to reduce the
@@ -921,7 +2024,7 @@ impl PrefilterState {
     /// Return true if and only if this state indicates that a prefilter is
     /// still effective.
     #[inline]
-    fn is_effective(&mut self) -> bool {
+    pub(crate) fn is_effective(&mut self) -> bool {
         if self.is_inert() {
             return false;
         }
@@ -940,7 +2043,7 @@ impl PrefilterState {
     /// Returns true if the prefilter this state represents should no longer
     /// be used.
     #[inline]
-    fn is_inert(&self) -> bool {
+    pub(crate) fn is_inert(&self) -> bool {
         self.skips == 0
     }
 
@@ -960,6 +2063,8 @@ pub(crate) struct Pre<'a> {
     prestate: &'a mut PrefilterState,
     /// The actual prefilter.
     prestrat: &'a Prefilter,
+    /// Statistics to update, if they are being collected.
+    stats: Option<&'a mut SearchStats>,
 }
 
 impl<'a> Pre<'a> {
@@ -968,13 +2073,35 @@ impl<'a> Pre<'a> {
     pub(crate) fn find(&mut self, haystack: &[u8]) -> Option<usize> {
         let result = self.prestrat.find(haystack);
         self.prestate.update(result.unwrap_or(haystack.len()));
+        if let Some(ref mut stats) = self.stats {
+            stats.candidates += u64::from(result.is_some());
+        }
         result
     }
 
     /// Return true if and only if this prefilter should be used.
     #[inline]
     pub(crate) fn is_effective(&mut self) -> bool {
-        self.prestate.is_effective()
+        match self.stats {
+            None => self.prestate.is_effective(),
+            Some(ref mut stats) => {
+                let was_inert = self.prestate.is_inert();
+                let effective = self.prestate.is_effective();
+                if !was_inert && !effective {
+                    stats.prefilter_disabled += 1;
+                }
+                effective
+            }
+        }
+    }
+
+    /// Record that `len` bytes of the needle were compared with the haystack
+    /// while confirming a candidate.
+    #[inline]
+    pub(crate) fn verified(&mut self, len: usize) {
+        if let Some(ref mut stats) = self.stats {
+            stats.verified_bytes += len as u64;
+        }
     }
 }
 
@@ -1026,3 +2153,178 @@ fn do_packed_search(needle: &[u8]) -> bool {
     const MAX_LEN: usize = 32;
     MIN_LEN <= needle.len() && needle.len() <= MAX_LEN
 }
+
+/// Returns true if the pair of bytes chosen from the needle is believed to be
+/// too common for a prefilter that looks for it to be effective.
+///
+/// This happens in low-entropy haystacks, like hex dumps or DNA sequences,
+/// where every byte in the needle is common. A prefilter that only looks for
+/// a pair then reports so many candidates that it winds up being disabled.
+/// In that case, a prefilter that looks for a third byte is used instead.
+///
+/// This is only consulted for needles that are too long for the packed pair
+/// algorithm. Short needles of English text typically have a pair whose
+/// combined rank is just as high as one from a hex dump or a DNA sequence,
+/// since they have fewer bytes to pick a rare pair from. So using this to
+/// move short needles off the packed pair algorithm would slow down the
+/// common case, and short needles made up of common bytes keep using the
+/// packed pair algorithm instead. (Needles of at most `4` bytes are checked
+/// with `is_very_common_pair` to pick the short needle algorithm.)
+#[cfg(any(
+    all(target_arch = "x86_64", target_feature = "sse2"),
+    all(target_arch = "wasm32", target_feature = "simd128"),
+    target_arch = "aarch64"
+))]
+#[inline]
+fn is_common_pair<R: HeuristicFrequencyRank>(
+    ranker: R,
+    pair: Pair,
+    needle: &[u8],
+) -> bool {
+    /// The minimum combined rank of the pair for it to be considered common.
+    ///
+    /// With the default frequency ranking, this is reached by the rarest pair
+    /// of bytes of every needle made up only of lowercase hex digits, or only
+    /// of the letters `ACGT`. Most needles of English text have at least one
+    /// byte whose rank is low enough to stay below it.
+    const MIN_COMMON_RANK: u16 = 320;
+
+    pair_rank(ranker, pair, needle) >= MIN_COMMON_RANK
+}
+
+/// Returns true if the pair of bytes chosen from a needle of `2`, `3` or `4`
+/// bytes is believed to be common enough for the short needle algorithm to
+/// be faster than the packed pair algorithm.
+///
+/// The short needle algorithm compares every byte of the needle at every
+/// position, so it doesn't depend on how common the needle's bytes are. The
+/// packed pair algorithm only compares two bytes at every position, but has
+/// to confirm every candidate it finds. So it is faster unless the pair
+/// occurs very frequently, like the most common letters of English text.
+/// In particular, needles found in code like `\r\n`, `::`, `=>` or `impl`
+/// stay on the packed pair algorithm. (See the `memmem/shortneedle`
+/// benchmarks.)
+#[cfg(any(
+    all(target_arch = "x86_64", target_feature = "sse2"),
+    all(target_arch = "wasm32", target_feature = "simd128"),
+    target_arch = "aarch64"
+))]
+#[inline]
+fn is_very_common_pair<R: HeuristicFrequencyRank>(
+    ranker: R,
+    pair: Pair,
+    needle: &[u8],
+) -> bool {
+    /// The minimum combined rank of the pair for the short needle algorithm
+    /// to be used. With the default frequency ranking, this is reached by
+    /// `the` or `e `, but not by `\r\n` or `impl`.
+    const MIN_VERY_COMMON_RANK: u16 = 480;
+
+    pair_rank(ranker, pair, needle) >= MIN_VERY_COMMON_RANK
+}
+
+/// Returns the combined rank of the bytes at the offsets of the pair given.
+#[cfg(any(
+    all(target_arch = "x86_64", target_feature = "sse2"),
+    all(target_arch = "wasm32", target_feature = "simd128"),
+    target_arch = "aarch64"
+))]
+#[inline]
+fn pair_rank<R: HeuristicFrequencyRank>(
+    ranker: R,
+    pair: Pair,
+    needle: &[u8],
+) -> u16 {
+    let rank1 = ranker.rank(needle[usize::from(pair.index1())]);
+    let rank2 = ranker.rank(needle[usize::from(pair.index2())]);
+    u16::from(rank1) + u16::from(rank2)
+}
+
+// The functions below build the vector implementations of the packed pair
+// and packed triple algorithms, but only when the current dispatch cap permits
+// them. (See `crate::set_dispatch_cap`.) The cap is only consulted when a
+// searcher is built, so it has no impact on search times.
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+#[inline]
+fn avx2_with_pair(needle: &[u8], pair: Pair) -> Option<avx2::Finder> {
+    if !crate::dispatch::is_permitted(Implementation::Avx2) {
+        return None;
+    }
+    avx2::Finder::with_pair(needle, pair)
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+#[inline]
+fn sse2_with_pair(needle: &[u8], pair: Pair) -> Option<sse2::Finder> {
+    if !crate::dispatch::is_permitted(Implementation::Sse2) {
+        return None;
+    }
+    sse2::Finder::with_pair(needle, pair)
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+#[inline]
+fn simd128_with_pair(needle: &[u8], pair: Pair) -> Option<simd128::Finder> {
+    if !crate::dispatch::is_permitted(Implementation::Simd128) {
+        return None;
+    }
+    simd128::Finder::with_pair(needle, pair)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline]
+fn neon_with_pair(needle: &[u8], pair: Pair) -> Option<neon::Finder> {
+    if !crate::dispatch::is_permitted(Implementation::Neon) {
+        return None;
+    }
+    neon::Finder::with_pair(needle, pair)
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+#[inline]
+fn avx2_with_triple(
+    needle: &[u8],
+    triple: Triple,
+) -> Option<avx2::TripleFinder> {
+    if !crate::dispatch::is_permitted(Implementation::Avx2) {
+        return None;
+    }
+    avx2::TripleFinder::with_triple(needle, triple)
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+#[inline]
+fn sse2_with_triple(
+    needle: &[u8],
+    triple: Triple,
+) -> Option<sse2::TripleFinder> {
+    if !crate::dispatch::is_permitted(Implementation::Sse2) {
+        return None;
+    }
+    sse2::TripleFinder::with_triple(needle, triple)
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+#[inline]
+fn simd128_with_triple(
+    needle: &[u8],
+    triple: Triple,
+) -> Option<simd128::TripleFinder> {
+    if !crate::dispatch::is_permitted(Implementation::Simd128) {
+        return None;
+    }
+    simd128::TripleFinder::with_triple(needle, triple)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline]
+fn neon_with_triple(
+    needle: &[u8],
+    triple: Triple,
+) -> Option<neon::TripleFinder> {
+    if !crate::dispatch::is_permitted(Implementation::Neon) {
+        return None;
+    }
+    neon::TripleFinder::with_triple(needle, triple)
+}
@@ -0,0 +1,314 @@
+use core::ops::Deref;
+
+use crate::{
+    arch::all::is_equal,
+    memmem::{is_word_byte, searcher::PrefilterState, Finder},
+    SegmentPos,
+};
+
+/// An iterator over non-overlapping substring matches in a haystack made up
+/// of a sequence of segments.
+///
+/// Matches may cross segment boundaries. Each match is reported by the
+/// position at which it begins.
+///
+/// This iterator is created by [`Finder::find_segmented_iter`].
+///
+/// `'s` is the lifetime of the segments, `'n` is the lifetime of the needle
+/// and `S` is the type of each segment.
+#[derive(Clone, Debug)]
+pub struct FindSegmentedIter<'s, 'n, S> {
+    segments: &'s [S],
+    prestate: PrefilterState,
+    finder: Finder<'n>,
+    /// The index of the segment currently being searched.
+    segment: usize,
+    /// The offset into the current segment at which to resume searching.
+    /// This may exceed the length of the current segment, in which case the
+    /// position is normalized before the next search.
+    at: usize,
+    /// The offset of the beginning of the current segment in the logical
+    /// haystack.
+    base: usize,
+    /// Set when an empty needle has matched at the end of the logical
+    /// haystack.
+    done: bool,
+}
+
+impl<'s, 'n, S: Deref<Target = [u8]>> FindSegmentedIter<'s, 'n, S> {
+    #[inline]
+    pub(crate) fn new(
+        segments: &'s [S],
+        finder: Finder<'n>,
+    ) -> FindSegmentedIter<'s, 'n, S> {
+        FindSegmentedIter {
+            segments,
+            prestate: PrefilterState::new(),
+            finder,
+            segment: 0,
+            at: 0,
+            base: 0,
+            done: false,
+        }
+    }
+
+    /// Advance the current segment until `self.at` falls inside of it. If
+    /// there is no such segment, then this stops at `self.segments.len()`.
+    #[inline]
+    fn normalize(&mut self) {
+        while let Some(segment) = self.segments.get(self.segment) {
+            if self.at < segment.len() {
+                break;
+            }
+            self.at -= segment.len();
+            self.base += segment.len();
+            self.segment += 1;
+        }
+    }
+
+    #[inline]
+    fn pos(&self, segment: usize, segment_offset: usize) -> SegmentPos {
+        SegmentPos::new(self.base + segment_offset, segment, segment_offset)
+    }
+
+    /// Returns true if and only if the needle occurs at the given position,
+    /// possibly crossing into subsequent segments.
+    fn is_match_at(&self, segment: usize, at: usize) -> bool {
+        let needle = self.finder.needle();
+        let (mut rest, mut k, mut p) = (needle, segment, at);
+        while !rest.is_empty() {
+            let avail = match self.segments.get(k) {
+                None => return false,
+                Some(segment) => &segment[p..],
+            };
+            let len = avail.len().min(rest.len());
+            if !is_equal(&avail[..len], &rest[..len]) {
+                return false;
+            }
+            rest = &rest[len..];
+            k += 1;
+            p = 0;
+        }
+        !self.finder.whole_word || self.is_word_match(segment, at)
+    }
+
+    /// Returns true if and only if a match of the needle at the given
+    /// position begins and ends on an ASCII word boundary. The bytes on
+    /// either side of the match may be in other segments.
+    fn is_word_match(&self, segment: usize, at: usize) -> bool {
+        let needle = self.finder.needle();
+        let before = byte_before(self.segments, segment, at);
+        let after = byte_at(self.segments, segment, at + needle.len());
+        match (needle.first(), needle.last()) {
+            (Some(&first), Some(&last)) => {
+                is_word_byte(before) != is_word_byte(Some(first))
+                    && is_word_byte(Some(last)) != is_word_byte(after)
+            }
+            _ => is_word_byte(before) != is_word_byte(after),
+        }
+    }
+
+    /// Returns the first match of a non-empty needle that begins at or after
+    /// the current position.
+    fn next_nonempty(&mut self) -> Option<SegmentPos> {
+        let needle = self.finder.needle();
+        while let Some(segment) = self.segments.get(self.segment) {
+            // First look for a match entirely within this segment. Any such
+            // match always begins before any match crossing into the next
+            // segment.
+            let mut at = self.at;
+            while let Some(i) = self.finder.searcher.find(
+                &mut self.prestate,
+                &segment[at..],
+                needle,
+            ) {
+                let start = at + i;
+                if !self.finder.whole_word
+                    || self.is_word_match(self.segment, start)
+                {
+                    self.at = start + needle.len();
+                    return Some(self.pos(self.segment, start));
+                }
+                at = start + 1;
+            }
+            // Otherwise, look for a match beginning in the last
+            // `needle.len() - 1` bytes of this segment.
+            let mut at =
+                self.at.max(segment.len().saturating_sub(needle.len() - 1));
+            while let Some(i) = crate::memchr(needle[0], &segment[at..]) {
+                let start = at + i;
+                if self.is_match_at(self.segment, start) {
+                    self.at = start + needle.len();
+                    return Some(self.pos(self.segment, start));
+                }
+                at = start + 1;
+            }
+            self.base += segment.len();
+            self.segment += 1;
+            self.at = 0;
+        }
+        None
+    }
+
+    /// Returns the next match of the empty needle. This matches at every
+    /// position in the logical haystack, including its end.
+    fn next_empty(&mut self) -> Option<SegmentPos> {
+        while !self.done {
+            let pos = self.pos(self.segment, self.at);
+            if self.segment == self.segments.len() {
+                self.done = true;
+            } else {
+                self.at += 1;
+                self.normalize();
+            }
+            if !self.finder.whole_word
+                || self.is_word_match(pos.segment(), pos.segment_offset())
+            {
+                return Some(pos);
+            }
+        }
+        None
+    }
+}
+
+impl<'s, 'n, S: Deref<Target = [u8]>> Iterator
+    for FindSegmentedIter<'s, 'n, S>
+{
+    type Item = SegmentPos;
+
+    fn next(&mut self) -> Option<SegmentPos> {
+        self.normalize();
+        if self.finder.needle().is_empty() {
+            self.next_empty()
+        } else {
+            self.next_nonempty()
+        }
+    }
+}
+
+/// Returns the byte immediately preceding the given position, which may be
+/// in an earlier segment.
+fn byte_before<S: Deref<Target = [u8]>>(
+    segments: &[S],
+    segment: usize,
+    at: usize,
+) -> Option<u8> {
+    if at > 0 {
+        return Some(segments[segment][at - 1]);
+    }
+    segments[..segment.min(segments.len())]
+        .iter()
+        .rev()
+        .find_map(|segment| segment.last().copied())
+}
+
+/// Returns the byte at the given position, which may be past the end of the
+/// given segment and thus in a later segment.
+fn byte_at<S: Deref<Target = [u8]>>(
+    segments: &[S],
+    segment: usize,
+    at: usize,
+) -> Option<u8> {
+    let (mut k, mut p) = (segment, at);
+    while let Some(segment) = segments.get(k) {
+        if p < segment.len() {
+            return Some(segment[p]);
+        }
+        p -= segment.len();
+        k += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::memmem::{FindIter, FinderBuilder};
+
+    /// Splits the given haystack into segments at the given cut points.
+    fn split<'h>(haystack: &'h [u8], cuts: &[usize]) -> Vec<&'h [u8]> {
+        let mut segments = Vec::new();
+        let mut last = 0;
+        for &cut in cuts {
+            let cut = cut.clamp(last, haystack.len());
+            segments.push(&haystack[last..cut]);
+            last = cut;
+        }
+        segments.push(&haystack[last..]);
+        segments
+    }
+
+    fn check(builder: &FinderBuilder, haystack: &[u8], needle: &[u8]) {
+        let finder = builder.build_forward(needle);
+        let expected: Vec<usize> =
+            FindIter::new(haystack, finder.as_ref()).collect();
+        let cutsets: &[&[usize]] = &[
+            &[],
+            &[0],
+            &[1],
+            &[1, 1, 2],
+            &[2, 3, 5, 8, 13],
+            &[0, 0, 100, 100],
+            &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12],
+        ];
+        for cuts in cutsets {
+            let segments = split(haystack, cuts);
+            let got: Vec<usize> = finder
+                .find_segmented_iter(&segments)
+                .map(|pos| {
+                    let segment = segments.get(pos.segment());
+                    match segment.and_then(|s| s.get(pos.segment_offset())) {
+                        Some(&b) => assert_eq!(haystack[pos.offset()], b),
+                        None => assert_eq!(pos.offset(), haystack.len()),
+                    }
+                    pos.offset()
+                })
+                .collect();
+            assert_eq!(
+                expected, got,
+                "haystack: {:?}, needle: {:?}, cuts: {:?}",
+                haystack, needle, cuts,
+            );
+        }
+    }
+
+    #[test]
+    fn segmented() {
+        let haystacks: &[&[u8]] = &[
+            b"",
+            b"a",
+            b"abc",
+            b"aaaaaaaa",
+            b"abcabcabcabc",
+            b"the quick brown fox jumps over the lazy dog",
+            b"foo_foo foo-foo xfoo",
+        ];
+        let needles: &[&[u8]] = &[
+            b"",
+            b"a",
+            b"aa",
+            b"aaa",
+            b"abc",
+            b"cab",
+            b"bcabca",
+            b"the",
+            b"fox jumps",
+            b"foo",
+            b"o",
+            b"zzz",
+        ];
+        let mut builder = FinderBuilder::new();
+        for &haystack in haystacks {
+            for &needle in needles {
+                check(&builder, haystack, needle);
+            }
+        }
+        builder.whole_word(true);
+        for &haystack in haystacks {
+            for &needle in needles {
+                check(&builder, haystack, needle);
+            }
+        }
+    }
+}
@@ -0,0 +1,488 @@
+use crate::{
+    arch::all::{packedpair::HeuristicFrequencyRank, twoway::Factorization},
+    cow::CowBytes,
+    memmem::{
+        searcher::{Searcher, SearcherRev},
+        Finder, FinderRev,
+    },
+};
+
+/// The label at the beginning of every serialized `Finder`.
+const FINDER_LABEL: &[u8; 8] = b"memmem-f";
+
+/// The label at the beginning of every serialized `FinderRev`.
+const FINDER_REV_LABEL: &[u8; 8] = b"memmem-r";
+
+/// The factorization written for searchers that don't use Two-Way.
+pub(crate) const NO_FACTORIZATION: Factorization =
+    Factorization { critical_pos: 0, small_period: false, shift: 0 };
+
+/// The version of the serialization format. This should be incremented
+/// whenever the format changes.
+///
+/// Version 2 added the short needle strategy, version 3 added the triple
+/// prefilter and version 4 added a checksum. Finders serialized with earlier
+/// versions are rejected, since they can't be checked for corruption.
+const VERSION: u32 = 4;
+
+/// An error that occurs when deserializing a [`Finder`] or [`FinderRev`].
+///
+/// This error is returned when the bytes given aren't a valid serialization
+/// of a finder. For example, when they were produced by an incompatible
+/// version of this crate, or when they are truncated or corrupt.
+#[derive(Clone, Debug)]
+pub struct DeserializeError(&'static str);
+
+impl DeserializeError {
+    pub(crate) fn new(msg: &'static str) -> DeserializeError {
+        DeserializeError(msg)
+    }
+}
+
+impl core::fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "invalid serialized finder: {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DeserializeError {}
+
+/// Writes the given forward finder to `dst`.
+#[cfg(feature = "alloc")]
+pub(crate) fn write_finder(
+    finder: &Finder<'_>,
+    dst: &mut alloc::vec::Vec<u8>,
+) {
+    let (start, needle) = (dst.len(), finder.needle());
+    write_header(FINDER_LABEL, needle, finder.whole_word, dst);
+    finder.searcher.write_to(needle, dst);
+    write_u32(checksum(&dst[start..]), dst);
+}
+
+/// Writes the given reverse finder to `dst`.
+#[cfg(feature = "alloc")]
+pub(crate) fn write_finder_rev(
+    finder: &FinderRev<'_>,
+    dst: &mut alloc::vec::Vec<u8>,
+) {
+    let (start, needle) = (dst.len(), finder.needle());
+    write_header(FINDER_REV_LABEL, needle, finder.whole_word, dst);
+    finder.searcher.write_to(needle, dst);
+    write_u32(checksum(&dst[start..]), dst);
+}
+
+/// Reads a forward finder from the beginning of `bytes`, and returns it
+/// along with the number of bytes read. The ranker given must be the one
+/// that the finder was built with.
+pub(crate) fn read_finder<R: HeuristicFrequencyRank>(
+    ranker: R,
+    bytes: &[u8],
+) -> Result<(Finder<'_>, usize), DeserializeError> {
+    let mut r = Reader::new(bytes);
+    let (needle, whole_word) = r.header(FINDER_LABEL)?;
+    let searcher = Searcher::read_from(ranker, needle, &mut r)?;
+    r.checksum()?;
+    let needle = CowBytes::new(needle);
+    Ok((Finder { needle, searcher, whole_word }, r.pos))
+}
+
+/// Reads a reverse finder from the beginning of `bytes`, and returns it
+/// along with the number of bytes read.
+pub(crate) fn read_finder_rev(
+    bytes: &[u8],
+) -> Result<(FinderRev<'_>, usize), DeserializeError> {
+    let mut r = Reader::new(bytes);
+    let (needle, whole_word) = r.header(FINDER_REV_LABEL)?;
+    let searcher = SearcherRev::read_from(needle, &mut r)?;
+    r.checksum()?;
+    let needle = CowBytes::new(needle);
+    Ok((FinderRev { needle, searcher, whole_word }, r.pos))
+}
+
+/// Writes the parts of a serialized finder that precede its searcher.
+#[cfg(feature = "alloc")]
+fn write_header(
+    label: &[u8; 8],
+    needle: &[u8],
+    whole_word: bool,
+    dst: &mut alloc::vec::Vec<u8>,
+) {
+    dst.extend_from_slice(label);
+    write_u32(VERSION, dst);
+    write_u64(needle.len(), dst);
+    dst.extend_from_slice(needle);
+    dst.push(u8::from(whole_word));
+}
+
+/// Writes a factorization, or a placeholder when there isn't one.
+#[cfg(feature = "alloc")]
+pub(crate) fn write_factorization(
+    factorization: Option<Factorization>,
+    dst: &mut alloc::vec::Vec<u8>,
+) {
+    let f = factorization.unwrap_or(NO_FACTORIZATION);
+    write_u64(f.critical_pos, dst);
+    dst.push(u8::from(f.small_period));
+    write_u64(f.shift, dst);
+}
+
+/// Computes the checksum written at the end of a serialized finder.
+///
+/// This is 32-bit FNV-1a. Since each step is a bijection on the hash, any
+/// single byte that differs is guaranteed to change it.
+fn checksum(bytes: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811C_9DC5;
+    for &b in bytes {
+        hash ^= u32::from(b);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+/// Writes a `u32` in little endian format.
+#[cfg(feature = "alloc")]
+pub(crate) fn write_u32(n: u32, dst: &mut alloc::vec::Vec<u8>) {
+    dst.extend_from_slice(&n.to_le_bytes());
+}
+
+/// Writes a `usize` as a `u64` in little endian format.
+#[cfg(feature = "alloc")]
+fn write_u64(n: usize, dst: &mut alloc::vec::Vec<u8>) {
+    // OK since we don't support platforms with pointers bigger than 64 bits.
+    dst.extend_from_slice(&(n as u64).to_le_bytes());
+}
+
+/// A cursor for reading the fields of a serialized finder.
+#[derive(Debug)]
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes, pos: 0 }
+    }
+
+    /// Reads the parts of a serialized finder that precede its searcher,
+    /// and returns the needle and whether only whole words are matched.
+    fn header(
+        &mut self,
+        label: &[u8; 8],
+    ) -> Result<(&'a [u8], bool), DeserializeError> {
+        if self.bytes(label.len())? != label {
+            return Err(DeserializeError::new("unrecognized label"));
+        }
+        if self.u32()? != VERSION {
+            return Err(DeserializeError::new("unsupported version"));
+        }
+        let len = self.usize()?;
+        let needle = self.bytes(len)?;
+        let whole_word = self.bool()?;
+        Ok((needle, whole_word))
+    }
+
+    /// Reads the checksum that follows a serialized finder, and checks it
+    /// against everything that was read before it.
+    fn checksum(&mut self) -> Result<(), DeserializeError> {
+        let expected = checksum(&self.bytes[..self.pos]);
+        if self.u32()? != expected {
+            return Err(DeserializeError::new("checksum mismatch"));
+        }
+        Ok(())
+    }
+
+    /// Reads a factorization. Whether it can be used with a needle is
+    /// checked by the searcher reading it.
+    pub(crate) fn factorization(
+        &mut self,
+    ) -> Result<Factorization, DeserializeError> {
+        let critical_pos = self.usize()?;
+        let small_period = self.bool()?;
+        let shift = self.usize()?;
+        Ok(Factorization { critical_pos, small_period, shift })
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], DeserializeError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or(DeserializeError::new("unexpected end of input"))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, DeserializeError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, DeserializeError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DeserializeError::new("invalid boolean")),
+        }
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, DeserializeError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn usize(&mut self) -> Result<usize, DeserializeError> {
+        let bytes = self.bytes(8)?;
+        usize::try_from(u64::from_le_bytes(bytes.try_into().unwrap()))
+            .map_err(|_| DeserializeError::new("integer too large"))
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::memmem::{Finder, FinderBuilder, FinderRev, Prefilter};
+
+    #[test]
+    fn roundtrip_forward() {
+        for prefilter in [Prefilter::Auto, Prefilter::None] {
+            crate::tests::substring::Runner::new()
+                .fwd(move |h, n| {
+                    let finder = FinderBuilder::new()
+                        .prefilter(prefilter)
+                        .build_forward(n);
+                    let bytes = finder.to_bytes();
+                    let (got, nread) = Finder::from_bytes(&bytes).unwrap();
+                    assert_eq!(bytes.len(), nread);
+                    assert_eq!(finder.strategy(), got.strategy());
+                    Some(got.find(h))
+                })
+                .run();
+        }
+    }
+
+    #[test]
+    fn roundtrip_reverse() {
+        crate::tests::substring::Runner::new()
+            .rev(|h, n| {
+                let bytes = FinderRev::new(n).to_bytes();
+                let (finder, nread) = FinderRev::from_bytes(&bytes).unwrap();
+                assert_eq!(bytes.len(), nread);
+                Some(finder.rfind(h))
+            })
+            .run();
+    }
+
+    #[test]
+    fn roundtrip_triple() {
+        let needle = b"0123456789abcdef0123456789abcdef0123456789";
+        let finder = Finder::new(needle);
+        let bytes = finder.to_bytes();
+        let (got, _) = Finder::from_bytes(&bytes).unwrap();
+        assert_eq!(finder.strategy(), got.strategy());
+        let haystack = [&b"fedcba9876543210"[..], needle].concat();
+        assert_eq!(Some(16), got.find(&haystack));
+    }
+
+    #[test]
+    fn roundtrip_whole_word() {
+        let finder =
+            FinderBuilder::new().whole_word(true).build_forward("foo");
+        let bytes = finder.to_bytes();
+        let (finder, _) = Finder::from_bytes(&bytes).unwrap();
+        assert_eq!(Some(8), finder.find(b"foobar, foo"));
+    }
+
+    #[test]
+    fn roundtrip_ranker() {
+        use crate::arch::all::packedpair::HeuristicFrequencyRank;
+
+        struct Reverse;
+
+        impl HeuristicFrequencyRank for Reverse {
+            fn rank(&self, byte: u8) -> u8 {
+                u8::MAX - byte
+            }
+        }
+
+        let needle = b"the quick brown fox jumps over the lazy dog";
+        let finder =
+            FinderBuilder::new().build_forward_with_ranker(Reverse, needle);
+        let bytes = finder.to_bytes();
+        let (got, _) =
+            Finder::from_bytes_with_ranker(Reverse, &bytes).unwrap();
+        assert_eq!(finder.strategy(), got.strategy());
+        assert_eq!(Some(4), got.find(&[&b"fox "[..], needle].concat()));
+        // The rare bytes are read instead of being chosen again, so they
+        // are the same even when a different ranker is given.
+        let (got, _) = Finder::from_bytes(&bytes).unwrap();
+        assert_eq!(finder.strategy(), got.strategy());
+    }
+
+    #[test]
+    fn corrupt() {
+        let needles: &[&[u8]] = &[
+            b"",
+            b"a",
+            b"ab",
+            b"foobar",
+            b"abababab",
+            b"zzzzzzzzzzzzzzzzzzz",
+            b"the quick brown fox jumps over the lazy dog",
+            b"0123456789abcdef0123456789abcdef0123456789",
+        ];
+        for &needle in needles {
+            // The searcher's state follows the label, version, needle
+            // length, needle and whole word flag. Its first byte is the
+            // strategy, which may legitimately be changed to a similar one.
+            let start = 8 + 4 + 8 + needle.len() + 1 + 1;
+            for prefilter in [Prefilter::Auto, Prefilter::None] {
+                let fwd = FinderBuilder::new()
+                    .prefilter(prefilter)
+                    .build_forward(needle)
+                    .to_bytes();
+                for i in start..fwd.len() {
+                    for bit in 0..8 {
+                        let mut bytes = fwd.clone();
+                        bytes[i] ^= 1 << bit;
+                        assert!(
+                            Finder::from_bytes(&bytes).is_err(),
+                            "needle: {:?}, byte: {}, bit: {}",
+                            needle,
+                            i,
+                            bit,
+                        );
+                    }
+                }
+            }
+            let rev = FinderRev::new(needle).to_bytes();
+            for i in start..rev.len() {
+                for bit in 0..8 {
+                    let mut bytes = rev.clone();
+                    bytes[i] ^= 1 << bit;
+                    assert!(
+                        FinderRev::from_bytes(&bytes).is_err(),
+                        "needle: {:?}, byte: {}, bit: {}",
+                        needle,
+                        i,
+                        bit,
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn crafted() {
+        // Serialized finders that were changed to use Two-Way with the
+        // factorization given, and whose checksum was updated to match.
+        // These may report incorrect matches, but searching with them must
+        // not panic or fail to terminate.
+        fn craft(
+            bytes: &[u8],
+            needle: &[u8],
+            forward: bool,
+            factorization: (usize, bool, usize),
+        ) -> Vec<u8> {
+            let (critical_pos, small_period, shift) = factorization;
+            let tag = 8 + 4 + 8 + needle.len() + 1;
+            // The Rabin-Karp hash is the only part of the searcher that is
+            // kept. It follows the strategy and, for forward searchers, the
+            // rare bytes.
+            let hash = if forward {
+                tag + 3 + usize::from(bytes[tag] == 7)
+            } else {
+                tag + 1
+            };
+            let hash = &bytes[hash..hash + 8];
+            let mut crafted = bytes[..tag].to_vec();
+            crafted.push(2);
+            if forward {
+                // A forward searcher using Two-Way has no rare bytes.
+                crafted.extend_from_slice(&[0, 0]);
+            }
+            crafted.extend_from_slice(hash);
+            crafted.extend_from_slice(&(critical_pos as u64).to_le_bytes());
+            crafted.push(u8::from(small_period));
+            crafted.extend_from_slice(&(shift as u64).to_le_bytes());
+            let sum = super::checksum(&crafted);
+            crafted.extend_from_slice(&sum.to_le_bytes());
+            crafted
+        }
+
+        let needles: &[&[u8]] = &[b"ab", b"aab", b"foobar", b"abababab"];
+        let haystack = b"foobar abababab aabaab ab abab foo foofoobar";
+        for &needle in needles {
+            let fwd = Finder::new(needle).to_bytes();
+            let rev = FinderRev::new(needle).to_bytes();
+            for critical_pos in 0..=needle.len() + 1 {
+                for small_period in [false, true] {
+                    for shift in 0..=needle.len() + 1 {
+                        let f = (critical_pos, small_period, shift);
+                        let bytes = craft(&fwd, needle, true, f);
+                        if let Ok((finder, _)) = Finder::from_bytes(&bytes) {
+                            let _: Vec<usize> =
+                                finder.find_iter(haystack).collect();
+                        }
+                        let bytes = craft(&rev, needle, false, f);
+                        if let Ok((finder, _)) = FinderRev::from_bytes(&bytes)
+                        {
+                            let _: Vec<usize> =
+                                finder.rfind_iter(haystack).collect();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let needles: &[&[u8]] = &[
+            b"",
+            b"a",
+            b"ab",
+            b"foobar",
+            b"abababab",
+            b"zzzzzzzzzzzzzzzzzzz",
+            b"0123456789abcdef0123456789abcdef0123456789",
+        ];
+        let haystack = b"foobar abababab zzzzzzzzzzzzzzzzzzzz abab foo";
+        for &needle in needles {
+            let fwd = Finder::new(needle).to_bytes();
+            let rev = FinderRev::new(needle).to_bytes();
+            assert!(Finder::from_bytes(&rev).is_err());
+            assert!(FinderRev::from_bytes(&fwd).is_err());
+            for len in 0..fwd.len() {
+                assert!(Finder::from_bytes(&fwd[..len]).is_err());
+            }
+            for len in 0..rev.len() {
+                assert!(FinderRev::from_bytes(&rev[..len]).is_err());
+            }
+            // Corrupting any byte either results in an error, or in a finder
+            // that can be used to search without panicking.
+            for i in 0..fwd.len() {
+                for bit in 0..8 {
+                    let mut bytes = fwd.clone();
+                    bytes[i] ^= 1 << bit;
+                    if let Ok((finder, _)) = Finder::from_bytes(&bytes) {
+                        let _: Vec<usize> =
+                            finder.find_iter(haystack).collect();
+                    }
+                }
+            }
+            for i in 0..rev.len() {
+                for bit in 0..8 {
+                    let mut bytes = rev.clone();
+                    bytes[i] ^= 1 << bit;
+                    if let Ok((finder, _)) = FinderRev::from_bytes(&bytes) {
+                        let _: Vec<usize> =
+                            finder.rfind_iter(haystack).collect();
+                    }
+                }
+            }
+        }
+    }
+}
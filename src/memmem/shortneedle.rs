@@ -0,0 +1,246 @@
+use crate::{dispatch::is_permitted, Implementation};
+
+#[cfg(target_arch = "aarch64")]
+use crate::arch::aarch64::neon::shortneedle as neon;
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+use crate::arch::wasm32::simd128::shortneedle as simd128;
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+use crate::arch::x86_64::{
+    avx2::shortneedle as avx2, sse2::shortneedle as sse2,
+};
+
+/// A searcher for needles of `2`, `3` or `4` bytes that uses the best vector
+/// implementation of the "short needle" algorithm available.
+///
+/// This is chosen by the "meta" substring searchers for needles this short,
+/// since every position reported by the algorithm is a match. That is,
+/// unlike the "packed pair" algorithm, there is no verification step.
+///
+/// Each vector implementation is a distinct type for every needle length,
+/// so we use the same union and function pointer approach as `Searcher`. The
+/// functions are always paired with the field of the union that is set.
+#[derive(Clone, Copy)]
+pub(crate) struct ShortNeedle {
+    find: ShortNeedleFn,
+    rfind: ShortNeedleFn,
+    kind: ShortNeedleKind,
+    implementation: Implementation,
+}
+
+/// The type of a search function for a short needle.
+///
+/// # Safety
+///
+/// When using a function of this type, callers must ensure that the correct
+/// function is paired with the value populated in `ShortNeedleKind` union.
+type ShortNeedleFn =
+    unsafe fn(kind: &ShortNeedleKind, haystack: &[u8]) -> Option<usize>;
+
+/// A union of the "short needle" finders for every vector implementation and
+/// needle length.
+///
+/// This union should only be read by the functions paired with it in
+/// `ShortNeedle`.
+#[derive(Clone, Copy)]
+union ShortNeedleKind {
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    sse2_2: sse2::Finder<2>,
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    sse2_3: sse2::Finder<3>,
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    sse2_4: sse2::Finder<4>,
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    avx2_2: avx2::Finder<2>,
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    avx2_3: avx2::Finder<3>,
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    avx2_4: avx2::Finder<4>,
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    simd128_2: simd128::Finder<2>,
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    simd128_3: simd128::Finder<3>,
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    simd128_4: simd128::Finder<4>,
+    #[cfg(target_arch = "aarch64")]
+    neon_2: neon::Finder<2>,
+    #[cfg(target_arch = "aarch64")]
+    neon_3: neon::Finder<3>,
+    #[cfg(target_arch = "aarch64")]
+    neon_4: neon::Finder<4>,
+}
+
+/// Builds a `ShortNeedle` for the given needle with the given vector
+/// implementation, by choosing the union field for the needle's length.
+///
+/// This evaluates to `None` when the implementation isn't available or when
+/// the needle has an unsupported length.
+macro_rules! build {
+    ($needle:expr, $imp:expr, $module:ident {
+        $($len:literal => $field:ident,)+
+    }) => {{
+        /// Defines the search functions that read the given field.
+        macro_rules! fns {
+            ($f:ident) => {{
+                unsafe fn find(
+                    kind: &ShortNeedleKind,
+                    haystack: &[u8],
+                ) -> Option<usize> {
+                    kind.$f.find(haystack)
+                }
+                unsafe fn rfind(
+                    kind: &ShortNeedleKind,
+                    haystack: &[u8],
+                ) -> Option<usize> {
+                    kind.$f.rfind(haystack)
+                }
+                (find as ShortNeedleFn, rfind as ShortNeedleFn)
+            }};
+        }
+
+        let needle: &[u8] = $needle;
+        match needle.len() {
+            $(
+                $len => {
+                    let finder =
+                        $module::Finder::<$len>::new(needle.try_into().ok()?)?;
+                    let (find, rfind) = fns!($field);
+                    let kind = ShortNeedleKind { $field: finder };
+                    let implementation = $imp;
+                    Some(ShortNeedle { find, rfind, kind, implementation })
+                }
+            )+
+            _ => None,
+        }
+    }};
+}
+
+impl ShortNeedle {
+    /// Creates a new searcher for the given needle.
+    ///
+    /// This returns `None` when the needle isn't `2`, `3` or `4` bytes long,
+    /// or when no vector implementation is available.
+    #[inline]
+    pub(crate) fn new(needle: &[u8]) -> Option<ShortNeedle> {
+        if !(2..=4).contains(&needle.len()) {
+            return None;
+        }
+        #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+        {
+            ShortNeedle::avx2(needle).or_else(|| ShortNeedle::sse2(needle))
+        }
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        {
+            if !is_permitted(Implementation::Simd128) {
+                return None;
+            }
+            build!(needle, Implementation::Simd128, simd128 {
+                2 => simd128_2,
+                3 => simd128_3,
+                4 => simd128_4,
+            })
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            if !is_permitted(Implementation::Neon) {
+                return None;
+            }
+            build!(needle, Implementation::Neon, neon {
+                2 => neon_2,
+                3 => neon_3,
+                4 => neon_4,
+            })
+        }
+    }
+
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    #[inline]
+    fn avx2(needle: &[u8]) -> Option<ShortNeedle> {
+        if !is_permitted(Implementation::Avx2) {
+            return None;
+        }
+        build!(needle, Implementation::Avx2, avx2 {
+            2 => avx2_2,
+            3 => avx2_3,
+            4 => avx2_4,
+        })
+    }
+
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    #[inline]
+    fn sse2(needle: &[u8]) -> Option<ShortNeedle> {
+        if !is_permitted(Implementation::Sse2) {
+            return None;
+        }
+        build!(needle, Implementation::Sse2, sse2 {
+            2 => sse2_2,
+            3 => sse2_3,
+            4 => sse2_4,
+        })
+    }
+
+    /// Returns the vector implementation used by this searcher.
+    #[inline]
+    pub(crate) fn implementation(&self) -> Implementation {
+        self.implementation
+    }
+
+    /// Returns the offset of the first occurrence of the needle in the given
+    /// haystack.
+    #[inline(always)]
+    pub(crate) fn find(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: By construction, `self.find` is paired with the field of
+        // the union set in `self.kind`.
+        unsafe { (self.find)(&self.kind, haystack) }
+    }
+
+    /// Returns the offset of the last occurrence of the needle in the given
+    /// haystack.
+    #[inline(always)]
+    pub(crate) fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: By construction, `self.rfind` is paired with the field of
+        // the union set in `self.kind`.
+        unsafe { (self.rfind)(&self.kind, haystack) }
+    }
+}
+
+impl core::fmt::Debug for ShortNeedle {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("ShortNeedle")
+            .field("kind", &"<short needle kind union>")
+            .field("implementation", &self.implementation)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forward() {
+        crate::tests::substring::Runner::new()
+            .fwd(|h, n| Some(ShortNeedle::new(n)?.find(h)))
+            .run();
+    }
+
+    #[test]
+    fn reverse() {
+        crate::tests::substring::Runner::new()
+            .rev(|h, n| Some(ShortNeedle::new(n)?.rfind(h)))
+            .run();
+    }
+
+    #[test]
+    fn lengths() {
+        let vector = crate::dispatch_info().memmem();
+        for len in 0..8 {
+            let needle = &b"abcdefgh"[..len];
+            let short = ShortNeedle::new(needle);
+            let supported = vector.is_vector() && (2..=4).contains(&len);
+            assert_eq!(supported, short.is_some());
+            if let Some(short) = short {
+                assert_eq!(vector, short.implementation());
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+/// Statistics collected while searching for a needle.
+///
+/// Statistics are only collected by the routines that explicitly ask for
+/// them, such as [`Finder::find_with_stats`] and [`FindIter::with_stats`].
+/// Ordinary searches don't collect them. The counts from every
+/// search given the same `SearchStats` value are accumulated.
+///
+/// These are meant for profiling. For example, they can be used to judge
+/// whether a custom [`HeuristicFrequencyRank`] chooses bytes that are
+/// actually rare in a particular corpus. The exact numbers depend on the
+/// strategy chosen by the finder (see [`Finder::strategy`]), which can differ
+/// between targets.
+///
+/// Note that collecting statistics may be slower than an ordinary search.
+/// In particular, when a finder uses a vector algorithm to confirm
+/// candidates on its own, the candidates are instead confirmed one at a
+/// time so that they can be counted. The matches reported are always the
+/// same.
+///
+/// [`Finder::find_with_stats`]: crate::memmem::Finder::find_with_stats
+/// [`FindIter::with_stats`]: crate::memmem::FindIter::with_stats
+/// [`Finder::strategy`]: crate::memmem::Finder::strategy
+/// [`HeuristicFrequencyRank`]:
+/// crate::arch::all::packedpair::HeuristicFrequencyRank
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::{Finder, SearchStats};
+///
+/// let mut haystack = [b'x'; 1006];
+/// haystack[1000..].copy_from_slice(b"foobar");
+///
+/// let finder = Finder::new("foobar");
+/// let mut stats = SearchStats::new();
+/// assert_eq!(Some(1000), finder.find_with_stats(&haystack, &mut stats));
+/// assert_eq!(1, stats.searches());
+/// assert_eq!(1006, stats.bytes_scanned());
+/// println!("{:?}", stats);
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SearchStats {
+    pub(crate) searches: u64,
+    pub(crate) bytes_scanned: u64,
+    pub(crate) candidates: u64,
+    pub(crate) false_positives: u64,
+    pub(crate) verified_bytes: u64,
+    pub(crate) prefilter_disabled: u64,
+    pub(crate) rabinkarp_searches: u64,
+    pub(crate) twoway_searches: u64,
+}
+
+impl SearchStats {
+    /// Create a new set of statistics with all counts set to zero.
+    #[inline]
+    pub fn new() -> SearchStats {
+        SearchStats::default()
+    }
+
+    /// Returns the number of searches executed.
+    ///
+    /// An iterator executes one search for each match it reports, plus one
+    /// more for the search that finds no match. (Except for the empty needle,
+    /// since its last match is at the very end of the haystack.)
+    #[inline]
+    pub fn searches(&self) -> u64 {
+        self.searches
+    }
+
+    /// Returns the number of haystack bytes searched.
+    ///
+    /// When a search finds a match, this includes the bytes up to the end of
+    /// the match. Otherwise, it includes all of the bytes searched.
+    #[inline]
+    pub fn bytes_scanned(&self) -> u64 {
+        self.bytes_scanned
+    }
+
+    /// Returns the number of candidate positions reported by a prefilter or
+    /// by a vector algorithm looking for a pair of rare bytes.
+    #[inline]
+    pub fn candidates(&self) -> u64 {
+        self.candidates
+    }
+
+    /// Returns the number of candidate positions that were not confirmed as
+    /// the start of a match.
+    #[inline]
+    pub fn false_positives(&self) -> u64 {
+        self.false_positives
+    }
+
+    /// Returns the number of bytes compared between the needle and the
+    /// haystack while confirming candidates.
+    ///
+    /// This is only counted when a prefilter or a vector algorithm is used.
+    /// Otherwise, it is always zero.
+    #[inline]
+    pub fn verified_bytes(&self) -> u64 {
+        self.verified_bytes
+    }
+
+    /// Returns the number of times a prefilter was disabled because it was
+    /// believed to be ineffective.
+    ///
+    /// A prefilter is disabled when the candidates it reports don't skip
+    /// enough of the haystack, on average. Once disabled, it stays disabled
+    /// for the remainder of the search. (Or for the remainder of the
+    /// iterator.)
+    #[inline]
+    pub fn prefilter_disabled(&self) -> u64 {
+        self.prefilter_disabled
+    }
+
+    /// Returns the number of searches that used the Rabin-Karp algorithm.
+    ///
+    /// Rabin-Karp is used instead of the chosen strategy when the haystack
+    /// is short.
+    #[inline]
+    pub fn rabinkarp_searches(&self) -> u64 {
+        self.rabinkarp_searches
+    }
+
+    /// Returns the number of searches that used the Two-Way algorithm.
+    #[inline]
+    pub fn twoway_searches(&self) -> u64 {
+        self.twoway_searches
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::memmem::{Finder, FinderBuilder, Prefilter, Strategy};
+
+    use super::*;
+
+    #[test]
+    fn forward() {
+        for prefilter in [Prefilter::Auto, Prefilter::None] {
+            crate::tests::substring::Runner::new()
+                .fwd(move |h, n| {
+                    let finder = FinderBuilder::new()
+                        .prefilter(prefilter)
+                        .build_forward(n);
+                    let mut stats = SearchStats::new();
+                    let got = finder.find_with_stats(h, &mut stats);
+                    assert_eq!(1, stats.searches());
+                    assert!(stats.false_positives() <= stats.candidates());
+                    Some(got)
+                })
+                .run();
+        }
+    }
+
+    #[test]
+    fn iter() {
+        let haystack = b"foo bar foofoo barfoo quux foo";
+        for needle in ["", "o", "foo", "foo bar", "barfoo quux"] {
+            let finder = Finder::new(needle);
+            let expected: Vec<usize> = finder.find_iter(haystack).collect();
+            let mut it = finder.find_iter(haystack).with_stats();
+            let got: Vec<usize> = it.by_ref().collect();
+            assert_eq!(expected, got);
+            let stats = it.stats().unwrap();
+            // One search for each match, and one that finds nothing. An
+            // empty needle matches at the very end, after which there is
+            // nothing left to search.
+            let extra = u64::from(!needle.is_empty());
+            assert_eq!(got.len() as u64 + extra, stats.searches());
+        }
+        assert!(Finder::new("foo").find_iter(b"foo").stats().is_none());
+    }
+
+    #[test]
+    fn prefilter_disabled() {
+        // Every other position is a candidate for this needle, so the
+        // prefilter should be deemed ineffective.
+        let haystack = "ab".repeat(5000);
+        let needle = alloc::format!("{}ba", "ab".repeat(20));
+        let finder = Finder::new(&needle);
+        assert!(matches!(
+            finder.strategy(),
+            Strategy::TwoWayWithPrefilter { .. }
+        ));
+        let mut stats = SearchStats::new();
+        assert_eq!(
+            None,
+            finder.find_with_stats(haystack.as_bytes(), &mut stats)
+        );
+        assert_eq!(1, stats.prefilter_disabled());
+        assert_eq!(1, stats.twoway_searches());
+        assert_eq!(0, stats.rabinkarp_searches());
+        assert_eq!(haystack.len() as u64, stats.bytes_scanned());
+        assert!(stats.candidates() >= 50);
+        assert_eq!(stats.candidates(), stats.false_positives());
+        assert!(stats.verified_bytes() > 0);
+    }
+
+    #[test]
+    fn candidates() {
+        let haystack = "foo fob foo fox foobar";
+        let finder = Finder::new("foobar");
+        let mut stats = SearchStats::new();
+        assert_eq!(
+            Some(16),
+            finder.find_with_stats(haystack.as_bytes(), &mut stats)
+        );
+        assert_eq!(22, stats.bytes_scanned());
+        match finder.strategy() {
+            Strategy::PackedPair { .. }
+            | Strategy::TwoWayWithPrefilter { .. } => {
+                assert!(stats.candidates() >= 1);
+                assert_eq!(stats.candidates() - 1, stats.false_positives());
+            }
+            _ => {}
+        }
+    }
+}
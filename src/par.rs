@@ -0,0 +1,501 @@
+/*!
+This module provides routines for searching large haystacks in parallel.
+
+The routines in this module split a haystack into partitions and search each
+partition on the [`rayon`] global thread pool. The results are exactly the
+same as the ones produced by their sequential counterparts, including for
+matches that straddle partition boundaries and for the leftmost-first
+non-overlapping semantics of substring search.
+
+Splitting a search across threads has overhead, so this is only worth it for
+very large haystacks (for example, hundreds of megabytes or more). Haystacks
+are never split into partitions smaller than 1 MiB.
+
+This module is only available when the `rayon` feature is enabled.
+
+# Example
+
+```
+use memchr::par;
+
+let haystack = b"foo bar foo baz foo quux".repeat(1_000);
+assert_eq!(3_000, par::memmem_count(&haystack, b"foo"));
+assert_eq!(5_000, par::memchr_count(b' ', &haystack));
+```
+*/
+
+use std::vec::Vec;
+
+use rayon::prelude::*;
+
+use crate::memmem::{searcher::PrefilterState, Finder};
+
+/// The smallest partition that a haystack is split into.
+const MIN_PARTITION_LEN: usize = 1 << 20;
+
+/// The number of needle lengths past the resume point that the sequential
+/// chain of matches is followed while looking for the independent chain of a
+/// partition. Past this, the rest of the haystack is searched sequentially.
+const MAX_RESYNC_NEEDLES: usize = 64;
+
+/// Returns the number of occurrences of a byte in a haystack, searching it in
+/// parallel.
+///
+/// This returns the same result as `memchr_iter(needle, haystack).count()`.
+///
+/// # Example
+///
+/// ```
+/// let haystack = b"a\nb\nc\n".repeat(1_000);
+/// assert_eq!(3_000, memchr::par::memchr_count(b'\n', &haystack));
+/// ```
+pub fn memchr_count(needle: u8, haystack: &[u8]) -> usize {
+    haystack
+        .par_chunks(partition_len(haystack.len()))
+        .map(|chunk| crate::memchr_iter(needle, chunk).count())
+        .sum()
+}
+
+/// Returns the offsets of all occurrences of a byte in a haystack, in
+/// ascending order, searching it in parallel.
+///
+/// This returns the same result as
+/// `memchr_iter(needle, haystack).collect::<Vec<_>>()`.
+///
+/// # Example
+///
+/// ```
+/// let haystack = b"a,b,c";
+/// assert_eq!(vec![1, 3], memchr::par::memchr_find_all(b',', haystack));
+/// ```
+pub fn memchr_find_all(needle: u8, haystack: &[u8]) -> Vec<usize> {
+    let len = partition_len(haystack.len());
+    haystack
+        .par_chunks(len)
+        .enumerate()
+        .flat_map_iter(|(i, chunk)| {
+            crate::memchr_iter(needle, chunk).map(move |pos| i * len + pos)
+        })
+        .collect()
+}
+
+/// Returns the number of non-overlapping occurrences of a needle in a
+/// haystack, searching it in parallel.
+///
+/// This returns the same result as
+/// `memmem::find_iter(haystack, needle).count()`.
+///
+/// This routine is useful if you're doing a parallel search once. If you're
+/// searching with the same needle many times, then it is likely more
+/// efficient to build a [`Finder`] once and use [`Finder::par_count`].
+///
+/// # Example
+///
+/// ```
+/// let haystack = b"aaaaa".repeat(1_000);
+/// assert_eq!(2_500, memchr::par::memmem_count(&haystack, b"aa"));
+/// ```
+pub fn memmem_count(haystack: &[u8], needle: &[u8]) -> usize {
+    Finder::new(needle).par_count(haystack)
+}
+
+/// Returns the offsets of all non-overlapping occurrences of a needle in a
+/// haystack, in ascending order, searching it in parallel.
+///
+/// This returns the same result as
+/// `memmem::find_iter(haystack, needle).collect::<Vec<_>>()`.
+///
+/// This routine is useful if you're doing a parallel search once. If you're
+/// searching with the same needle many times, then it is likely more
+/// efficient to build a [`Finder`] once and use [`Finder::par_find_all`].
+///
+/// # Example
+///
+/// ```
+/// let haystack = b"foo bar foo";
+/// assert_eq!(vec![0, 8], memchr::par::memmem_find_all(haystack, b"foo"));
+/// ```
+pub fn memmem_find_all(haystack: &[u8], needle: &[u8]) -> Vec<usize> {
+    Finder::new(needle).par_find_all(haystack)
+}
+
+/// Returns the length of the partitions that a haystack of the given length
+/// should be split into.
+pub(crate) fn partition_len(haystack_len: usize) -> usize {
+    let threads = rayon::current_num_threads().max(1);
+    let len = haystack_len / threads + usize::from(haystack_len % threads > 0);
+    len.max(MIN_PARTITION_LEN)
+}
+
+/// Counts all non-overlapping matches of the given finder, searching
+/// partitions of the given length in parallel.
+pub(crate) fn count(finder: &Finder, haystack: &[u8], len: usize) -> usize {
+    if finder.needle().is_empty() {
+        return finder.find_iter(haystack).count();
+    }
+    let parts: Vec<Partition> = partitions(haystack.len(), len)
+        .map(|(start, end)| {
+            let mut part = Partition::new(start, end);
+            for pos in Chain::new(finder, haystack, start, end) {
+                part.push(pos, finder.needle().len());
+            }
+            part
+        })
+        .collect();
+    let mut total = 0;
+    let mut resume = 0;
+    for part in parts.iter() {
+        if resume <= part.start {
+            total += part.count;
+            resume = part.last_end.unwrap_or(resume);
+            continue;
+        }
+        let sync = Resync::new(finder, haystack, part, resume);
+        total += sync.prefix.len();
+        resume = sync.resume;
+        if sync.abandoned {
+            let len = haystack.len();
+            return total + Chain::new(finder, haystack, resume, len).count();
+        }
+        if let Some(skipped) = sync.skipped {
+            total += part.count - skipped;
+        }
+    }
+    total
+}
+
+/// Finds all non-overlapping matches of the given finder, searching
+/// partitions of the given length in parallel.
+pub(crate) fn find_all(
+    finder: &Finder,
+    haystack: &[u8],
+    len: usize,
+) -> Vec<usize> {
+    if finder.needle().is_empty() {
+        return finder.find_iter(haystack).collect();
+    }
+    let parts: Vec<(Partition, Vec<usize>)> = partitions(haystack.len(), len)
+        .map(|(start, end)| {
+            let mut part = Partition::new(start, end);
+            let mut matches = Vec::new();
+            for pos in Chain::new(finder, haystack, start, end) {
+                part.push(pos, finder.needle().len());
+                matches.push(pos);
+            }
+            (part, matches)
+        })
+        .collect();
+    let mut all = Vec::new();
+    let mut resume = 0;
+    for (part, matches) in parts.iter() {
+        if resume <= part.start {
+            all.extend_from_slice(matches);
+            resume = part.last_end.unwrap_or(resume);
+            continue;
+        }
+        let sync = Resync::new(finder, haystack, part, resume);
+        all.extend_from_slice(&sync.prefix);
+        resume = sync.resume;
+        if sync.abandoned {
+            let len = haystack.len();
+            all.extend(Chain::new(finder, haystack, resume, len));
+            return all;
+        }
+        if let Some(skipped) = sync.skipped {
+            all.extend_from_slice(&matches[skipped..]);
+        }
+    }
+    all
+}
+
+/// Returns a parallel iterator over the bounds of each partition of a
+/// haystack with the given length. There is always at least one partition,
+/// even if the haystack is empty.
+fn partitions(
+    haystack_len: usize,
+    len: usize,
+) -> impl IndexedParallelIterator<Item = (usize, usize)> {
+    let count =
+        (haystack_len / len + usize::from(haystack_len % len > 0)).max(1);
+    (0..count).into_par_iter().map(move |i| {
+        (i * len, haystack_len.min(i.saturating_add(1).saturating_mul(len)))
+    })
+}
+
+/// A summary of the matches found by searching a single partition
+/// independently of all other partitions.
+#[derive(Debug)]
+struct Partition {
+    /// The offset at which the partition begins.
+    start: usize,
+    /// The offset at which the partition ends. Matches starting in the
+    /// partition may extend past this offset.
+    end: usize,
+    /// The number of matches found in the partition.
+    count: usize,
+    /// The offset at which the last match found in the partition ends.
+    last_end: Option<usize>,
+}
+
+impl Partition {
+    fn new(start: usize, end: usize) -> Partition {
+        Partition { start, end, count: 0, last_end: None }
+    }
+
+    fn push(&mut self, pos: usize, needle_len: usize) {
+        self.count += 1;
+        self.last_end = Some(pos + needle_len);
+    }
+}
+
+/// An iterator over the leftmost-first non-overlapping matches of a non-empty
+/// needle that begin at or after a given offset and before the end of a
+/// partition.
+///
+/// Since a match may begin anywhere in the partition, the search extends up
+/// to `needle.len() - 1` bytes past the end of the partition.
+struct Chain<'a, 'h> {
+    finder: &'a Finder<'a>,
+    haystack: &'h [u8],
+    prestate: PrefilterState,
+    at: usize,
+    end: usize,
+}
+
+impl<'a, 'h> Chain<'a, 'h> {
+    fn new(
+        finder: &'a Finder<'a>,
+        haystack: &'h [u8],
+        at: usize,
+        end: usize,
+    ) -> Chain<'a, 'h> {
+        let end = haystack.len().min(end + (finder.needle().len() - 1));
+        Chain { finder, haystack, prestate: PrefilterState::new(), at, end }
+    }
+}
+
+impl<'a, 'h> Iterator for Chain<'a, 'h> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let pos = self.finder.find_in(
+            &mut self.prestate,
+            self.haystack,
+            self.at,
+            self.end,
+        )?;
+        self.at = pos + self.finder.needle().len();
+        Some(pos)
+    }
+}
+
+/// The correction applied to a partition whose first bytes were consumed by
+/// a match beginning in a previous partition.
+///
+/// In this case, the matches found by searching the partition independently
+/// may not be the matches that a sequential search would find. However, the
+/// two chains of matches are identical after the first match they have in
+/// common, since each match is determined entirely by where the previous one
+/// ended. So the sequential chain is followed from the true resume point
+/// until it either meets the independent chain or leaves the partition.
+/// This is usually only a handful of matches.
+///
+/// In some haystacks, the chains may never meet. For example, searching for
+/// `aa` in a long run of `a`s yields two chains that start at different
+/// parities. Following the sequential chain through every such partition
+/// would make the whole search slower than a sequential one, so the chain is
+/// abandoned once it gets far enough from the resume point without meeting
+/// the independent chain. In that case, the caller searches the rest of the
+/// haystack sequentially. So in the worst case, a parallel search costs as
+/// much as a sequential search plus the work wasted on the partitions.
+#[derive(Debug)]
+struct Resync {
+    /// The matches in the sequential chain before it meets the independent
+    /// chain.
+    prefix: Vec<usize>,
+    /// When the chains meet, this is the number of matches in the
+    /// independent chain that precede the meeting point.
+    skipped: Option<usize>,
+    /// The offset at which the next partition's matches must begin at or
+    /// after.
+    resume: usize,
+    /// Set when the sequential chain was abandoned before meeting the
+    /// independent chain or leaving the partition.
+    abandoned: bool,
+}
+
+impl Resync {
+    fn new(
+        finder: &Finder,
+        haystack: &[u8],
+        part: &Partition,
+        resume: usize,
+    ) -> Resync {
+        let mut sync = Resync {
+            prefix: Vec::new(),
+            skipped: None,
+            resume,
+            abandoned: false,
+        };
+        // Every match in the sequential chain is also a match in the
+        // partition, so there is nothing to follow when it has none.
+        if part.count == 0 {
+            return sync;
+        }
+        let give_up = resume.saturating_add(
+            MAX_RESYNC_NEEDLES.saturating_mul(finder.needle().len()),
+        );
+        let mut indep = Chain::new(finder, haystack, part.start, part.end);
+        let mut candidate = indep.next();
+        let mut skipped = 0;
+        for pos in Chain::new(finder, haystack, resume, part.end) {
+            while candidate.map_or(false, |c| c < pos) {
+                candidate = indep.next();
+                skipped += 1;
+            }
+            if candidate == Some(pos) {
+                sync.skipped = Some(skipped);
+                sync.resume = part.last_end.unwrap();
+                return sync;
+            }
+            if pos >= give_up {
+                sync.abandoned = true;
+                return sync;
+            }
+            sync.prefix.push(pos);
+            sync.resume = pos + finder.needle().len();
+        }
+        sync
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use crate::memmem::FinderBuilder;
+
+    fn check(builder: &FinderBuilder, haystack: &[u8], needle: &[u8]) {
+        let finder = builder.build_forward(needle);
+        let expected: Vec<usize> = finder.find_iter(haystack).collect();
+        for len in 1..=haystack.len() + 1 {
+            let got = super::find_all(&finder, haystack, len);
+            assert_eq!(
+                expected, got,
+                "haystack: {:?}, needle: {:?}, partition length: {}",
+                haystack, needle, len,
+            );
+            let got = super::count(&finder, haystack, len);
+            assert_eq!(
+                expected.len(),
+                got,
+                "haystack: {:?}, needle: {:?}, partition length: {}",
+                haystack,
+                needle,
+                len,
+            );
+        }
+    }
+
+    #[test]
+    fn partitioned() {
+        let haystacks: &[&[u8]] = &[
+            b"",
+            b"a",
+            b"aaaaaaaaa",
+            b"abababababa",
+            b"abcabcabcabc",
+            b"foo_foo foo-foo xfoo foo",
+        ];
+        let needles: &[&[u8]] =
+            &[b"", b"a", b"aa", b"aaa", b"aba", b"abab", b"cab", b"foo"];
+        let mut builder = FinderBuilder::new();
+        for &haystack in haystacks {
+            for &needle in needles {
+                check(&builder, haystack, needle);
+            }
+        }
+        builder.whole_word(true);
+        for &haystack in haystacks {
+            for &needle in needles {
+                check(&builder, haystack, needle);
+            }
+        }
+    }
+
+    #[test]
+    fn periodic() {
+        // The chains of matches never meet in a long run of `a`s, so the
+        // search falls back to a sequential one.
+        let mut haystack = b"a".repeat(5_000);
+        haystack.extend_from_slice(b"b");
+        haystack.extend_from_slice(&b"ab".repeat(1_000));
+        haystack.extend_from_slice(&b"a".repeat(3_001));
+        for &needle in &[&b"aa"[..], b"aaa", b"aba", b"abab"] {
+            let finder = FinderBuilder::new().build_forward(needle);
+            let expected: Vec<usize> = finder.find_iter(&haystack).collect();
+            for &len in &[1, 7, 100, 1_001, 4_999] {
+                let got = super::find_all(&finder, &haystack, len);
+                assert_eq!(
+                    expected, got,
+                    "needle: {:?}, partition length: {}",
+                    needle, len,
+                );
+                let got = super::count(&finder, &haystack, len);
+                assert_eq!(
+                    expected.len(),
+                    got,
+                    "needle: {:?}, partition length: {}",
+                    needle,
+                    len,
+                );
+            }
+        }
+
+        let finder = FinderBuilder::new().build_forward("aa");
+        let part = partition(&finder, &haystack, 1_000, 2_000);
+        let sync = super::Resync::new(&finder, &haystack, &part, 1_001);
+        assert!(sync.abandoned);
+        assert!(sync.prefix.len() <= super::MAX_RESYNC_NEEDLES);
+    }
+
+    #[test]
+    fn resync_empty_partition() {
+        // A match straddles into each partition of length 4, but none
+        // begins in them, so nothing is searched sequentially.
+        let haystack = b"xxxfoo.xxxfoo.xxxfoo.";
+        let finder = FinderBuilder::new().build_forward("foo");
+        let part = partition(&finder, haystack, 4, 8);
+        assert_eq!(0, part.count);
+        let sync = super::Resync::new(&finder, haystack, &part, 6);
+        assert!(sync.prefix.is_empty());
+        assert_eq!(None, sync.skipped);
+        assert_eq!(6, sync.resume);
+        assert!(!sync.abandoned);
+        check(&FinderBuilder::new(), haystack, b"foo");
+    }
+
+    /// Searches the partition with the given bounds independently, like
+    /// `count` and `find_all` do.
+    fn partition(
+        finder: &crate::memmem::Finder,
+        haystack: &[u8],
+        start: usize,
+        end: usize,
+    ) -> super::Partition {
+        let mut part = super::Partition::new(start, end);
+        for pos in super::Chain::new(finder, haystack, start, end) {
+            part.push(pos, finder.needle().len());
+        }
+        part
+    }
+
+    #[test]
+    fn memchr_partitioned() {
+        let haystack = b"a\nbb\n\nccc\n".repeat(200_000);
+        let expected: Vec<usize> =
+            crate::memchr_iter(b'\n', &haystack).collect();
+        assert_eq!(expected, super::memchr_find_all(b'\n', &haystack));
+        assert_eq!(expected.len(), super::memchr_count(b'\n', &haystack));
+    }
+}
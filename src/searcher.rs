@@ -0,0 +1,970 @@
+/*!
+Reusable searchers for one, two or three bytes that pick the best available
+implementation when they are built.
+
+The free functions like [`memchr`](crate::memchr()) choose an implementation
+the first time they are called and store that choice in a global. Every
+subsequent call then loads it from an atomic. The searchers in this module
+instead make that choice once in their constructors and store it in the
+searcher itself, which makes them a good fit for searching many haystacks
+for the same needle.
+
+The choice is made in the same way as for the free functions. That is, it
+respects [`set_dispatch_cap`](crate::set_dispatch_cap) and
+[`set_cpu_features`](crate::set_cpu_features), but only at the time a
+searcher is built.
+*/
+
+use crate::{
+    arch::{all::memchr as fallback, generic::memchr as generic},
+    dispatch::Implementation,
+};
+
+#[cfg(target_arch = "aarch64")]
+use crate::arch::aarch64::neon::memchr as neon;
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+use crate::arch::wasm32::simd128::memchr as simd128;
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+use crate::arch::x86_64::{avx2::memchr as avx2, sse2::memchr as sse2};
+
+/// The functions for searching with a particular implementation.
+///
+/// Each function reads one particular field of the union `K`. It is paired
+/// with a union value that has that field set. This is the same approach
+/// used by the substring searcher in `memmem`, and for the same reason: an
+/// enum requires case analysis on every call, which adds measurable
+/// overhead for short haystacks.
+struct Fns<K> {
+    find_raw: unsafe fn(&K, *const u8, *const u8) -> Option<*const u8>,
+    rfind_raw: unsafe fn(&K, *const u8, *const u8) -> Option<*const u8>,
+    count_raw: unsafe fn(&K, *const u8, *const u8) -> usize,
+}
+
+impl<K> Clone for Fns<K> {
+    fn clone(&self) -> Fns<K> {
+        *self
+    }
+}
+
+impl<K> Copy for Fns<K> {}
+
+/// Defines a union over the searchers of each implementation for the same
+/// number of bytes.
+macro_rules! define_kind {
+    ($name:ident, $ty:ident) => {
+        #[derive(Clone, Copy)]
+        union $name {
+            fallback: fallback::$ty,
+            #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+            sse2: sse2::$ty,
+            #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+            avx2: avx2::$ty,
+            #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+            simd128: simd128::$ty,
+            #[cfg(target_arch = "aarch64")]
+            neon: neon::$ty,
+        }
+    };
+}
+
+define_kind!(OneKind, One);
+define_kind!(TwoKind, Two);
+define_kind!(ThreeKind, Three);
+
+/// Builds the `Fns` that read the given field of a union.
+///
+/// The `count` form is for searchers with a specialized `count_raw` routine.
+/// Otherwise, counting is done by searching repeatedly.
+///
+/// # Safety
+///
+/// The `Fns` returned must only be called with a union whose `$field` field
+/// is set.
+macro_rules! fns {
+    (count: $kind:ident, $field:ident) => {{
+        unsafe fn count_raw(
+            kind: &$kind,
+            start: *const u8,
+            end: *const u8,
+        ) -> usize {
+            kind.$field.count_raw(start, end)
+        }
+        fns!(@imp $kind, $field, count_raw)
+    }};
+    ($kind:ident, $field:ident) => {{
+        unsafe fn count_raw(
+            kind: &$kind,
+            mut start: *const u8,
+            end: *const u8,
+        ) -> usize {
+            let mut count = 0;
+            while let Some(found) = kind.$field.find_raw(start, end) {
+                count += 1;
+                start = found.add(1);
+            }
+            count
+        }
+        fns!(@imp $kind, $field, count_raw)
+    }};
+    (@imp $kind:ident, $field:ident, $count_raw:ident) => {{
+        unsafe fn find_raw(
+            kind: &$kind,
+            start: *const u8,
+            end: *const u8,
+        ) -> Option<*const u8> {
+            kind.$field.find_raw(start, end)
+        }
+        unsafe fn rfind_raw(
+            kind: &$kind,
+            start: *const u8,
+            end: *const u8,
+        ) -> Option<*const u8> {
+            kind.$field.rfind_raw(start, end)
+        }
+        Fns { find_raw, rfind_raw, count_raw: $count_raw }
+    }};
+}
+
+/// Chooses the best implementation of the given searcher type for the
+/// current target, and returns its functions, the union with the chosen
+/// searcher set and the implementation chosen.
+///
+/// The `count` form is for searchers with a specialized `count_raw` routine.
+macro_rules! choose {
+    (count: $($args:tt)*) => {
+        choose!(@imp [count:] $($args)*)
+    };
+    (@imp [$($count:tt)*] $kind:ident, $ty:ident, $($needle:ident),+) => {{
+        #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+        {
+            use crate::dispatch::is_permitted;
+
+            if is_permitted(Implementation::Avx2) {
+                if let Some(s) = avx2::$ty::new($($needle),+) {
+                    debug!("chose AVX2 for {}", stringify!($ty));
+                    let fns = fns!($($count)* $kind, avx2);
+                    return (fns, $kind { avx2: s }, Implementation::Avx2);
+                }
+            }
+            if is_permitted(Implementation::Sse2) {
+                if let Some(s) = sse2::$ty::new($($needle),+) {
+                    debug!("chose SSE2 for {}", stringify!($ty));
+                    let fns = fns!($($count)* $kind, sse2);
+                    return (fns, $kind { sse2: s }, Implementation::Sse2);
+                }
+            }
+        }
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        {
+            if crate::dispatch::is_permitted(Implementation::Simd128) {
+                if let Some(s) = simd128::$ty::new($($needle),+) {
+                    debug!("chose simd128 for {}", stringify!($ty));
+                    let fns = fns!($($count)* $kind, simd128);
+                    let kind = $kind { simd128: s };
+                    return (fns, kind, Implementation::Simd128);
+                }
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            if crate::dispatch::is_permitted(Implementation::Neon) {
+                if let Some(s) = neon::$ty::new($($needle),+) {
+                    debug!("chose neon for {}", stringify!($ty));
+                    let fns = fns!($($count)* $kind, neon);
+                    return (fns, $kind { neon: s }, Implementation::Neon);
+                }
+            }
+        }
+        debug!("chose fallback for {}", stringify!($ty));
+        let s = fallback::$ty::new($($needle),+);
+        let fns = fns!($($count)* $kind, fallback);
+        (fns, $kind { fallback: s }, Implementation::Fallback)
+    }};
+    ($($args:tt)*) => {
+        choose!(@imp [] $($args)*)
+    };
+}
+
+/// Finds all occurrences of a single byte in a haystack.
+///
+/// Unlike the searchers in the [`arch`](crate::arch) sub-modules, this
+/// searcher can always be built. It uses the best implementation available
+/// for the current target, which is chosen once in [`One::new`].
+///
+/// # Example
+///
+/// ```
+/// use memchr::One;
+///
+/// let searcher = One::new(b'a');
+/// assert_eq!(Some(5), searcher.find(b"foo bar baz"));
+/// assert_eq!(Some(9), searcher.rfind(b"foo bar baz"));
+/// assert_eq!(2, searcher.count(b"foo bar baz"));
+/// assert_eq!(None, searcher.find(b"quux"));
+/// ```
+#[derive(Clone, Copy)]
+pub struct One {
+    fns: Fns<OneKind>,
+    kind: OneKind,
+    implementation: Implementation,
+    needle: u8,
+}
+
+impl One {
+    /// Create a new searcher that finds occurrences of the byte given.
+    #[inline]
+    pub fn new(needle: u8) -> One {
+        let (fns, kind, implementation) = One::choose(needle);
+        One { fns, kind, implementation, needle }
+    }
+
+    #[inline]
+    fn choose(needle: u8) -> (Fns<OneKind>, OneKind, Implementation) {
+        choose!(count: OneKind, One, needle)
+    }
+
+    /// A test-only routine so that we can bundle a bunch of quickcheck
+    /// properties into a single macro.
+    #[cfg(test)]
+    pub(crate) fn try_new(needle: u8) -> Option<One> {
+        Some(One::new(needle))
+    }
+
+    /// Returns the implementation chosen by this searcher.
+    #[inline]
+    pub fn implementation(&self) -> Implementation {
+        self.implementation
+    }
+
+    /// Return the first occurrence of the needle in the given haystack. If no
+    /// such occurrence exists, then `None` is returned.
+    ///
+    /// The occurrence is reported as an offset into `haystack`. Its maximum
+    /// value is `haystack.len() - 1`.
+    #[inline]
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: `find_raw` guarantees that if a pointer is returned, it
+        // falls within the bounds of the start and end pointers.
+        unsafe {
+            generic::search_slice_with_raw(haystack, |s, e| {
+                self.find_raw(s, e)
+            })
+        }
+    }
+
+    /// Return the last occurrence of the needle in the given haystack. If no
+    /// such occurrence exists, then `None` is returned.
+    ///
+    /// The occurrence is reported as an offset into `haystack`. Its maximum
+    /// value is `haystack.len() - 1`.
+    #[inline]
+    pub fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: `rfind_raw` guarantees that if a pointer is returned, it
+        // falls within the bounds of the start and end pointers.
+        unsafe {
+            generic::search_slice_with_raw(haystack, |s, e| {
+                self.rfind_raw(s, e)
+            })
+        }
+    }
+
+    /// Counts all occurrences of this byte in the given haystack.
+    #[inline]
+    pub fn count(&self, haystack: &[u8]) -> usize {
+        // SAFETY: All of our pointers are derived directly from a borrowed
+        // slice, which is guaranteed to be valid.
+        unsafe {
+            let start = haystack.as_ptr();
+            let end = start.add(haystack.len());
+            self.count_raw(start, end)
+        }
+    }
+
+    /// Like `find`, but accepts and returns raw pointers.
+    ///
+    /// When a match is found, the pointer returned is guaranteed to be
+    /// `>= start` and `< end`.
+    ///
+    /// This routine is useful if you're already using raw pointers and would
+    /// like to avoid converting back to a slice before executing a search.
+    ///
+    /// # Safety
+    ///
+    /// The same safety requirements as for
+    /// [`arch::all::memchr::One::find_raw`](fallback::One::find_raw) apply.
+    #[inline]
+    pub unsafe fn find_raw(
+        &self,
+        start: *const u8,
+        end: *const u8,
+    ) -> Option<*const u8> {
+        // SAFETY: `fns` is always paired with the field set in `kind`.
+        (self.fns.find_raw)(&self.kind, start, end)
+    }
+
+    /// Like `rfind`, but accepts and returns raw pointers.
+    ///
+    /// When a match is found, the pointer returned is guaranteed to be
+    /// `>= start` and `< end`.
+    ///
+    /// # Safety
+    ///
+    /// The same safety requirements as for [`One::find_raw`] apply.
+    #[inline]
+    pub unsafe fn rfind_raw(
+        &self,
+        start: *const u8,
+        end: *const u8,
+    ) -> Option<*const u8> {
+        // SAFETY: `fns` is always paired with the field set in `kind`.
+        (self.fns.rfind_raw)(&self.kind, start, end)
+    }
+
+    /// Like `count`, but accepts and returns raw pointers.
+    ///
+    /// # Safety
+    ///
+    /// The same safety requirements as for [`One::find_raw`] apply.
+    #[inline]
+    pub unsafe fn count_raw(&self, start: *const u8, end: *const u8) -> usize {
+        // SAFETY: `fns` is always paired with the field set in `kind`.
+        (self.fns.count_raw)(&self.kind, start, end)
+    }
+
+    /// Returns an iterator over all occurrences of the needle byte in the
+    /// given haystack.
+    ///
+    /// The iterator returned implements `DoubleEndedIterator`. This means it
+    /// can also be used to find occurrences in reverse order.
+    #[inline]
+    pub fn iter<'a, 'h>(&'a self, haystack: &'h [u8]) -> OneIter<'a, 'h> {
+        OneIter { searcher: self, it: generic::Iter::new(haystack) }
+    }
+}
+
+impl core::fmt::Debug for One {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("One")
+            .field("needle", &self.needle)
+            .field("implementation", &self.implementation)
+            .finish()
+    }
+}
+
+/// An iterator over all occurrences of a single byte in a haystack.
+///
+/// This iterator implements `DoubleEndedIterator`, which means it can also be
+/// used to find occurrences in reverse order.
+///
+/// This iterator is created by the [`One::iter`] method.
+///
+/// The lifetime parameters are as follows:
+///
+/// * `'a` refers to the lifetime of the underlying [`One`] searcher.
+/// * `'h` refers to the lifetime of the haystack being searched.
+#[derive(Clone, Debug)]
+pub struct OneIter<'a, 'h> {
+    searcher: &'a One,
+    it: generic::Iter<'h>,
+}
+
+impl<'a, 'h> Iterator for OneIter<'a, 'h> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        // SAFETY: We rely on the generic iterator to provide valid start
+        // and end pointers, but we guarantee that any pointer returned by
+        // 'find_raw' falls within the bounds of the start and end pointer.
+        unsafe { self.it.next(|s, e| self.searcher.find_raw(s, e)) }
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.it.count(|s, e| {
+            // SAFETY: We rely on our generic iterator to return valid start
+            // and end pointers.
+            unsafe { self.searcher.count_raw(s, e) }
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.it.size_hint()
+    }
+}
+
+impl<'a, 'h> DoubleEndedIterator for OneIter<'a, 'h> {
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        // SAFETY: We rely on the generic iterator to provide valid start
+        // and end pointers, but we guarantee that any pointer returned by
+        // 'rfind_raw' falls within the bounds of the start and end pointer.
+        unsafe { self.it.next_back(|s, e| self.searcher.rfind_raw(s, e)) }
+    }
+}
+
+impl<'a, 'h> core::iter::FusedIterator for OneIter<'a, 'h> {}
+
+/// Finds all occurrences of two bytes in a haystack.
+///
+/// That is, this reports matches of one of two possible bytes. For example,
+/// searching for `a` or `b` in `afoobar` would report matches at offsets `0`,
+/// `4` and `5`.
+///
+/// Unlike the searchers in the [`arch`](crate::arch) sub-modules, this
+/// searcher can always be built. It uses the best implementation available
+/// for the current target, which is chosen once in [`Two::new`].
+///
+/// # Example
+///
+/// ```
+/// use memchr::Two;
+///
+/// let searcher = Two::new(b'a', b'b');
+/// let matches: Vec<usize> = searcher.iter(b"afoobar").collect();
+/// assert_eq!(vec![0, 4, 5], matches);
+/// ```
+#[derive(Clone, Copy)]
+pub struct Two {
+    fns: Fns<TwoKind>,
+    kind: TwoKind,
+    implementation: Implementation,
+    needles: [u8; 2],
+}
+
+impl Two {
+    /// Create a new searcher that finds occurrences of the bytes given.
+    #[inline]
+    pub fn new(needle1: u8, needle2: u8) -> Two {
+        let (fns, kind, implementation) = Two::choose(needle1, needle2);
+        Two { fns, kind, implementation, needles: [needle1, needle2] }
+    }
+
+    #[inline]
+    fn choose(
+        needle1: u8,
+        needle2: u8,
+    ) -> (Fns<TwoKind>, TwoKind, Implementation) {
+        choose!(TwoKind, Two, needle1, needle2)
+    }
+
+    /// A test-only routine so that we can bundle a bunch of quickcheck
+    /// properties into a single macro.
+    #[cfg(test)]
+    pub(crate) fn try_new(needle1: u8, needle2: u8) -> Option<Two> {
+        Some(Two::new(needle1, needle2))
+    }
+
+    /// Returns the implementation chosen by this searcher.
+    #[inline]
+    pub fn implementation(&self) -> Implementation {
+        self.implementation
+    }
+
+    /// Return the first occurrence of one of the needle bytes in the given
+    /// haystack. If no such occurrence exists, then `None` is returned.
+    ///
+    /// The occurrence is reported as an offset into `haystack`. Its maximum
+    /// value is `haystack.len() - 1`.
+    #[inline]
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: `find_raw` guarantees that if a pointer is returned, it
+        // falls within the bounds of the start and end pointers.
+        unsafe {
+            generic::search_slice_with_raw(haystack, |s, e| {
+                self.find_raw(s, e)
+            })
+        }
+    }
+
+    /// Return the last occurrence of one of the needle bytes in the given
+    /// haystack. If no such occurrence exists, then `None` is returned.
+    ///
+    /// The occurrence is reported as an offset into `haystack`. Its maximum
+    /// value is `haystack.len() - 1`.
+    #[inline]
+    pub fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: `rfind_raw` guarantees that if a pointer is returned, it
+        // falls within the bounds of the start and end pointers.
+        unsafe {
+            generic::search_slice_with_raw(haystack, |s, e| {
+                self.rfind_raw(s, e)
+            })
+        }
+    }
+
+    /// Counts all occurrences of either needle byte in the given haystack.
+    #[inline]
+    pub fn count(&self, haystack: &[u8]) -> usize {
+        // SAFETY: All of our pointers are derived directly from a borrowed
+        // slice, which is guaranteed to be valid.
+        unsafe {
+            let start = haystack.as_ptr();
+            let end = start.add(haystack.len());
+            self.count_raw(start, end)
+        }
+    }
+
+    /// Like `find`, but accepts and returns raw pointers.
+    ///
+    /// When a match is found, the pointer returned is guaranteed to be
+    /// `>= start` and `< end`.
+    ///
+    /// # Safety
+    ///
+    /// The same safety requirements as for [`One::find_raw`] apply.
+    #[inline]
+    pub unsafe fn find_raw(
+        &self,
+        start: *const u8,
+        end: *const u8,
+    ) -> Option<*const u8> {
+        // SAFETY: `fns` is always paired with the field set in `kind`.
+        (self.fns.find_raw)(&self.kind, start, end)
+    }
+
+    /// Like `rfind`, but accepts and returns raw pointers.
+    ///
+    /// When a match is found, the pointer returned is guaranteed to be
+    /// `>= start` and `< end`.
+    ///
+    /// # Safety
+    ///
+    /// The same safety requirements as for [`One::find_raw`] apply.
+    #[inline]
+    pub unsafe fn rfind_raw(
+        &self,
+        start: *const u8,
+        end: *const u8,
+    ) -> Option<*const u8> {
+        // SAFETY: `fns` is always paired with the field set in `kind`.
+        (self.fns.rfind_raw)(&self.kind, start, end)
+    }
+
+    /// Like `count`, but accepts and returns raw pointers.
+    ///
+    /// # Safety
+    ///
+    /// The same safety requirements as for [`One::find_raw`] apply.
+    #[inline]
+    pub unsafe fn count_raw(&self, start: *const u8, end: *const u8) -> usize {
+        // SAFETY: `fns` is always paired with the field set in `kind`.
+        (self.fns.count_raw)(&self.kind, start, end)
+    }
+
+    /// Returns an iterator over all occurrences of the needle bytes in the
+    /// given haystack.
+    ///
+    /// The iterator returned implements `DoubleEndedIterator`. This means it
+    /// can also be used to find occurrences in reverse order.
+    #[inline]
+    pub fn iter<'a, 'h>(&'a self, haystack: &'h [u8]) -> TwoIter<'a, 'h> {
+        TwoIter { searcher: self, it: generic::Iter::new(haystack) }
+    }
+}
+
+impl core::fmt::Debug for Two {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Two")
+            .field("needles", &self.needles)
+            .field("implementation", &self.implementation)
+            .finish()
+    }
+}
+
+/// An iterator over all occurrences of two possible bytes in a haystack.
+///
+/// This iterator implements `DoubleEndedIterator`, which means it can also be
+/// used to find occurrences in reverse order.
+///
+/// This iterator is created by the [`Two::iter`] method.
+///
+/// The lifetime parameters are as follows:
+///
+/// * `'a` refers to the lifetime of the underlying [`Two`] searcher.
+/// * `'h` refers to the lifetime of the haystack being searched.
+#[derive(Clone, Debug)]
+pub struct TwoIter<'a, 'h> {
+    searcher: &'a Two,
+    it: generic::Iter<'h>,
+}
+
+impl<'a, 'h> Iterator for TwoIter<'a, 'h> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        // SAFETY: We rely on the generic iterator to provide valid start
+        // and end pointers, but we guarantee that any pointer returned by
+        // 'find_raw' falls within the bounds of the start and end pointer.
+        unsafe { self.it.next(|s, e| self.searcher.find_raw(s, e)) }
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.it.count(|s, e| {
+            // SAFETY: We rely on our generic iterator to return valid start
+            // and end pointers.
+            unsafe { self.searcher.count_raw(s, e) }
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.it.size_hint()
+    }
+}
+
+impl<'a, 'h> DoubleEndedIterator for TwoIter<'a, 'h> {
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        // SAFETY: We rely on the generic iterator to provide valid start
+        // and end pointers, but we guarantee that any pointer returned by
+        // 'rfind_raw' falls within the bounds of the start and end pointer.
+        unsafe { self.it.next_back(|s, e| self.searcher.rfind_raw(s, e)) }
+    }
+}
+
+impl<'a, 'h> core::iter::FusedIterator for TwoIter<'a, 'h> {}
+
+/// Finds all occurrences of three bytes in a haystack.
+///
+/// That is, this reports matches of one of three possible bytes. For example,
+/// searching for `a`, `b` or `o` in `afoobar` would report matches at offsets
+/// `0`, `2`, `3`, `4` and `5`.
+///
+/// Unlike the searchers in the [`arch`](crate::arch) sub-modules, this
+/// searcher can always be built. It uses the best implementation available
+/// for the current target, which is chosen once in [`Three::new`].
+///
+/// # Example
+///
+/// ```
+/// use memchr::Three;
+///
+/// let searcher = Three::new(b'a', b'b', b'o');
+/// let matches: Vec<usize> = searcher.iter(b"afoobar").rev().collect();
+/// assert_eq!(vec![5, 4, 3, 2, 0], matches);
+/// ```
+#[derive(Clone, Copy)]
+pub struct Three {
+    fns: Fns<ThreeKind>,
+    kind: ThreeKind,
+    implementation: Implementation,
+    needles: [u8; 3],
+}
+
+impl Three {
+    /// Create a new searcher that finds occurrences of the bytes given.
+    #[inline]
+    pub fn new(needle1: u8, needle2: u8, needle3: u8) -> Three {
+        let (fns, kind, implementation) =
+            Three::choose(needle1, needle2, needle3);
+        let needles = [needle1, needle2, needle3];
+        Three { fns, kind, implementation, needles }
+    }
+
+    #[inline]
+    fn choose(
+        needle1: u8,
+        needle2: u8,
+        needle3: u8,
+    ) -> (Fns<ThreeKind>, ThreeKind, Implementation) {
+        choose!(ThreeKind, Three, needle1, needle2, needle3)
+    }
+
+    /// A test-only routine so that we can bundle a bunch of quickcheck
+    /// properties into a single macro.
+    #[cfg(test)]
+    pub(crate) fn try_new(
+        needle1: u8,
+        needle2: u8,
+        needle3: u8,
+    ) -> Option<Three> {
+        Some(Three::new(needle1, needle2, needle3))
+    }
+
+    /// Returns the implementation chosen by this searcher.
+    #[inline]
+    pub fn implementation(&self) -> Implementation {
+        self.implementation
+    }
+
+    /// Return the first occurrence of one of the needle bytes in the given
+    /// haystack. If no such occurrence exists, then `None` is returned.
+    ///
+    /// The occurrence is reported as an offset into `haystack`. Its maximum
+    /// value is `haystack.len() - 1`.
+    #[inline]
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: `find_raw` guarantees that if a pointer is returned, it
+        // falls within the bounds of the start and end pointers.
+        unsafe {
+            generic::search_slice_with_raw(haystack, |s, e| {
+                self.find_raw(s, e)
+            })
+        }
+    }
+
+    /// Return the last occurrence of one of the needle bytes in the given
+    /// haystack. If no such occurrence exists, then `None` is returned.
+    ///
+    /// The occurrence is reported as an offset into `haystack`. Its maximum
+    /// value is `haystack.len() - 1`.
+    #[inline]
+    pub fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+        // SAFETY: `rfind_raw` guarantees that if a pointer is returned, it
+        // falls within the bounds of the start and end pointers.
+        unsafe {
+            generic::search_slice_with_raw(haystack, |s, e| {
+                self.rfind_raw(s, e)
+            })
+        }
+    }
+
+    /// Counts all occurrences of any of the needle bytes in the given
+    /// haystack.
+    #[inline]
+    pub fn count(&self, haystack: &[u8]) -> usize {
+        // SAFETY: All of our pointers are derived directly from a borrowed
+        // slice, which is guaranteed to be valid.
+        unsafe {
+            let start = haystack.as_ptr();
+            let end = start.add(haystack.len());
+            self.count_raw(start, end)
+        }
+    }
+
+    /// Like `find`, but accepts and returns raw pointers.
+    ///
+    /// When a match is found, the pointer returned is guaranteed to be
+    /// `>= start` and `< end`.
+    ///
+    /// # Safety
+    ///
+    /// The same safety requirements as for [`One::find_raw`] apply.
+    #[inline]
+    pub unsafe fn find_raw(
+        &self,
+        start: *const u8,
+        end: *const u8,
+    ) -> Option<*const u8> {
+        // SAFETY: `fns` is always paired with the field set in `kind`.
+        (self.fns.find_raw)(&self.kind, start, end)
+    }
+
+    /// Like `rfind`, but accepts and returns raw pointers.
+    ///
+    /// When a match is found, the pointer returned is guaranteed to be
+    /// `>= start` and `< end`.
+    ///
+    /// # Safety
+    ///
+    /// The same safety requirements as for [`One::find_raw`] apply.
+    #[inline]
+    pub unsafe fn rfind_raw(
+        &self,
+        start: *const u8,
+        end: *const u8,
+    ) -> Option<*const u8> {
+        // SAFETY: `fns` is always paired with the field set in `kind`.
+        (self.fns.rfind_raw)(&self.kind, start, end)
+    }
+
+    /// Like `count`, but accepts and returns raw pointers.
+    ///
+    /// # Safety
+    ///
+    /// The same safety requirements as for [`One::find_raw`] apply.
+    #[inline]
+    pub unsafe fn count_raw(&self, start: *const u8, end: *const u8) -> usize {
+        // SAFETY: `fns` is always paired with the field set in `kind`.
+        (self.fns.count_raw)(&self.kind, start, end)
+    }
+
+    /// Returns an iterator over all occurrences of the needle bytes in the
+    /// given haystack.
+    ///
+    /// The iterator returned implements `DoubleEndedIterator`. This means it
+    /// can also be used to find occurrences in reverse order.
+    #[inline]
+    pub fn iter<'a, 'h>(&'a self, haystack: &'h [u8]) -> ThreeIter<'a, 'h> {
+        ThreeIter { searcher: self, it: generic::Iter::new(haystack) }
+    }
+}
+
+impl core::fmt::Debug for Three {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Three")
+            .field("needles", &self.needles)
+            .field("implementation", &self.implementation)
+            .finish()
+    }
+}
+
+/// An iterator over all occurrences of three possible bytes in a haystack.
+///
+/// This iterator implements `DoubleEndedIterator`, which means it can also be
+/// used to find occurrences in reverse order.
+///
+/// This iterator is created by the [`Three::iter`] method.
+///
+/// The lifetime parameters are as follows:
+///
+/// * `'a` refers to the lifetime of the underlying [`Three`] searcher.
+/// * `'h` refers to the lifetime of the haystack being searched.
+#[derive(Clone, Debug)]
+pub struct ThreeIter<'a, 'h> {
+    searcher: &'a Three,
+    it: generic::Iter<'h>,
+}
+
+impl<'a, 'h> Iterator for ThreeIter<'a, 'h> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        // SAFETY: We rely on the generic iterator to provide valid start
+        // and end pointers, but we guarantee that any pointer returned by
+        // 'find_raw' falls within the bounds of the start and end pointer.
+        unsafe { self.it.next(|s, e| self.searcher.find_raw(s, e)) }
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.it.count(|s, e| {
+            // SAFETY: We rely on our generic iterator to return valid start
+            // and end pointers.
+            unsafe { self.searcher.count_raw(s, e) }
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.it.size_hint()
+    }
+}
+
+impl<'a, 'h> DoubleEndedIterator for ThreeIter<'a, 'h> {
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        // SAFETY: We rely on the generic iterator to provide valid start
+        // and end pointers, but we guarantee that any pointer returned by
+        // 'rfind_raw' falls within the bounds of the start and end pointer.
+        unsafe { self.it.next_back(|s, e| self.searcher.rfind_raw(s, e)) }
+    }
+}
+
+impl<'a, 'h> core::iter::FusedIterator for ThreeIter<'a, 'h> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    define_memchr_quickcheck!(super, try_new);
+
+    #[test]
+    fn forward_one() {
+        crate::tests::memchr::Runner::new(1).forward_iter(
+            |haystack, needles| {
+                Some(One::new(needles[0]).iter(haystack).collect())
+            },
+        )
+    }
+
+    #[test]
+    fn reverse_one() {
+        crate::tests::memchr::Runner::new(1).reverse_iter(
+            |haystack, needles| {
+                Some(One::new(needles[0]).iter(haystack).rev().collect())
+            },
+        )
+    }
+
+    #[test]
+    fn count_one() {
+        crate::tests::memchr::Runner::new(1).count_iter(|haystack, needles| {
+            Some(One::new(needles[0]).count(haystack))
+        })
+    }
+
+    #[test]
+    fn forward_two() {
+        crate::tests::memchr::Runner::new(2).forward_iter(
+            |haystack, needles| {
+                let n1 = needles.first().copied()?;
+                let n2 = needles.get(1).copied()?;
+                Some(Two::new(n1, n2).iter(haystack).collect())
+            },
+        )
+    }
+
+    #[test]
+    fn reverse_two() {
+        crate::tests::memchr::Runner::new(2).reverse_iter(
+            |haystack, needles| {
+                let n1 = needles.first().copied()?;
+                let n2 = needles.get(1).copied()?;
+                Some(Two::new(n1, n2).iter(haystack).rev().collect())
+            },
+        )
+    }
+
+    #[test]
+    fn count_two() {
+        crate::tests::memchr::Runner::new(2).count_iter(|haystack, needles| {
+            let n1 = needles.first().copied()?;
+            let n2 = needles.get(1).copied()?;
+            Some(Two::new(n1, n2).count(haystack))
+        })
+    }
+
+    #[test]
+    fn forward_three() {
+        crate::tests::memchr::Runner::new(3).forward_iter(
+            |haystack, needles| {
+                let n1 = needles.first().copied()?;
+                let n2 = needles.get(1).copied()?;
+                let n3 = needles.get(2).copied()?;
+                Some(Three::new(n1, n2, n3).iter(haystack).collect())
+            },
+        )
+    }
+
+    #[test]
+    fn reverse_three() {
+        crate::tests::memchr::Runner::new(3).reverse_iter(
+            |haystack, needles| {
+                let n1 = needles.first().copied()?;
+                let n2 = needles.get(1).copied()?;
+                let n3 = needles.get(2).copied()?;
+                Some(Three::new(n1, n2, n3).iter(haystack).rev().collect())
+            },
+        )
+    }
+
+    #[test]
+    fn count_three() {
+        crate::tests::memchr::Runner::new(3).count_iter(|haystack, needles| {
+            let n1 = needles.first().copied()?;
+            let n2 = needles.get(1).copied()?;
+            let n3 = needles.get(2).copied()?;
+            Some(Three::new(n1, n2, n3).count(haystack))
+        })
+    }
+
+    #[test]
+    fn implementation() {
+        assert_eq!(Some(0), crate::memchr(b'a', b"a"));
+        assert_eq!(Some(0), crate::memchr2(b'a', b'b', b"a"));
+        assert_eq!(Some(0), crate::memchr3(b'a', b'b', b'c', b"a"));
+        let info = crate::dispatch_info();
+        let one = One::new(b'a').implementation();
+        assert_eq!(info.memchr(), Some(one));
+        let two = Two::new(b'a', b'b').implementation();
+        assert_eq!(info.memchr2(), Some(two));
+        let three = Three::new(b'a', b'b', b'c').implementation();
+        assert_eq!(info.memchr3(), Some(three));
+    }
+}
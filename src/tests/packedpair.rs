@@ -20,6 +20,32 @@ const SEEDS: &[Seed] = &[
     Seed { first: b'x', index1: b'y', index2: b'y' },
 ];
 
+/// Returns the offset of the first occurrence of `needle` in `haystack`, by
+/// confirming each candidate reported by the given prefilter.
+///
+/// This permits testing prefilters with the substring search tests. Once the
+/// rest of the haystack is shorter than `min_haystack_len`, it is searched
+/// one position at a time instead.
+pub(crate) fn find_with_prefilter(
+    haystack: &[u8],
+    needle: &[u8],
+    min_haystack_len: usize,
+    mut prefilter: impl FnMut(&[u8]) -> Option<usize>,
+) -> Option<usize> {
+    let mut pos = 0;
+    while haystack.len() - pos >= min_haystack_len {
+        let i = pos + prefilter(&haystack[pos..])?;
+        if haystack[i..].starts_with(needle) {
+            return Some(i);
+        }
+        pos = i + 1;
+    }
+    haystack[pos..]
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|i| pos + i)
+}
+
 /// Runs a host of "packed pair" search tests.
 ///
 /// These tests specifically look for the occurrence of a possible substring
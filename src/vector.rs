@@ -1,3 +1,54 @@
+/*!
+A generic vectorized search loop for finding bytes matching a predicate.
+
+The [`find`] and [`find_raw`] routines run the same aligned and unrolled
+search loop used by the [`arch`](crate::arch) `memchr` implementations. But
+instead of looking for a fixed set of bytes, they look for the first byte
+matching an arbitrary [`Kernel`]. This gets one the crate's handling of
+alignment, loop unrolling and short haystacks for free.
+
+The [`SimdVector`] trait describes the handful of vector operations a kernel
+and the search loop need. This crate implements it for the vector types it
+supports, such as `__m128i` and `__m256i` on `x86_64`, but callers may also
+implement it for their own types.
+
+# Example
+
+This shows how to find the first ASCII digit in a haystack using SSE2 on
+`x86_64`.
+
+```
+# #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))] {
+use core::arch::x86_64::*;
+
+use memchr::vector::{find, Kernel};
+
+struct Digit;
+
+impl Kernel<__m128i> for Digit {
+    unsafe fn find_in_vector(&self, chunk: __m128i) -> __m128i {
+        // Signed comparisons are fine here, since all bytes greater than
+        // 0x7F are negative and thus never digits.
+        let ge0 = _mm_cmpgt_epi8(chunk, _mm_set1_epi8(b'0' as i8 - 1));
+        let le9 = _mm_cmplt_epi8(chunk, _mm_set1_epi8(b'9' as i8 + 1));
+        _mm_and_si128(ge0, le9)
+    }
+
+    fn is_match(&self, byte: u8) -> bool {
+        byte.is_ascii_digit()
+    }
+}
+
+let haystack = b"the year was 1997, or so";
+// SAFETY: SSE2 is enabled at compile time.
+assert_eq!(Some(13), unsafe { find::<__m128i, _>(&Digit, haystack) });
+assert_eq!(None, unsafe { find::<__m128i, _>(&Digit, b"no digits here") });
+# }
+```
+*/
+
+use crate::{arch::generic::memchr as generic, ext::Pointer};
+
 /// A trait for describing vector operations used by vectorized searchers.
 ///
 /// The trait is highly constrained to low level vector operations needed.
@@ -191,15 +242,321 @@ impl MoveMask for SensibleMoveMask {
     }
 }
 
+/// A trait for the vector operations used by a [`Kernel`].
+///
+/// This is a small and stable subset of the vector operations used
+/// internally by this crate. It is implemented for `__m128i` and `__m256i` on
+/// `x86_64`, `uint8x16_t` on `aarch64` and `v128` on `wasm32` (when
+/// `simd128` is enabled), but callers may also implement it for their own
+/// types.
+///
+/// Every vector is made up of `SimdVector::BYTES` lanes, where each lane is
+/// a single byte. The lanes are ordered by the address of the bytes they are
+/// loaded from.
+///
+/// # Safety
+///
+/// All methods are not safe since they are intended to be implemented using
+/// vendor intrinsics, which are also not safe. Callers must ensure that the
+/// appropriate target features are enabled in the calling function, and that
+/// the current CPU supports them. All implementations should avoid marking
+/// the routines with `#[target_feature]` and instead mark them as
+/// `#[inline(always)]` to ensure they get appropriately inlined.
+///
+/// Implementing this trait is safe. The routines in this crate never rely on
+/// the correctness of an implementation for memory safety. An incorrect
+/// implementation may only result in incorrect search results. (Of course,
+/// implementations must still uphold the safety obligations of any unsafe
+/// code they use themselves.)
+pub trait SimdVector: Copy + core::fmt::Debug {
+    /// The number of bytes in the vector. That is, this is the size of the
+    /// vector in memory.
+    ///
+    /// This must be a power of two that is no bigger than `64`.
+    const BYTES: usize;
+
+    /// Create a vector with 8-bit lanes with the given byte repeated into each
+    /// lane.
+    ///
+    /// # Safety
+    ///
+    /// Callers must ensure that the vector operations are supported by the
+    /// current environment.
+    unsafe fn splat(byte: u8) -> Self;
+
+    /// Read a vector-size number of bytes from the given pointer. The pointer
+    /// does not need to be aligned.
+    ///
+    /// # Safety
+    ///
+    /// Callers must guarantee that at least `BYTES` bytes are readable from
+    /// `data`. Callers must also ensure that the vector operations are
+    /// supported by the current environment.
+    unsafe fn load_unaligned(data: *const u8) -> Self;
+
+    /// Compare each lane for equality. The lanes of the vector returned are
+    /// `0xFF` when the corresponding lanes are equal, and `0x00` otherwise.
+    ///
+    /// # Safety
+    ///
+    /// Callers must ensure that the vector operations are supported by the
+    /// current environment.
+    unsafe fn cmpeq(self, vector2: Self) -> Self;
+
+    /// Compute the bitwise `and` of two vectors.
+    ///
+    /// # Safety
+    ///
+    /// Callers must ensure that the vector operations are supported by the
+    /// current environment.
+    unsafe fn and(self, vector2: Self) -> Self;
+
+    /// Compute the bitwise `or` of two vectors.
+    ///
+    /// # Safety
+    ///
+    /// Callers must ensure that the vector operations are supported by the
+    /// current environment.
+    unsafe fn or(self, vector2: Self) -> Self;
+
+    /// Return a mask whose `i`th bit is set if and only if the most
+    /// significant bit in the `i`th lane of this vector is set. All bits at
+    /// or above `BYTES` must be zero.
+    ///
+    /// The offset of the first matching byte in a chunk is computed from the
+    /// number of trailing zeros in this mask.
+    ///
+    /// # Safety
+    ///
+    /// Callers must ensure that the vector operations are supported by the
+    /// current environment.
+    unsafe fn movemask(self) -> u64;
+}
+
+/// A predicate over bytes that can be evaluated on an entire vector at once.
+///
+/// Kernels are used with [`find`] and [`find_raw`] to search for the first
+/// byte in a haystack matching the predicate.
+///
+/// Implementations must be consistent. That is, for every lane in a vector,
+/// `find_in_vector` should set the most significant bit of that lane if and
+/// only if `is_match` returns true for the corresponding byte. Otherwise, the
+/// search results are unspecified. (But memory safety is never violated.)
+pub trait Kernel<V: SimdVector> {
+    /// Return a vector whose lanes have their most significant bit set if and
+    /// only if the corresponding byte in `chunk` matches.
+    ///
+    /// # Safety
+    ///
+    /// Callers must ensure that the vector operations are supported by the
+    /// current environment.
+    unsafe fn find_in_vector(&self, chunk: V) -> V;
+
+    /// Return true if and only if the given byte matches.
+    ///
+    /// This is used for haystacks that are too short to load a vector from.
+    fn is_match(&self, byte: u8) -> bool;
+}
+
+/// Return the offset of the first byte in the given haystack matching the
+/// kernel given. If no such byte exists, then `None` is returned.
+///
+/// This is a convenience routine for [`find_raw`] that works on slices.
+///
+/// # Panics
+///
+/// When `V::BYTES` is not a power of two or is bigger than `64`. (Or when the
+/// offset reported by an incorrect `SimdVector::movemask` is out of bounds.)
+///
+/// # Safety
+///
+/// Callers must ensure that the vector operations of `V` are supported by
+/// the current environment. For example, to use `__m256i` on `x86_64`,
+/// callers must ensure that AVX2 is available.
+///
+/// For best performance, this should be called from within a function that
+/// enables the target features needed by `V` with a `#[target_feature]`
+/// attribute. This routine and the kernel's methods can then be inlined into
+/// that function.
+#[inline(always)]
+pub unsafe fn find<V: SimdVector, K: Kernel<V>>(
+    kernel: &K,
+    haystack: &[u8],
+) -> Option<usize> {
+    let start = haystack.as_ptr();
+    let end = start.add(haystack.len());
+    let found = find_raw(kernel, start, end)?;
+    // We don't use `Pointer::distance` here since the pointer returned may
+    // not be in bounds when the kernel or `movemask` implementation is
+    // incorrect.
+    let offset = found.as_usize().wrapping_sub(start.as_usize());
+    assert!(offset < haystack.len(), "match offset out of bounds");
+    Some(offset)
+}
+
+/// Like [`find`], but accepts and returns raw pointers.
+///
+/// When a match is found, the pointer returned is guaranteed to be
+/// `>= start` and `< end`, as long as the kernel and the
+/// `SimdVector::movemask` implementation for `V` are correct.
+///
+/// # Panics
+///
+/// When `V::BYTES` is not a power of two or is bigger than `64`.
+///
+/// # Safety
+///
+/// Callers must ensure that the vector operations of `V` are supported by
+/// the current environment. Moreover:
+///
+/// * Both `start` and `end` must be valid for reads.
+/// * Both `start` and `end` must point to an initialized value.
+/// * Both `start` and `end` must point to the same allocated object and
+///   must either be in bounds or at most one byte past the end of the
+///   allocated object.
+/// * Both `start` and `end` must be _derived from_ a pointer to the same
+///   object.
+/// * The distance between `start` and `end` must not overflow `isize`.
+/// * The distance being in bounds must not rely on "wrapping around" the
+///   address space.
+///
+/// Note that callers may pass a pair of pointers such that `start >= end`.
+/// In that case, `None` will always be returned.
+#[inline(always)]
+pub unsafe fn find_raw<V: SimdVector, K: Kernel<V>>(
+    kernel: &K,
+    start: *const u8,
+    end: *const u8,
+) -> Option<*const u8> {
+    assert!(
+        V::BYTES.is_power_of_two() && V::BYTES <= 64,
+        "vector size must be a power of 2 no bigger than 64",
+    );
+    if start >= end {
+        return None;
+    }
+    if end.distance(start) < V::BYTES {
+        // SAFETY: We require the caller to pass valid start/end pointers.
+        return generic::fwd_byte_by_byte(start, end, |b| kernel.is_match(b));
+    }
+    // SAFETY: We've checked that `V::BYTES` is a power of two and that our
+    // haystack is big enough to run on the vector routine. Pointer validity
+    // and vector support are the caller's responsibility.
+    generic::find_raw_with(start, end, |chunk: Bridge<V>| {
+        Bridge(kernel.find_in_vector(chunk.0))
+    })
+}
+
+/// Adapts a `SimdVector` to the internal `Vector` trait, so that public
+/// kernels can run on the same search loop as `memchr`.
+///
+/// Since `SimdVector` has no aligned loads, the aligned loads in the search
+/// loop are done with unaligned loads.
+#[derive(Clone, Copy, Debug)]
+struct Bridge<V>(V);
+
+impl<V: SimdVector> Vector for Bridge<V> {
+    const BYTES: usize = V::BYTES;
+    const ALIGN: usize = V::BYTES - 1;
+
+    type Mask = BridgeMoveMask;
+
+    #[inline(always)]
+    unsafe fn splat(byte: u8) -> Bridge<V> {
+        Bridge(V::splat(byte))
+    }
+
+    #[inline(always)]
+    unsafe fn load_aligned(data: *const u8) -> Bridge<V> {
+        Bridge(V::load_unaligned(data))
+    }
+
+    #[inline(always)]
+    unsafe fn load_unaligned(data: *const u8) -> Bridge<V> {
+        Bridge(V::load_unaligned(data))
+    }
+
+    #[inline(always)]
+    unsafe fn movemask(self) -> BridgeMoveMask {
+        BridgeMoveMask(self.0.movemask())
+    }
+
+    #[inline(always)]
+    unsafe fn cmpeq(self, vector2: Self) -> Bridge<V> {
+        Bridge(self.0.cmpeq(vector2.0))
+    }
+
+    #[inline(always)]
+    unsafe fn and(self, vector2: Self) -> Bridge<V> {
+        Bridge(self.0.and(vector2.0))
+    }
+
+    #[inline(always)]
+    unsafe fn or(self, vector2: Self) -> Bridge<V> {
+        Bridge(self.0.or(vector2.0))
+    }
+}
+
+/// The mask returned by `SimdVector::movemask`.
+///
+/// This is like `SensibleMoveMask`, except that it can represent up to 64
+/// lanes. The `i`th bit always corresponds to the `i`th lane, so no byte
+/// swapping is needed to compute offsets.
+#[derive(Clone, Copy, Debug)]
+struct BridgeMoveMask(u64);
+
+impl MoveMask for BridgeMoveMask {
+    #[inline(always)]
+    fn all_zeros_except_least_significant(n: usize) -> BridgeMoveMask {
+        debug_assert!(n < 64);
+        BridgeMoveMask(!((1 << n) - 1))
+    }
+
+    #[inline(always)]
+    fn has_non_zero(self) -> bool {
+        self.0 != 0
+    }
+
+    #[inline(always)]
+    fn count_ones(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    #[inline(always)]
+    fn and(self, other: BridgeMoveMask) -> BridgeMoveMask {
+        BridgeMoveMask(self.0 & other.0)
+    }
+
+    #[inline(always)]
+    fn or(self, other: BridgeMoveMask) -> BridgeMoveMask {
+        BridgeMoveMask(self.0 | other.0)
+    }
+
+    #[inline(always)]
+    fn clear_least_significant_bit(self) -> BridgeMoveMask {
+        BridgeMoveMask(self.0 & (self.0 - 1))
+    }
+
+    #[inline(always)]
+    fn first_offset(self) -> usize {
+        self.0.trailing_zeros() as usize
+    }
+
+    #[inline(always)]
+    fn last_offset(self) -> usize {
+        64 - self.0.leading_zeros() as usize - 1
+    }
+}
+
 #[cfg(target_arch = "x86_64")]
 mod x86sse2 {
     use core::arch::x86_64::*;
 
-    use super::{SensibleMoveMask, Vector};
+    use super::{SensibleMoveMask, SimdVector, Vector};
 
     impl Vector for __m128i {
         const BYTES: usize = 16;
-        const ALIGN: usize = Self::BYTES - 1;
+        const ALIGN: usize = <Self as Vector>::BYTES - 1;
 
         type Mask = SensibleMoveMask;
 
@@ -238,17 +595,51 @@ mod x86sse2 {
             _mm_or_si128(self, vector2)
         }
     }
+
+    impl SimdVector for __m128i {
+        const BYTES: usize = 16;
+
+        #[inline(always)]
+        unsafe fn splat(byte: u8) -> __m128i {
+            <__m128i as Vector>::splat(byte)
+        }
+
+        #[inline(always)]
+        unsafe fn load_unaligned(data: *const u8) -> __m128i {
+            <__m128i as Vector>::load_unaligned(data)
+        }
+
+        #[inline(always)]
+        unsafe fn cmpeq(self, vector2: Self) -> __m128i {
+            Vector::cmpeq(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn and(self, vector2: Self) -> __m128i {
+            Vector::and(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> __m128i {
+            Vector::or(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn movemask(self) -> u64 {
+            u64::from(_mm_movemask_epi8(self) as u16)
+        }
+    }
 }
 
 #[cfg(target_arch = "x86_64")]
 mod x86avx2 {
     use core::arch::x86_64::*;
 
-    use super::{SensibleMoveMask, Vector};
+    use super::{SensibleMoveMask, SimdVector, Vector};
 
     impl Vector for __m256i {
         const BYTES: usize = 32;
-        const ALIGN: usize = Self::BYTES - 1;
+        const ALIGN: usize = <Self as Vector>::BYTES - 1;
 
         type Mask = SensibleMoveMask;
 
@@ -287,17 +678,51 @@ mod x86avx2 {
             _mm256_or_si256(self, vector2)
         }
     }
+
+    impl SimdVector for __m256i {
+        const BYTES: usize = 32;
+
+        #[inline(always)]
+        unsafe fn splat(byte: u8) -> __m256i {
+            <__m256i as Vector>::splat(byte)
+        }
+
+        #[inline(always)]
+        unsafe fn load_unaligned(data: *const u8) -> __m256i {
+            <__m256i as Vector>::load_unaligned(data)
+        }
+
+        #[inline(always)]
+        unsafe fn cmpeq(self, vector2: Self) -> __m256i {
+            Vector::cmpeq(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn and(self, vector2: Self) -> __m256i {
+            Vector::and(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> __m256i {
+            Vector::or(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn movemask(self) -> u64 {
+            u64::from(_mm256_movemask_epi8(self) as u32)
+        }
+    }
 }
 
 #[cfg(target_arch = "aarch64")]
 mod aarch64neon {
     use core::arch::aarch64::*;
 
-    use super::{MoveMask, Vector};
+    use super::{MoveMask, SimdVector, Vector};
 
     impl Vector for uint8x16_t {
         const BYTES: usize = 16;
-        const ALIGN: usize = Self::BYTES - 1;
+        const ALIGN: usize = <Self as Vector>::BYTES - 1;
 
         type Mask = NeonMoveMask;
 
@@ -310,7 +735,7 @@ mod aarch64neon {
         unsafe fn load_aligned(data: *const u8) -> uint8x16_t {
             // I've tried `data.cast::<uint8x16_t>().read()` instead, but
             // couldn't observe any benchmark differences.
-            Self::load_unaligned(data)
+            <Self as Vector>::load_unaligned(data)
         }
 
         #[inline(always)]
@@ -462,17 +887,59 @@ mod aarch64neon {
             16 - (self.get_for_offset().leading_zeros() >> 2) as usize - 1
         }
     }
+
+    impl SimdVector for uint8x16_t {
+        const BYTES: usize = 16;
+
+        #[inline(always)]
+        unsafe fn splat(byte: u8) -> uint8x16_t {
+            <uint8x16_t as Vector>::splat(byte)
+        }
+
+        #[inline(always)]
+        unsafe fn load_unaligned(data: *const u8) -> uint8x16_t {
+            <uint8x16_t as Vector>::load_unaligned(data)
+        }
+
+        #[inline(always)]
+        unsafe fn cmpeq(self, vector2: Self) -> uint8x16_t {
+            Vector::cmpeq(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn and(self, vector2: Self) -> uint8x16_t {
+            Vector::and(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> uint8x16_t {
+            Vector::or(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn movemask(self) -> u64 {
+            // Our neon mask sets bit `4 * i + 3` for lane `i`. So we gather
+            // those bits together, halving the distance between them at
+            // each step.
+            let mut bits =
+                (Vector::movemask(self).0 >> 3) & 0x1111111111111111;
+            bits = (bits | (bits >> 3)) & 0x0303030303030303;
+            bits = (bits | (bits >> 6)) & 0x000F000F000F000F;
+            bits = (bits | (bits >> 12)) & 0x000000FF000000FF;
+            (bits | (bits >> 24)) & 0xFFFF
+        }
+    }
 }
 
 #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
 mod wasm_simd128 {
     use core::arch::wasm32::*;
 
-    use super::{SensibleMoveMask, Vector};
+    use super::{SensibleMoveMask, SimdVector, Vector};
 
     impl Vector for v128 {
         const BYTES: usize = 16;
-        const ALIGN: usize = Self::BYTES - 1;
+        const ALIGN: usize = <Self as Vector>::BYTES - 1;
 
         type Mask = SensibleMoveMask;
 
@@ -511,4 +978,315 @@ mod wasm_simd128 {
             v128_or(self, vector2)
         }
     }
+
+    impl SimdVector for v128 {
+        const BYTES: usize = 16;
+
+        #[inline(always)]
+        unsafe fn splat(byte: u8) -> v128 {
+            <v128 as Vector>::splat(byte)
+        }
+
+        #[inline(always)]
+        unsafe fn load_unaligned(data: *const u8) -> v128 {
+            <v128 as Vector>::load_unaligned(data)
+        }
+
+        #[inline(always)]
+        unsafe fn cmpeq(self, vector2: Self) -> v128 {
+            Vector::cmpeq(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn and(self, vector2: Self) -> v128 {
+            Vector::and(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> v128 {
+            Vector::or(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn movemask(self) -> u64 {
+            u64::from(u8x16_bitmask(self))
+        }
+    }
+}
+
+#[cfg(feature = "portable-simd")]
+mod portable {
+    use core::simd::{
+        cmp::{SimdPartialEq, SimdPartialOrd},
+        Select, Simd,
+    };
+
+    use super::{MoveMask, SimdVector, Vector};
+
+    /// A movemask for vectors from `core::simd`.
+    ///
+    /// This is a "sensible" movemask, like `SensibleMoveMask`, except that
+    /// it can represent up to 64 lanes. The `i`th bit always corresponds to the
+    /// `i`th lane, regardless of the target's endianness, so no byte swapping
+    /// is needed to compute offsets.
+    #[derive(Clone, Copy, Debug)]
+    pub(crate) struct PortableMoveMask(u64);
+
+    impl MoveMask for PortableMoveMask {
+        #[inline(always)]
+        fn all_zeros_except_least_significant(n: usize) -> PortableMoveMask {
+            debug_assert!(n < 64);
+            PortableMoveMask(!((1 << n) - 1))
+        }
+
+        #[inline(always)]
+        fn has_non_zero(self) -> bool {
+            self.0 != 0
+        }
+
+        #[inline(always)]
+        fn count_ones(self) -> usize {
+            self.0.count_ones() as usize
+        }
+
+        #[inline(always)]
+        fn and(self, other: PortableMoveMask) -> PortableMoveMask {
+            PortableMoveMask(self.0 & other.0)
+        }
+
+        #[inline(always)]
+        fn or(self, other: PortableMoveMask) -> PortableMoveMask {
+            PortableMoveMask(self.0 | other.0)
+        }
+
+        #[inline(always)]
+        fn clear_least_significant_bit(self) -> PortableMoveMask {
+            PortableMoveMask(self.0 & (self.0 - 1))
+        }
+
+        #[inline(always)]
+        fn first_offset(self) -> usize {
+            self.0.trailing_zeros() as usize
+        }
+
+        #[inline(always)]
+        fn last_offset(self) -> usize {
+            64 - self.0.leading_zeros() as usize - 1
+        }
+    }
+
+    impl Vector for Simd<u8, 16> {
+        const BYTES: usize = 16;
+        const ALIGN: usize = <Self as Vector>::BYTES - 1;
+
+        type Mask = PortableMoveMask;
+
+        #[inline(always)]
+        unsafe fn splat(byte: u8) -> Simd<u8, 16> {
+            Simd::splat(byte)
+        }
+
+        #[inline(always)]
+        unsafe fn load_aligned(data: *const u8) -> Simd<u8, 16> {
+            data.cast::<Simd<u8, 16>>().read()
+        }
+
+        #[inline(always)]
+        unsafe fn load_unaligned(data: *const u8) -> Simd<u8, 16> {
+            data.cast::<Simd<u8, 16>>().read_unaligned()
+        }
+
+        #[inline(always)]
+        unsafe fn movemask(self) -> PortableMoveMask {
+            PortableMoveMask(self.simd_ge(Simd::splat(0x80)).to_bitmask())
+        }
+
+        #[inline(always)]
+        unsafe fn cmpeq(self, vector2: Self) -> Simd<u8, 16> {
+            self.simd_eq(vector2).select(Simd::splat(0xFF), Simd::splat(0x00))
+        }
+
+        #[inline(always)]
+        unsafe fn and(self, vector2: Self) -> Simd<u8, 16> {
+            self & vector2
+        }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> Simd<u8, 16> {
+            self | vector2
+        }
+
+        #[inline(always)]
+        unsafe fn movemask_will_have_non_zero(self) -> bool {
+            self.simd_ge(Simd::splat(0x80)).any()
+        }
+    }
+
+    impl SimdVector for Simd<u8, 16> {
+        const BYTES: usize = 16;
+
+        #[inline(always)]
+        unsafe fn splat(byte: u8) -> Simd<u8, 16> {
+            <Simd<u8, 16> as Vector>::splat(byte)
+        }
+
+        #[inline(always)]
+        unsafe fn load_unaligned(data: *const u8) -> Simd<u8, 16> {
+            <Simd<u8, 16> as Vector>::load_unaligned(data)
+        }
+
+        #[inline(always)]
+        unsafe fn cmpeq(self, vector2: Self) -> Simd<u8, 16> {
+            Vector::cmpeq(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn and(self, vector2: Self) -> Simd<u8, 16> {
+            Vector::and(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> Simd<u8, 16> {
+            Vector::or(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn movemask(self) -> u64 {
+            Vector::movemask(self).0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    /// A kernel that matches any one of up to three needle bytes.
+    struct AnyOf<V> {
+        needles: Vec<u8>,
+        vectors: Vec<V>,
+    }
+
+    impl<V: SimdVector> AnyOf<V> {
+        unsafe fn new(needles: &[u8]) -> AnyOf<V> {
+            let vectors = needles.iter().map(|&b| V::splat(b)).collect();
+            AnyOf { needles: needles.to_vec(), vectors }
+        }
+    }
+
+    impl<V: SimdVector> Kernel<V> for AnyOf<V> {
+        unsafe fn find_in_vector(&self, chunk: V) -> V {
+            let mut eq = V::splat(0).cmpeq(V::splat(1));
+            for &v in self.vectors.iter() {
+                eq = eq.or(v.cmpeq(chunk));
+            }
+            eq
+        }
+
+        fn is_match(&self, byte: u8) -> bool {
+            self.needles.contains(&byte)
+        }
+    }
+
+    /// Runs the memchr test suite on `find` using the vector type given.
+    ///
+    /// # Safety
+    ///
+    /// Callers must ensure that the vector operations of `V` are supported.
+    unsafe fn forward<V: SimdVector>() {
+        for len in 1..=3 {
+            crate::tests::memchr::Runner::new(len).forward_iter(
+                |haystack, needles| {
+                    let kernel = AnyOf::<V>::new(needles);
+                    let mut found = Vec::new();
+                    let mut at = 0;
+                    while let Some(i) = find(&kernel, &haystack[at..]) {
+                        found.push(at + i);
+                        at += i + 1;
+                    }
+                    Some(found)
+                },
+            )
+        }
+    }
+
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    #[test]
+    fn forward_sse2() {
+        // SAFETY: SSE2 is enabled at compile time.
+        unsafe { forward::<core::arch::x86_64::__m128i>() }
+    }
+
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    #[test]
+    fn forward_avx2() {
+        if !std::is_x86_feature_detected!("avx2") {
+            return;
+        }
+        // SAFETY: We checked that AVX2 is available above.
+        unsafe { forward::<core::arch::x86_64::__m256i>() }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[test]
+    fn forward_neon() {
+        // SAFETY: neon is always available on aarch64.
+        unsafe { forward::<core::arch::aarch64::uint8x16_t>() }
+    }
+
+    #[cfg(feature = "portable-simd")]
+    #[test]
+    fn forward_portable() {
+        // SAFETY: Vectors from `core::simd` are always available.
+        unsafe { forward::<core::simd::Simd<u8, 16>>() }
+    }
+
+    #[test]
+    fn short_and_empty() {
+        /// A "vector" that is a single byte wide.
+        #[derive(Clone, Copy, Debug)]
+        struct Byte(u8);
+
+        impl SimdVector for Byte {
+            const BYTES: usize = 1;
+
+            unsafe fn splat(byte: u8) -> Byte {
+                Byte(byte)
+            }
+            unsafe fn load_unaligned(data: *const u8) -> Byte {
+                Byte(*data)
+            }
+            unsafe fn cmpeq(self, vector2: Byte) -> Byte {
+                Byte(if self.0 == vector2.0 { 0xFF } else { 0x00 })
+            }
+            unsafe fn and(self, vector2: Byte) -> Byte {
+                Byte(self.0 & vector2.0)
+            }
+            unsafe fn or(self, vector2: Byte) -> Byte {
+                Byte(self.0 | vector2.0)
+            }
+            unsafe fn movemask(self) -> u64 {
+                u64::from(self.0 >> 7)
+            }
+        }
+
+        // SAFETY: Our `Byte` vector type is always supported.
+        unsafe {
+            let kernel = AnyOf::<Byte>::new(b"z");
+            assert_eq!(None, find(&kernel, b""));
+            assert_eq!(Some(0), find(&kernel, b"z"));
+            assert_eq!(Some(25), find(&kernel, b"abcdefghijklmnopqrstuvwxyz"));
+            assert_eq!(None, find(&kernel, b"abcdefghijklmnopqrstuvwxy"));
+        }
+        // SAFETY: The vector operations of `__m128i` are always supported
+        // when SSE2 is enabled at compile time.
+        #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+        unsafe {
+            let kernel = AnyOf::<core::arch::x86_64::__m128i>::new(b"z");
+            assert_eq!(None, find(&kernel, b""));
+            assert_eq!(Some(2), find(&kernel, b"xyz"));
+        }
+    }
 }